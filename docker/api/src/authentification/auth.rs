@@ -2,7 +2,7 @@ use sqlx::{mysql, MySqlPool};
 use jsonwebtoken::{encode, decode, Header, Algorithm, Validation, EncodingKey, DecodingKey, get_current_timestamp};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use openssl::{kdf, rand::rand_bytes, symm::{Cipher, Crypter, Mode}};
+use openssl::{kdf, memcmp, rand::rand_bytes, symm::{Cipher, Crypter, Mode}};
 use std::env;
 use jsonwebtoken::errors::ErrorKind;
 use passcheck::PasswordChecker;
@@ -10,6 +10,8 @@ use openssh::{Session, KnownHosts};
 use std::sync::Arc;
 use openssh_sftp_client::{Sftp, SftpOptions};
 use crate::{borg_script::create_user, error::APIError};
+use super::key_lease::{KeyLease, KeyLeases};
+use super::repository::Repository;
 
 // argon2id paramètres
 const MEMORY_COST: u32 = 64*1024;
@@ -47,15 +49,23 @@ pub enum BearerState{
 #[derive(sqlx::FromRow)]
 struct MysqlCredentials{
     id: String,
-    encrypt_master_key_1: String,
     encrypt_master_key_2: String
 }
 
+/// Clés borg d'un dépôt chiffrées en AES-256-GCM (hex), pour un utilisateur ou une invitation
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WrappedKeys{
+    pub encrypt_master_key_1: String,
+    pub encrypt_master_key_2: String
+}
+
 #[derive(Clone)]
 pub struct Auth{
-    db: MySqlPool,
+    pub db: MySqlPool,
     pub ssh_connexion: Arc<Session>,
-    pub sftp_connexion: Arc<Sftp>
+    pub sftp_connexion: Arc<Sftp>,
+    pub(super) key_leases: Arc<KeyLeases>,
+    scheduler_kdf: [u8; 32]
 }
 
 impl Auth {
@@ -70,6 +80,10 @@ impl Auth {
         })
         .username(&env::var("DB_USER").expect("DB_USER inexistant"))
         .database(&env::var("DB").expect("DB inexistant"));
+        // Sans ce secret, les clés confiées au planificateur de rétention ne peuvent pas être chiffrées
+        let scheduler_secret = env::var("SCHEDULER_KEY_SECRET").ok()
+        .filter(|secret| ! secret.is_empty())
+        .expect("SCHEDULER_KEY_SECRET inexistant");
         let session_ssh = Session::connect_mux("ssh://borg", KnownHosts::Add)
         .await.expect("Impossible de se connecter au serveur ssh");
         let session_sftp = Session::connect_mux("ssh://borg", KnownHosts::Add)
@@ -77,7 +91,9 @@ impl Auth {
         Self{
             db: MySqlPool::connect_with(opt).await.expect("Impossible de se connecter à la DB"),
             ssh_connexion: Arc::new(session_ssh),
-            sftp_connexion: Arc::new(Sftp::from_session(session_sftp, SftpOptions::default()).await.expect("test"))
+            sftp_connexion: Arc::new(Sftp::from_session(session_sftp, SftpOptions::default()).await.expect("test")),
+            key_leases: Arc::new(KeyLeases::default()),
+            scheduler_kdf: Auth::scheduler_kdf(&scheduler_secret)
         }
     }
     pub async fn signup(&self, login: Login) -> Result<String, APIError> {
@@ -125,10 +141,10 @@ impl Auth {
         ).await?;
 
         // chiffrement de la clé borg 1
-        let key_1_encrypted: String = self.encrypt_key(&kdf_client, master_key_1_encrypted)?;
+        let key_1_encrypted: String = Auth::encrypt_key(&kdf_client, master_key_1_encrypted)?;
 
         // chiffrement de la clé borg 2
-        let key_2_encrypted: String = self.encrypt_key(&kdf_client, master_key_2.as_bytes().to_vec())?;
+        let key_2_encrypted: String = Auth::encrypt_key(&kdf_client, master_key_2.as_bytes().to_vec())?;
 
         if key_1_encrypted.len()>1200{
             println!("Erreur longueur de clé borg 1 encrypted signup: {}", key_1_encrypted.len());
@@ -160,7 +176,7 @@ impl Auth {
         };
     }
 
-    fn encrypt_key(&self, kdf_client:&[u8], master_key: Vec<u8>)->Result<String, APIError>{
+    fn encrypt_key(kdf_client:&[u8], master_key: Vec<u8>)->Result<String, APIError>{
         /*Chiffrement clé_master_2 */
         const GCM_TAG_LEN: usize = 16;
         let mut iv = [0u8; 12];
@@ -220,7 +236,7 @@ impl Auth {
     pub async fn signin(&self, login:Login) -> Result<String, APIError>{
         /* Récupération clé master 2 */
         let mut conn = self.db.acquire().await.expect("Impossible d'acquerir une connection DB");
-        let query = sqlx::query_as("SELECT id, encrypt_master_key_1, \
+        let query = sqlx::query_as("SELECT id, \
         encrypt_master_key_2 FROM Credentials WHERE username=?").bind(login.username.as_str());
         let result: Vec<MysqlCredentials> = query.fetch_all(&mut *conn).await.expect("Une erreur c'est produite");
 
@@ -295,9 +311,26 @@ impl Auth {
         return username_for_encryption;
     }
 
-    pub async fn restore_master_key_file(&self, credentials: &Credentials)-> Result<(),APIError>{
+    /// Bail sur la clé du dépôt de l'utilisateur
+    pub async fn lease_master_key(&self, credentials: &Credentials)-> Result<KeyLease,APIError>{
+        self.lease_repository_key(credentials, &Repository::own(credentials)).await
+    }
+
+    /// Bail sur la clé du dépôt, déchiffrée avec le kdf du membre s'il s'agit du dépôt d'une organisation
+    pub async fn lease_repository_key(&self, credentials: &Credentials, repository: &Repository)-> Result<KeyLease,APIError>{
+        let keys = self.repository_keys(repository).await?;
+        KeyLease::acquire(self, &repository.id, &keys.encrypt_master_key_2, &credentials.kdf).await
+    }
+
+    /// Bail sur la clé 2 chiffrée avec un autre secret que le kdf de la session (hex)
+    pub async fn lease_key(&self, repository_id: &String, encrypt_master_key_2: &String, kdf: &String)-> Result<KeyLease,APIError>{
+        KeyLease::acquire(self, repository_id, encrypt_master_key_2, kdf).await
+    }
+
+    /// Restaure la clé 2 du dépôt chiffrée avec kdf (hex), uniquement à travers un KeyLease
+    pub(super) async fn restore_key_file(&self, repository_id: &String, encrypt_master_key_2: &String, kdf: &String)-> Result<(),APIError>{
         let filename = format!("{}/{}/.config/borg/keys/srv_repos_{}_repo", 
-        CLIENT_DIRECTORY, credentials.id, credentials.id);
+        CLIENT_DIRECTORY, repository_id, repository_id);
         println!(" Restauration de la clé {}", filename);
 
         //Vérification de la présence de la clé
@@ -326,7 +359,7 @@ impl Auth {
             return Ok(())//Err(APIError::Script)
         }
         // Déchiffrement de la clé Borg
-        let master_key_2 =self.decrypt_master_2_key(&credentials).await?;
+        let master_key_2 = Auth::decrypt_master_key(encrypt_master_key_2, kdf)?;
         // Création du fichier de la clé Borg
         let mut key_borg = match self.sftp_connexion.create(&filename).await {
             Ok(f)=>f,
@@ -343,24 +376,79 @@ impl Auth {
         };
     }
 
-    pub async fn decrypt_master_1_key(&self, credentials: &Credentials)-> Result<Vec<u8>,APIError>{ 
-        /* Récupération clé master 2 */
-        let mut conn = self.db.acquire().await.expect("Impossible d'acquerir une connection DB");
-        let query = sqlx::query_as("SELECT id, encrypt_master_key_1, \
-        encrypt_master_key_2  FROM Credentials WHERE id=?").bind(credentials.id.as_str());
-        let result: Vec<MysqlCredentials> = query.fetch_all(&mut *conn).await.expect("Une erreur c'est produite");
-        if result.len() != 1 {
-            println!("L'utilisateur {} n'est pas connue dans la base de données", credentials.id);
-            return Err(APIError::NotSignup);
+    /// Clé 1 du dépôt de l'utilisateur ou de son organisation, déchiffrée avec son kdf
+    pub async fn decrypt_repository_key_1(&self, credentials: &Credentials, repository: &Repository)-> Result<Vec<u8>,APIError>{
+        let keys = self.repository_keys(repository).await?;
+        Auth::decrypt_master_key(&keys.encrypt_master_key_1, &credentials.kdf)
+    }
+
+    /// Clés du dépôt chiffrées pour l'utilisateur : dans Credentials pour son propre dépôt, dans OrganisationMembers sinon
+    pub async fn repository_keys(&self, repository: &Repository)-> Result<WrappedKeys, APIError>{
+        if let Some(keys) = &repository.member_keys{
+            return Ok(keys.clone())
         }
-        // Déchiffrement de la clé
-        return Ok(Auth::decrypt_master_key(&result[0].encrypt_master_key_1,&credentials.kdf)?)
+        let keys: Option<WrappedKeys> = match sqlx::query_as("SELECT encrypt_master_key_1, encrypt_master_key_2 FROM Credentials WHERE id=?")
+        .bind(repository.id.as_str())
+        .fetch_optional(&self.db).await{
+            Ok(keys)=>keys,
+            Err(e)=>{
+                println!("Erreur lors de la récupération des clés du dépôt {} : {}", repository.id, e);
+                return Err(APIError::Database)
+            }
+        };
+        keys.ok_or(APIError::NotSignup)
+    }
+
+    /// Déchiffre les clés avec from_kdf (hex) et les chiffre à nouveau avec to_kdf : partage du dépôt par invitation
+    pub fn rewrap_keys(&self, keys: &WrappedKeys, from_kdf: &String, to_kdf: &[u8])-> Result<WrappedKeys, APIError>{
+        Ok(WrappedKeys{
+            encrypt_master_key_1: self.rewrap_key(&keys.encrypt_master_key_1, from_kdf, to_kdf)?,
+            encrypt_master_key_2: self.rewrap_key(&keys.encrypt_master_key_2, from_kdf, to_kdf)?
+        })
+    }
+
+    /// Une seule clé, la clé 2 suffit pour restaurer par un lien de partage
+    pub fn rewrap_key(&self, key: &String, from_kdf: &String, to_kdf: &[u8])-> Result<String, APIError>{
+        Auth::rewrap(key, from_kdf, to_kdf)
+    }
+
+    fn rewrap(key: &String, from_kdf: &String, to_kdf: &[u8])-> Result<String, APIError>{
+        let master_key = Auth::decrypt_master_key(key, from_kdf)?;
+        let wrapped = Auth::encrypt_key(to_kdf, master_key)?;
+        if wrapped.len()>1200{
+            println!("Erreur longueur de clé borg encrypted rewrap_key : {}", wrapped.len());
+            return Err(APIError::KDFError)
+        }
+        Ok(wrapped)
+    }
+
+    /// Clé qui chiffre les clés confiées au planificateur, dérivée de SCHEDULER_KEY_SECRET
+    fn scheduler_kdf(scheduler_secret: &str)->[u8; 32]{
+        let mut hasher = openssl::sha::Sha256::new();
+        hasher.update(b"strongholder-scheduler:");
+        hasher.update(scheduler_secret.as_bytes());
+        hasher.finish()
+    }
+
+    /// Clé 2 du dépôt chiffrée pour le planificateur, qui applique la rétention sans session
+    pub async fn scheduler_key(&self, credentials: &Credentials, repository: &Repository)-> Result<String, APIError>{
+        let keys = self.repository_keys(repository).await?;
+        self.rewrap_key(&keys.encrypt_master_key_2, &credentials.kdf, &self.scheduler_kdf)
+    }
+
+    /// Bail sur la clé confiée au planificateur.
+    /// KDFError si elle ne se déchiffre plus, par exemple après un changement de SCHEDULER_KEY_SECRET
+    pub async fn lease_scheduler_key(&self, repository_id: &String, encrypt_master_key_2: &String)-> Result<KeyLease,APIError>{
+        let kdf = hex::encode(self.scheduler_kdf);
+        // Si un autre bail est en cours, la clé n'est pas déchiffrée par KeyLease : vérification ici
+        Auth::decrypt_master_key(encrypt_master_key_2, &kdf)?;
+        self.lease_key(repository_id, encrypt_master_key_2, &kdf).await
     }
 
     async fn decrypt_master_2_key(&self, credentials: &Credentials)-> Result<Vec<u8>,APIError>{  
         /* Récupération clé master 2 */
         let mut conn = self.db.acquire().await.expect("Impossible d'acquerir une connection DB");
-        let query = sqlx::query_as("SELECT id, encrypt_master_key_1, \
+        let query = sqlx::query_as("SELECT id, \
         encrypt_master_key_2 FROM Credentials WHERE id=?").bind(credentials.id.as_str());
         let result: Vec<MysqlCredentials> = query.fetch_all(&mut *conn).await.expect("Une erreur c'est produite");
         
@@ -415,7 +503,8 @@ impl Auth {
 
     }
 
-    pub async fn delete_master_key_file(&self, uuid: &String)->Result<(), APIError>{
+    /// Uniquement à travers un KeyLease, d'autres travaux peuvent utiliser la clé
+    pub(super) async fn delete_master_key_file(&self, uuid: &String)->Result<(), APIError>{
         let filename = format!("{}/{}/.config/borg/keys/srv_repos_{}_repo", CLIENT_DIRECTORY, uuid, uuid);
         println!("Supression de la clé{}", filename);
         let output = match self.ssh_connexion.command("shred").args(["-u", &filename]).output().await{
@@ -446,6 +535,33 @@ impl Auth {
         return Ok(())
     }
 
+    /// Ré-authentification avant une action sensible : le mot de passe doit redonner le kdf du token
+    pub async fn verify_password(&self, credentials: &Credentials, password: &str)-> Result<(), APIError>{
+        let username: Option<(String,)> = match sqlx::query_as("SELECT username FROM Credentials WHERE id=?")
+        .bind(credentials.id.as_str())
+        .fetch_optional(&self.db).await{
+            Ok(username)=>username,
+            Err(e)=>{
+                println!("Erreur lors de la récupération du nom d'utilisateur : {}", e);
+                return Err(APIError::Database)
+            }
+        };
+        let Some((username,)) = username else{
+            return Err(APIError::NotSignup)
+        };
+        let login = Login{username, password: password.to_string()};
+        let username_for_encryption = Auth::corrrect_username_length(&login);
+        let kdf_client = self.create_kdf(&login.password, &username_for_encryption).await?;
+        let Ok(kdf_token) = hex::decode(&credentials.kdf) else{
+            return Err(APIError::KDFError)
+        };
+        if kdf_token.len() != kdf_client.len() || ! memcmp::eq(&kdf_token, &kdf_client){
+            println!("Mot de passe incorrect pour {}", credentials.id);
+            return Err(APIError::WrongPassword)
+        }
+        Ok(())
+    }
+
     pub fn decode_token(token_jwt: &str) -> Result<Credentials, APIError>{
         let jwt_secret=env::var("JWT_SECRET").expect("JWT_SECRET inexistant");
         let validation = Validation::new(Algorithm::HS384);
@@ -522,3 +638,33 @@ impl Auth {
         };
     }
 }
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn rewrap_changes_the_wrapping_key(){
+        let founder = [1u8; 32];
+        let invitation = [2u8; 32];
+        let wrapped = Auth::encrypt_key(&founder, b"borg key".to_vec()).unwrap();
+        let rewrapped = Auth::rewrap(&wrapped, &hex::encode(founder), &invitation).unwrap();
+        assert_eq!(Auth::decrypt_master_key(&rewrapped, &hex::encode(invitation)).unwrap(), b"borg key");
+        assert!(Auth::decrypt_master_key(&rewrapped, &hex::encode(founder)).is_err());
+    }
+
+    #[test]
+    fn scheduler_key_is_unreadable_after_a_secret_change(){
+        let founder = [1u8; 32];
+        let wrapped = Auth::encrypt_key(&founder, b"borg key".to_vec()).unwrap();
+        let escrowed = Auth::rewrap(&wrapped, &hex::encode(founder), &Auth::scheduler_kdf("old secret")).unwrap();
+        assert_eq!(Auth::decrypt_master_key(&escrowed, &hex::encode(Auth::scheduler_kdf("old secret"))).unwrap(), b"borg key");
+        assert_eq!(Auth::decrypt_master_key(&escrowed, &hex::encode(Auth::scheduler_kdf("new secret"))), Err(APIError::KDFError));
+    }
+
+    #[test]
+    fn rewrap_refuses_a_wrong_kdf(){
+        let wrapped = Auth::encrypt_key(&[1u8; 32], b"borg key".to_vec()).unwrap();
+        assert!(Auth::rewrap(&wrapped, &hex::encode([3u8; 32]), &[2u8; 32]).is_err());
+    }
+}
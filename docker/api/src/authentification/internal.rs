@@ -0,0 +1,21 @@
+use actix_web::HttpRequest;
+use openssl::memcmp;
+use std::env;
+
+/// Jeton partagé avec le docker borg (INTERNAL_API_TOKEN) pour les routes /internal, sans lui elles refusent tout
+pub fn valid_internal_token(req: &HttpRequest)->bool{
+    let Some(token) = env::var("INTERNAL_API_TOKEN").ok().filter(|token| !token.is_empty()) else{
+        return false
+    };
+    let Some(given) = req.headers().get("Authorization")
+    .and_then(|header| header.to_str().ok())
+    .and_then(|header| header.strip_prefix("Bearer ")) else{
+        return false
+    };
+    given.len() == token.len() && memcmp::eq(given.as_bytes(), token.as_bytes())
+}
+
+/// Id d'un client tel que créé au signup (uuid simple), c'est aussi son utilisateur sur le docker borg
+pub fn valid_user_id(user: &str)->bool{
+    user.len() == 32 && user.bytes().all(|c| c.is_ascii_hexdigit())
+}
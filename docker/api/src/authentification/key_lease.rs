@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::authentification::auth::Auth;
+use crate::error::APIError;
+
+/// Nombre de travaux qui utilisent la clé en clair de chaque dépôt (srv_repos_<id>_repo).
+/// Le premier bail restaure la clé et le dernier la supprime : une tâche qui se termine
+/// ne supprime plus la clé sous une commande borg encore en cours.
+#[derive(Default)]
+pub struct KeyLeases{
+    repositories: Mutex<HashMap<String, Arc<tokio::sync::Mutex<u32>>>>
+}
+
+impl KeyLeases{
+    fn counter(&self, repository_id: &str)->Arc<tokio::sync::Mutex<u32>>{
+        let mut repositories = self.repositories.lock().unwrap_or_else(|e| e.into_inner());
+        repositories.entry(repository_id.to_string()).or_default().clone()
+    }
+}
+
+/// Clé d'un dépôt présente tant que le bail existe.
+/// release rend le bail, un bail abandonné (erreur avec ?) est rendu en tâche de fond.
+pub struct KeyLease{
+    auth: Auth,
+    repository_id: String,
+    released: bool
+}
+
+impl KeyLease{
+    /// Restaure la clé 2 chiffrée avec kdf (hex) si aucun autre bail n'est en cours sur le dépôt
+    pub(super) async fn acquire(auth: &Auth, repository_id: &String, encrypt_master_key_2: &String, kdf: &String)->Result<KeyLease, APIError>{
+        let counter = auth.key_leases.counter(repository_id);
+        let mut count = counter.lock().await;
+        if *count == 0{
+            auth.restore_key_file(repository_id, encrypt_master_key_2, kdf).await?;
+        }
+        *count += 1;
+        Ok(KeyLease{
+            auth: auth.clone(),
+            repository_id: repository_id.clone(),
+            released: false
+        })
+    }
+
+    pub async fn release(mut self)->Result<(), APIError>{
+        self.released = true;
+        release(&self.auth, &self.repository_id).await
+    }
+}
+
+async fn release(auth: &Auth, repository_id: &String)->Result<(), APIError>{
+    let counter = auth.key_leases.counter(repository_id);
+    let mut count = counter.lock().await;
+    *count = count.saturating_sub(1);
+    if *count == 0{
+        auth.delete_master_key_file(repository_id).await?;
+    }
+    Ok(())
+}
+
+impl Drop for KeyLease{
+    fn drop(&mut self){
+        if self.released{
+            return
+        }
+        let auth = self.auth.clone();
+        let repository_id = std::mem::take(&mut self.repository_id);
+        actix_web::rt::spawn(async move {
+            if let Err(e) = release(&auth, &repository_id).await{
+                println!("Erreur lors de la libération de la clé du dépôt {} : {}", repository_id, e);
+            }
+        });
+    }
+}
@@ -1,5 +1,5 @@
 use actix_web::{Error, HttpResponse, ResponseError, body::BoxBody, cookie::Cookie, dev::{ServiceRequest, ServiceResponse}, middleware::Next, web};
-use crate::{authentification::auth::{Auth, BearerState}, error::APIError};
+use crate::{authentification::auth::{Auth, BearerState}, error::APIError, tasks};
 
 pub async fn authentification_middleware(
     req: ServiceRequest,
@@ -10,6 +10,10 @@ pub async fn authentification_middleware(
     if path == "/api/signin".to_string() || path == "/api/signup".to_string() {
         return Ok(next.call(req).await?.map_into_boxed_body())
     }
+    // Les liens de partage sont authentifiés par leur jeton signé, posté par la page /api/share
+    if path == "/api/share" {
+        return Ok(next.call(req).await?.map_into_boxed_body())
+    }
 
     // Récupération de auth
     let Some(auth) = req.app_data::<web::Data<Auth>>() else{
@@ -22,13 +26,18 @@ pub async fn authentification_middleware(
     };
 
     // Vérification de l'authentification
-    let (bearer_state, (result, _)) = match auth.validation(cookie.value().to_string()){
+    let (bearer_state, (result, credentials)) = match auth.validation(cookie.value().to_string()){
         Ok(res)=>res,
         Err(e)=>{
                 return Ok(req.into_response(e.error_response())) 
         }
     };
     
+    // Une session active donne accès à la clé du dépôt : analyse des nouvelles archives et rétention
+    if bearer_state == BearerState::Refresh{
+        tasks::session::spawn(auth.get_ref().clone(), credentials);
+    }
+
     // Lancement du service
     let mut res = next.call(req).await?;
 
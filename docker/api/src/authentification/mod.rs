@@ -1,2 +1,5 @@
 pub mod auth;
-pub mod middleware_auth;
\ No newline at end of file
+pub mod middleware_auth;
+pub mod internal;
+pub mod repository;
+pub mod key_lease;
\ No newline at end of file
@@ -0,0 +1,129 @@
+use actix_web::{web, HttpRequest};
+use serde::Deserialize;
+use crate::authentification::auth::{Auth, Credentials, WrappedKeys};
+use crate::database::organisations::{membership_by_organisation, membership_by_repository, shared_memberships, Membership, Role};
+use crate::error::APIError;
+
+/// Paramètre d'URL commun aux routes du dépôt : ?repository=<id> vise le dépôt partagé d'une organisation
+#[derive(Deserialize)]
+struct RepositoryQuery{
+    repository: Option<String>
+}
+
+/// Dépôt borg sur lequel agit une requête, /srv/repos/<id>
+#[derive(Debug)]
+pub struct Repository{
+    /// Id du dépôt et de l'utilisateur borg, celui du fondateur pour une organisation
+    pub id: String,
+    /// Clés du dépôt chiffrées avec le kdf du membre, None quand les clés sont dans Credentials
+    pub member_keys: Option<WrappedKeys>
+}
+
+impl Repository{
+    /// Dépôt personnel de l'utilisateur connecté
+    pub fn own(credentials: &Credentials)->Repository{
+        Repository{
+            id: credentials.id.clone(),
+            member_keys: None
+        }
+    }
+}
+
+/// Dépôt visé par la requête : celui de l'utilisateur sans paramètre repository, sinon le dépôt partagé d'une de ses organisations.
+/// Erreur Forbidden si l'utilisateur n'en est pas membre ou si son rôle est inférieur à required.
+pub async fn resolve_repository(auth: &Auth, req: &HttpRequest, credentials: &Credentials, required: Role)->Result<Repository, APIError>{
+    let Ok(query) = web::Query::<RepositoryQuery>::from_query(req.query_string()) else{
+        return Err(APIError::ValidInput)
+    };
+    let Some(repository_id) = &query.repository else{
+        return Ok(Repository::own(credentials))
+    };
+    if *repository_id == credentials.id{
+        return Ok(Repository::own(credentials))
+    }
+    let membership = membership_by_repository(&auth.db, repository_id, &credentials.id).await?;
+    member_repository(credentials, membership, required)
+}
+
+/// Dépôt partagé par l'organisation, pour les routes /organisations/{id}
+pub async fn resolve_organisation(auth: &Auth, credentials: &Credentials, organisation_id: u64, required: Role)->Result<Repository, APIError>{
+    let membership = membership_by_organisation(&auth.db, organisation_id, &credentials.id).await?;
+    member_repository(credentials, membership, required)
+}
+
+/// Dépôts partagés dont l'utilisateur est membre, l'appartenance est relue à chaque connexion
+pub async fn shared_repositories(auth: &Auth, credentials: &Credentials)->Result<Vec<Repository>, APIError>{
+    let memberships = shared_memberships(&auth.db, &credentials.id).await?;
+    Ok(memberships.into_iter()
+    .filter_map(|membership| member_repository(credentials, Some(membership), Role::Viewer).ok())
+    .collect())
+}
+
+fn member_repository(credentials: &Credentials, membership: Option<Membership>, required: Role)->Result<Repository, APIError>{
+    let Some(membership) = membership else{
+        println!("Dépôt partagé refusé, l'utilisateur n'est pas membre : {}", credentials.id);
+        return Err(APIError::Forbidden)
+    };
+    let role = Role::parse(&membership.role).ok_or(APIError::Forbidden)?;
+    if role < required{
+        println!("Rôle {} insuffisant ({} requis) pour l'utilisateur : {}", role.as_str(), required.as_str(), credentials.id);
+        return Err(APIError::Forbidden)
+    }
+    // Le fondateur déchiffre le dépôt avec ses propres clés
+    let member_keys = match (membership.encrypt_master_key_1, membership.encrypt_master_key_2){
+        (Some(encrypt_master_key_1), Some(encrypt_master_key_2))=>Some(WrappedKeys{encrypt_master_key_1, encrypt_master_key_2}),
+        _ if membership.repository_id == credentials.id=>None,
+        _=>return Err(APIError::Forbidden)
+    };
+    Ok(Repository{
+        id: membership.repository_id,
+        member_keys
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn credentials()->Credentials{
+        Credentials{exp: 0, id: String::from("member"), kdf: String::new()}
+    }
+
+    fn membership(repository_id: &str, role: &str, keys: bool)->Option<Membership>{
+        Some(Membership{
+            repository_id: repository_id.to_string(),
+            role: role.to_string(),
+            encrypt_master_key_1: keys.then(|| String::from("key1")),
+            encrypt_master_key_2: keys.then(|| String::from("key2"))
+        })
+    }
+
+    #[test]
+    fn member_gets_its_own_keys(){
+        let repository = member_repository(&credentials(), membership("founder", "restorer", true), Role::Restorer).unwrap();
+        assert_eq!(repository.id, "founder");
+        let keys = repository.member_keys.unwrap();
+        assert_eq!((keys.encrypt_master_key_1.as_str(), keys.encrypt_master_key_2.as_str()), ("key1", "key2"));
+    }
+
+    #[test]
+    fn founder_uses_the_session_keys(){
+        let repository = member_repository(&credentials(), membership("member", "owner", false), Role::Owner).unwrap();
+        assert_eq!(repository.id, "member");
+        assert!(repository.member_keys.is_none());
+    }
+
+    #[test]
+    fn refuses_a_lower_role(){
+        assert!(matches!(member_repository(&credentials(), membership("founder", "viewer", true), Role::Restorer), Err(APIError::Forbidden)));
+        assert!(matches!(member_repository(&credentials(), membership("founder", "restorer", true), Role::Owner), Err(APIError::Forbidden)));
+    }
+
+    #[test]
+    fn refuses_non_members(){
+        assert!(matches!(member_repository(&credentials(), None, Role::Viewer), Err(APIError::Forbidden)));
+        assert!(matches!(member_repository(&credentials(), membership("founder", "admin", true), Role::Viewer), Err(APIError::Forbidden)));
+        // Sans clés, seul le fondateur accède au dépôt
+        assert!(matches!(member_repository(&credentials(), membership("founder", "owner", false), Role::Viewer), Err(APIError::Forbidden)));
+    }
+}
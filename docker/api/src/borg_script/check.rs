@@ -0,0 +1,57 @@
+use openssh::Session;
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/check.sh";
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckMode{
+    /// borg check --repository-only, possible sans la clé
+    #[default]
+    Repository,
+    /// borg check --verify-data, déchiffre toutes les données
+    VerifyData
+}
+
+impl CheckMode {
+    pub fn as_str(&self)->&'static str{
+        match self {
+            CheckMode::Repository=>"repository",
+            CheckMode::VerifyData=>"verify_data"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckOutcome{
+    Healthy,
+    /// borg check a trouvé des erreurs d'intégrité
+    Corrupted,
+    /// Vérification impossible (dépôt verrouillé par une sauvegarde, clé absente)
+    NotRun,
+    /// borg check a échoué sans se prononcer sur le dépôt
+    Failed
+}
+
+/// Lance borg check et renvoie le résultat avec le rapport de borg
+pub async fn check_repository(uuid: &String, mode: CheckMode, ssh_connexion: Arc<Session>)->Result<(CheckOutcome, String), APIError>{
+    println!("Vérification {} du dépôt pour le client : {}", mode.as_str(), uuid);
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, uuid, mode.as_str()]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    // Le rapport peut contenir des chemins non UTF-8, il n'est qu'affiché
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let outcome = match output.status.code(){
+        Some(0)=>CheckOutcome::Healthy,
+        Some(10)=>CheckOutcome::Corrupted,
+        Some(11)=>CheckOutcome::NotRun,
+        Some(12)=>CheckOutcome::Failed,
+        code=>{
+            println!("Erreur du script check code {:?}\nstdout {}", code, &stdout);
+            return Err(APIError::Script)
+        }
+    };
+    Ok((outcome, stdout))
+}
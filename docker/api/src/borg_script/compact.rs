@@ -0,0 +1,55 @@
+use openssh::Session;
+use std::sync::Arc;
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/compact.sh";
+
+/// Compactage qui suit les suppressions de l'API (delete_archive.sh, prune.sh et compact.sh)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compaction{
+    Done,
+    /// Premier passage sur le dépôt : l'état de référence est enregistré, rien n'est compacté
+    Deferred,
+    /// Des archives ont disparu lors de transactions du client (append-only),
+    /// borg compact rendrait ces suppressions définitives
+    Refused(Vec<String>)
+}
+
+/// Codes de sortie 3 et 4 de compact.sh, repris par les scripts qui l'appellent
+pub(super) fn compaction(code: Option<i32>, stderr: &str)->Option<Compaction>{
+    match code{
+        Some(0)=>Some(Compaction::Done),
+        Some(3)=>Some(Compaction::Refused(stderr.lines()
+            .filter_map(|line| line.strip_prefix("client deleted: "))
+            .map(|archive| archive.trim().to_string())
+            .collect())),
+        Some(4)=>Some(Compaction::Deferred),
+        _=>None
+    }
+}
+
+/// Compacte le dépôt, y compris après des suppressions du client si accept_client_deletes
+pub async fn compact_repository(uuid: &String, accept_client_deletes: bool, ssh_connexion: Arc<Session>)->Result<Compaction, APIError>{
+    println!("Compactage du dépôt pour le client : {}", uuid);
+    let mut args = vec![SCRIPT, uuid.as_str()];
+    if accept_client_deletes{
+        args.push("--accept-client-deletes");
+    }
+    let output = match ssh_connexion.command("sudo").args(args).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stderr = match String::from_utf8(output.stderr.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stderr UTF8 compact");
+            return Err(APIError::UTF8)
+        }
+    };
+    match compaction(output.status.code(), &stderr){
+        Some(compaction)=>Ok(compaction),
+        None=>{
+            println!("Erreur lors du compactage\n stderr: {}", &stderr);
+            Err(APIError::Script)
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use openssh::Session;
+use std::sync::Arc;
+use crate::borg_script::compact::{compaction, Compaction};
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/delete_archive.sh";
+
+/// Supprime l'archive et son archive _logs puis compacte le dépôt.
+/// Renvoie les noms des archives supprimées et le résultat du compactage.
+pub async fn delete_archive(uuid: &String, archive: &str, ssh_connexion: Arc<Session>)->Result<(Vec<String>, Compaction), APIError>{
+    println!("Suppression de l'archive {} pour le client : {}", archive, uuid);
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, uuid, archive]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = match String::from_utf8(output.stdout.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stdout UTF8 delete_archive");
+            return Err(APIError::UTF8)
+        }
+    };
+    let stderr = match String::from_utf8(output.stderr.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stderr UTF8 delete_archive");
+            return Err(APIError::UTF8)
+        }
+    };
+    let Some(compaction) = compaction(output.status.code(), &stderr) else{
+        println!("Erreur lors de la suppression de l'archive\nstdout {}\n stderr: {}", &stdout, &stderr);
+        // code 2 : archive inexistante
+        if output.status.code() == Some(2){
+            return Err(APIError::NoFile)
+        }
+        return Err(APIError::Script)
+    };
+    Ok((stdout.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect(), compaction))
+}
@@ -0,0 +1,166 @@
+use openssh::Session;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::error::APIError;
+
+/*
+Sortie de borg diff --json-lines :
+{"path": "mnt/d/test.txt", "changes": [{"type": "modified", "added": 1024, "removed": 512}]}
+{"path": "mnt/d/new.txt", "changes": [{"type": "added", "size": 2048}]}
+{"path": "mnt/d/old", "changes": [{"type": "removed directory"}]}
+{"path": "mnt/d/script.sh", "changes": [{"type": "mode", "old_mode": "-rw-r--r--", "new_mode": "-rwxr-xr-x"}]}
+*/
+
+#[derive(Debug, Deserialize)]
+struct BorgDiffLine{
+    path: String,
+    changes: Vec<BorgDiffChange>
+}
+
+#[derive(Debug, Deserialize)]
+struct BorgDiffChange{
+    r#type: String,
+    size: Option<u64>,
+    added: Option<u64>,
+    removed: Option<u64>
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeType{
+    Added,
+    Removed,
+    Modified,
+    /// Seules les métadonnées ont changé (mode, propriétaire, dates)
+    Metadata
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffEntry{
+    pub path: String,
+    pub change: ChangeType,
+    /// Différence de taille en octets (négative si le fichier a diminué)
+    pub size_delta: i64,
+    /// Métadonnées modifiées (mode, owner, ctime, mtime...)
+    pub metadata: Vec<String>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveDiff{
+    pub archive_name_1: String,
+    pub archive_name_2: String,
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub metadata: usize,
+    pub size_delta: i64,
+    pub entries: Vec<DiffEntry>
+}
+
+pub async fn diff_archive(uuid: &String, ssh_connexion: Arc<Session>, archive_name_1: &String, archive_name_2: &String, path_prefix: Option<&str>)->Result<ArchiveDiff, APIError>{
+    println!("Diff entre {} et {} pour le client : {}", archive_name_1, archive_name_2, uuid);
+    let output = match ssh_connexion.command("sudo").args([String::from("/usr/local/sbin/diff.sh"), uuid.to_string(), archive_name_1.clone(), archive_name_2.clone()]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = match String::from_utf8(output.stdout.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stdout UTF8 diff_archive");
+            return Err(APIError::UTF8)
+        }
+    };
+    let stderr = match String::from_utf8(output.stderr.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stderr UTF8 diff_archive");
+            return Err(APIError::UTF8)
+        }
+    };
+    if ! output.status.success(){
+        println!("Erreur lors du diff entre {} et {}\nstdout {}\n stderr: {}", archive_name_1, archive_name_2, &stdout, &stderr);
+        return Err(APIError::Script)
+    }
+
+    // Les chemins borg ne commencent jamais par un /, le / final est ajouté par in_prefix
+    let path_prefix = path_prefix
+    .map(|prefix| prefix.trim_start_matches('/').trim_end_matches('/'))
+    .filter(|prefix| ! prefix.is_empty());
+    let mut diff = ArchiveDiff{
+        archive_name_1: archive_name_1.to_string(),
+        archive_name_2: archive_name_2.to_string(),
+        added: 0,
+        removed: 0,
+        modified: 0,
+        metadata: 0,
+        size_delta: 0,
+        entries: Vec::<DiffEntry>::new()
+    };
+    for line in stdout.split('\n') {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let diff_line: BorgDiffLine = match serde_json::from_str(line) {
+            Ok(d)=>d,
+            Err(_)=>{
+                println!("Erreur lors de la conversion string to BorgDiffLine diff_archive");
+                return Err(APIError::Json);
+            }
+        };
+        if let Some(prefix) = path_prefix && ! in_prefix(&diff_line.path, prefix){
+            continue;
+        }
+        let entry = parse_diff_line(diff_line);
+        match entry.change {
+            ChangeType::Added=>diff.added += 1,
+            ChangeType::Removed=>diff.removed += 1,
+            ChangeType::Modified=>diff.modified += 1,
+            ChangeType::Metadata=>diff.metadata += 1
+        }
+        diff.size_delta += entry.size_delta;
+        diff.entries.push(entry);
+    }
+    println!("Fin du diff pour le client {}", uuid);
+    Ok(diff)
+}
+
+/// Le chemin est le préfixe ou se trouve dessous : home/user ne retient pas home/username
+fn in_prefix(path: &str, prefix: &str)->bool{
+    path == prefix || path.starts_with(&format!("{prefix}/"))
+}
+
+fn parse_diff_line(diff_line: BorgDiffLine)->DiffEntry{
+    let mut change = ChangeType::Metadata;
+    let mut size_delta: i64 = 0;
+    let mut metadata = Vec::<String>::new();
+    for borg_change in diff_line.changes{
+        let size = borg_change.size.unwrap_or(0) as i64;
+        // "added", "added directory", "added link"... ont le même préfixe
+        if borg_change.r#type.starts_with("added"){
+            change = ChangeType::Added;
+            size_delta += size;
+        }else if borg_change.r#type.starts_with("removed"){
+            change = ChangeType::Removed;
+            size_delta -= size;
+        }else if borg_change.r#type == "modified" || borg_change.r#type == "changed link"{
+            change = ChangeType::Modified;
+            size_delta += borg_change.added.unwrap_or(0) as i64 - borg_change.removed.unwrap_or(0) as i64;
+        }else{
+            metadata.push(borg_change.r#type);
+        }
+    }
+    DiffEntry{path: diff_line.path, change, size_delta, metadata}
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn prefix_stops_at_path_boundary(){
+        assert!(in_prefix("home/user", "home/user"));
+        assert!(in_prefix("home/user/notes.txt", "home/user"));
+        assert!(! in_prefix("home/username/notes.txt", "home/user"));
+        assert!(! in_prefix("home", "home/user"));
+    }
+}
@@ -0,0 +1,53 @@
+use openssh::Session;
+use std::sync::Arc;
+use crate::database::ssh_keys::{BORG, TUNNEL};
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/import_authorized_keys.sh";
+
+/// Clé encore dans un fichier authorized_keys installé avant la table SshKeys
+#[derive(Debug)]
+pub struct LegacyKey{
+    pub purpose: &'static str,
+    pub user_id: String,
+    /// Type et base64, sans les options de la ligne
+    pub public_key: String
+}
+
+/// Une ligne par clé : "<usage> <client> <type> <base64>"
+pub async fn list_legacy_keys(ssh_connexion: Arc<Session>)->Result<Vec<LegacyKey>, APIError>{
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, "list"]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if ! output.status.success(){
+        println!("Erreur lors de la liste des clés authorized_keys\nstdout {}\n stderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+        return Err(APIError::Script)
+    }
+    Ok(stdout.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let purpose = match fields.next()?{
+            "borg"=>BORG,
+            "tunnel"=>TUNNEL,
+            _=>return None
+        };
+        let user_id = fields.next()?.to_string();
+        let public_key = format!("{} {}", fields.next()?, fields.next()?);
+        Some(LegacyKey{purpose, user_id, public_key})
+    }).collect())
+}
+
+/// Renomme les fichiers authorized_keys : sshd ne les lira plus après le redémarrage du docker borg
+pub async fn finish_import(ssh_connexion: Arc<Session>)->Result<(), APIError>{
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, "done"]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if ! output.status.success(){
+        println!("Erreur lors de la fin de l'import des clés\nstdout {}\n stderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+        return Err(APIError::Script)
+    }
+    print!("{}", stdout);
+    Ok(())
+}
@@ -0,0 +1,247 @@
+use openssh::Session;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/info.sh";
+/// Durée de validité des statistiques en cache (STATS_CACHE_SECONDS)
+const DEFAULT_CACHE_SECONDS: u64 = 300;
+
+/// borg info --all-archives est lent, le résultat est gardé quelques minutes par client
+static STATISTICS_CACHE: LazyLock<Mutex<HashMap<String, (Instant, RepositoryStatistics)>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/*
+Sortie de borg info --json sur le dépôt (extrait) :
+{
+    "cache": {
+        "stats": {
+            "total_chunks": 1024, "total_csize": 52428800, "total_size": 104857600,
+            "total_unique_chunks": 512, "unique_csize": 26214400, "unique_size": 52428800
+        }
+    }
+}
+*/
+
+#[derive(Debug, Deserialize)]
+struct BorgRepositoryInfo{
+    cache: BorgCache,
+    /// Présent seulement avec --all-archives
+    #[serde(default)]
+    archives: Vec<BorgArchiveInfo>
+}
+
+/*
+Une archive de borg info --json -a '*' (extrait) :
+{
+    "name": "2026-02-18_16-36-55", "start": "2026-02-18T16:36:55.000000", "end": "2026-02-18T16:37:10.000000",
+    "stats": {"compressed_size": 1048576, "deduplicated_size": 4096, "nfiles": 42, "original_size": 2097152}
+}
+*/
+#[derive(Debug, Deserialize)]
+struct BorgArchiveInfo{
+    name: String,
+    start: String,
+    end: String,
+    stats: BorgArchiveStats
+}
+
+#[derive(Debug, Deserialize)]
+struct BorgArchiveStats{
+    original_size: u64,
+    compressed_size: u64,
+    deduplicated_size: u64,
+    nfiles: u64
+}
+
+#[derive(Debug, Deserialize)]
+struct BorgCache{
+    stats: BorgCacheStats
+}
+
+#[derive(Debug, Deserialize)]
+struct BorgCacheStats{
+    total_size: u64,
+    total_csize: u64,
+    unique_csize: u64
+}
+
+/// Tailles du dépôt en octets
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct RepositoryStats{
+    /// Somme des tailles d'origine de toutes les archives
+    pub original_size: u64,
+    /// Somme des tailles compressées de toutes les archives
+    pub compressed_size: u64,
+    /// Place réellement occupée sur le disque après déduplication
+    pub deduplicated_size: u64
+}
+
+async fn borg_info(uuid: &String, archive: Option<&str>, ssh_connexion: Arc<Session>)->Result<String, APIError>{
+    let mut args = vec![SCRIPT, uuid.as_str()];
+    args.extend(archive);
+    let output = match ssh_connexion.command("sudo").args(args).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = match String::from_utf8(output.stdout.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stdout UTF8 info");
+            return Err(APIError::UTF8)
+        }
+    };
+    let stderr = match String::from_utf8(output.stderr.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stderr UTF8 info");
+            return Err(APIError::UTF8)
+        }
+    };
+    if ! output.status.success(){
+        println!("Erreur lors de borg info pour le client {}\nstdout {}\n stderr: {}", uuid, &stdout, &stderr);
+        return Err(APIError::Script)
+    }
+    Ok(stdout)
+}
+
+/// Statistiques d'une archive de données, en octets
+#[derive(Debug, Serialize, Clone)]
+pub struct ArchiveStats{
+    pub name: String,
+    pub start: String,
+    pub end: String,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    /// Place libérée si l'archive était supprimée (données présentes uniquement dans cette archive)
+    pub deduplicated_size: u64,
+    pub nfiles: u64
+}
+
+/// Vue d'ensemble du dépôt pour le tableau de bord
+#[derive(Debug, Serialize, Clone)]
+pub struct RepositoryStatistics{
+    #[serde(flatten)]
+    pub repository: RepositoryStats,
+    /// Taille d'origine / taille compressée
+    pub compression_ratio: f64,
+    /// Nombre d'archives de données (les archives _logs ne sont pas comptées)
+    pub archive_count: usize,
+    pub oldest_archive: Option<String>,
+    pub newest_archive: Option<String>,
+    /// De la plus ancienne à la plus récente
+    pub archives: Vec<ArchiveStats>
+}
+
+fn parse_info(stdout: &str)->Result<BorgRepositoryInfo, APIError>{
+    match serde_json::from_str(stdout){
+        Ok(info)=>Ok(info),
+        Err(e)=>{
+            println!("Erreur lecture de borg info : {}", e);
+            Err(APIError::Json)
+        }
+    }
+}
+
+impl From<&BorgCacheStats> for RepositoryStats {
+    fn from(stats: &BorgCacheStats)->Self{
+        RepositoryStats{
+            original_size: stats.total_size,
+            compressed_size: stats.total_csize,
+            deduplicated_size: stats.unique_csize
+        }
+    }
+}
+
+pub async fn repository_info(uuid: &String, ssh_connexion: Arc<Session>)->Result<RepositoryStats, APIError>{
+    println!("Informations du dépôt pour le client : {}", uuid);
+    let stdout = borg_info(uuid, None, ssh_connexion).await?;
+    let info = parse_info(&stdout)?;
+    Ok(RepositoryStats::from(&info.cache.stats))
+}
+
+/// Statistiques d'une seule archive
+pub async fn archive_info(uuid: &String, archive: &str, ssh_connexion: Arc<Session>)->Result<ArchiveStats, APIError>{
+    let stdout = borg_info(uuid, Some(archive), ssh_connexion).await?;
+    let info = parse_info(&stdout)?;
+    let Some(archive) = info.archives.into_iter().next() else{
+        println!("borg info n'a renvoyé aucune archive pour {}", archive);
+        return Err(APIError::NoFile)
+    };
+    Ok(ArchiveStats::from(archive))
+}
+
+impl From<BorgArchiveInfo> for ArchiveStats {
+    fn from(archive: BorgArchiveInfo)->Self{
+        ArchiveStats{
+            name: archive.name,
+            start: archive.start,
+            end: archive.end,
+            original_size: archive.stats.original_size,
+            compressed_size: archive.stats.compressed_size,
+            deduplicated_size: archive.stats.deduplicated_size,
+            nfiles: archive.stats.nfiles
+        }
+    }
+}
+
+/// Informations du dépôt et de chacune de ses archives, en un seul appel à borg info.
+/// Peut être long sur un gros dépôt : borg doit parcourir les chunks de chaque archive.
+pub async fn repository_statistics(uuid: &String, ssh_connexion: Arc<Session>)->Result<RepositoryStatistics, APIError>{
+    println!("Statistiques du dépôt pour le client : {}", uuid);
+    let stdout = borg_info(uuid, Some("--all-archives"), ssh_connexion).await?;
+    let info = parse_info(&stdout)?;
+    let repository = RepositoryStats::from(&info.cache.stats);
+    let mut archives: Vec<ArchiveStats> = info.archives.into_iter()
+    .filter(|archive| !archive.name.ends_with("_logs"))
+    .map(ArchiveStats::from)
+    .collect();
+    // Les dates ISO se trient comme des chaînes
+    archives.sort_by(|a, b| a.start.cmp(&b.start));
+    let compression_ratio = if repository.compressed_size == 0{
+        0.0
+    }else{
+        repository.original_size as f64 / repository.compressed_size as f64
+    };
+    Ok(RepositoryStatistics{
+        repository,
+        compression_ratio,
+        archive_count: archives.len(),
+        oldest_archive: archives.first().map(|archive| archive.name.clone()),
+        newest_archive: archives.last().map(|archive| archive.name.clone()),
+        archives
+    })
+}
+
+fn cache_duration()->Duration{
+    let seconds = env::var("STATS_CACHE_SECONDS").ok()
+    .and_then(|value| value.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_CACHE_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+/// Statistiques encore valides pour ce client
+pub fn cached_statistics(uuid: &str)->Option<RepositoryStatistics>{
+    let Ok(cache) = STATISTICS_CACHE.lock() else{
+        return None
+    };
+    let (cached_at, statistics) = cache.get(uuid)?;
+    (cached_at.elapsed() < cache_duration()).then(|| statistics.clone())
+}
+
+pub fn cache_statistics(uuid: &str, statistics: &RepositoryStatistics){
+    if let Ok(mut cache) = STATISTICS_CACHE.lock(){
+        // Les entrées expirées des autres clients sont retirées au passage
+        let duration = cache_duration();
+        cache.retain(|_, (cached_at, _)| cached_at.elapsed() < duration);
+        cache.insert(uuid.to_string(), (Instant::now(), statistics.clone()));
+    }
+}
+
+/// À appeler quand des archives sont supprimées
+pub fn invalidate_statistics(uuid: &str){
+    if let Ok(mut cache) = STATISTICS_CACHE.lock(){
+        cache.remove(uuid);
+    }
+}
@@ -1,20 +0,0 @@
-use openssh::Session;
-use std::sync::Arc;
-use openssh_sftp_client::Sftp;
-
-pub async fn install_client_tunnel_key(uuid: String, ssh_key:&String, filepath:String, ssh_connexion: Arc<Session>, sftp_connexion:Arc<Sftp>){
-    // Crée le fichier
-    let mut f = sftp_connexion.create(filepath.clone())
-        .await.expect("Création du fichier à échouer");
-    let _ = f.write(ssh_key.as_bytes()).await;
-    let _ = f.close().await;
-
-    /* Execution du script d'ajout de la clé ssh */
-    let script_path=String::from("/usr/local/sbin/install_client_tunnel_key.sh");
-    match ssh_connexion.command("sudo").args([&script_path, &uuid, &filepath]).output().await{
-        Ok(_)=>println!("script install client tunnel key"),
-        Err(e)=>println!("{}", e.to_string())
-    };
-    // suppresion de la clé
-    let _ = ssh_connexion.command("rm").arg(filepath).output().await;
-}
\ No newline at end of file
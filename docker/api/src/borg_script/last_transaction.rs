@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use openssh::Session;
+use std::sync::Arc;
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/last_transaction.sh";
+
+/// Date de la dernière transaction validée par un client (borg serve --append-only), sans la clé du dépôt.
+/// None si aucun client n'a encore écrit dans le dépôt
+pub async fn last_transaction_at(uuid: &String, ssh_connexion: Arc<Session>)->Result<Option<u64>, APIError>{
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, uuid]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success(){
+        println!("Erreur lors de la lecture des transactions du dépôt\nstdout {}\n stderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+        return Err(APIError::Script)
+    }
+    let line = stdout.trim();
+    if line.is_empty(){
+        return Ok(None)
+    }
+    match parse_transaction(line){
+        Some(time)=>Ok(Some(time)),
+        None=>{
+            println!("Ligne de transaction illisible pour {} : {}", uuid, line);
+            Err(APIError::Script)
+        }
+    }
+}
+
+/// "transaction 42, UTC time 2026-02-18T16:37:10.123456" -> timestamp
+fn parse_transaction(line: &str)->Option<u64>{
+    let (_, time) = line.split_once("UTC time ")?;
+    let time = NaiveDateTime::parse_from_str(time.trim(), "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    u64::try_from(time.and_utc().timestamp()).ok()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn parses_the_transaction_time(){
+        assert_eq!(parse_transaction("transaction 42, UTC time 2026-02-18T16:37:10.123456"), Some(1771432630));
+        // isoformat() de Python omet les microsecondes nulles
+        assert_eq!(parse_transaction("transaction 3, UTC time 2026-02-18T16:37:10"), Some(1771432630));
+    }
+
+    #[test]
+    fn rejects_other_lines(){
+        assert_eq!(parse_transaction("transaction 42"), None);
+        assert_eq!(parse_transaction("transaction 42, UTC time hier"), None);
+    }
+}
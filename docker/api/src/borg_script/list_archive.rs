@@ -4,16 +4,37 @@ use serde_json;
 use serde::{Deserialize, Serialize};
 use crate::error::APIError;
 
+/// Les archives d'un appareil sont nommées <appareil>@<date>
+pub const DEVICE_SEPARATOR: char = '@';
+
+/// Appareil d'une archive ou de son archive _logs, None pour les archives sans appareil
+pub fn archive_device(archive_name: &str)->Option<&str>{
+    archive_name.split_once(DEVICE_SEPARATOR).map(|(device, _)| device)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ArchiveData{
     pub archive: String,
-    pub time: String
+    pub time: String,
+    /// Identifiant borg de l'archive, unique même si un nom est réutilisé
+    #[serde(default)]
+    pub id: String,
+    /// Appareil lu dans le nom de l'archive
+    #[serde(default)]
+    pub device: Option<String>
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Archives{
     pub archives: Vec<ArchiveData>
 }
 
+impl Archives {
+    /// Ne garde que les archives de cet appareil
+    pub fn retain_device(&mut self, device: &str){
+        self.archives.retain(|archive| archive.device.as_deref() == Some(device));
+    }
+}
+
 pub async fn list_archive(uuid: &String, ssh_connexion: Arc<Session>,)->Result<Archives, APIError>{
     println!("List des archive pour le client : {}", uuid);
     let output = match ssh_connexion.command("sudo").args([String::from("/usr/local/sbin/list.sh"), uuid.to_string()]).output().await{
@@ -42,7 +63,10 @@ pub async fn list_archive(uuid: &String, ssh_connexion: Arc<Session>,)->Result<A
         return Err(APIError::Script)
     }
 
-    let archives: Archives = serde_json::from_str(&stdout).expect("serde_json");
+    let mut archives: Archives = serde_json::from_str(&stdout).expect("serde_json");
+    for archive in archives.archives.iter_mut(){
+        archive.device = archive_device(&archive.archive).map(str::to_string);
+    }
     println!("Fin du listing pour le client{}", uuid);
     return Ok(archives)
 }
@@ -2,176 +2,115 @@ use openssh::Session;
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use crate::error::APIError;
-use crate::borg_script::list_archive::{ArchiveContent, list_archive, list_archive_content, Archives};
+use crate::borg_script::list_archive::{list_archive, ArchiveData};
 
-#[derive(Serialize, Deserialize)]
+const SCRIPT: &str = "/usr/local/sbin/read_logs.sh";
+/// Nombre d'archives lues par appel au script
+const READ_BATCH: usize = 50;
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct Logs{
-    logs: Vec<String>
+    pub logs: Vec<String>,
+    /// Archives de logs qui n'ont pas pu être lues, les autres sont tout de même renvoyées
+    pub failed: Vec<String>,
+    /// Date de la dernière archive renvoyée, à repasser en since pour ne récupérer que les suivantes
+    pub latest: Option<String>
 }
 
 /*
 {
     archives: [
-        ArchiveData { archive: "2026-02-18_11-43-46", time: "2026-02-18T10:43:50.000000" }, 
-        ArchiveData { archive: "2026-02-18_11-43-46_logs", time: "2026-02-18T10:44:01.000000"}
+        ArchiveData { archive: "2026-02-18_11-43-46", time: "2026-02-18T10:43:50.000000", id: "..." }, 
+        ArchiveData { archive: "2026-02-18_11-43-46_logs", time: "2026-02-18T10:44:01.000000", id: "..."}
         ] 
 }
-[
-    ArchiveContent { 
-        archive_name: "2026-02-18_11-43-46", 
-        archive_content: [
-            ArchiveFile { type: "-", path: "mnt/d/2025-12-17 14-47-33.mkv", mtime: "2025-12-17T13:48:04.000000", size: 9806209 }
-        ] 
-    }, 
-    ArchiveContent { 
-        archive_name: "2026-02-18_11-43-46_logs", 
-        archive_content: [
-            ArchiveFile { type: "-", path: "home/hugo/.config/borg/logs/2026-02-18_11-43-46_71aea833849e4c258f17c381669b1c7c.log", mtime: "2026-02-18T10:43:59.864507", size: 1835 }
-        ] 
-    }
-]
+L'archive 2026-02-18_11-43-46_logs contient le fichier
+home/hugo/.config/borg/logs/2026-02-18_11-43-46_71aea833849e4c258f17c381669b1c7c.log
 */
 
-pub async fn list_log_content(uuid: &String, ssh_connexion: Arc<Session>)->Result<Logs, APIError>{
-    let mut archives_content = Vec::<ArchiveContent>::new();
-    let mut archives = list_archive(uuid, ssh_connexion.clone()).await?;
-    extract_log_archive(&mut archives);
-    
-    for archive_name in &archives.archives{
-        archives_content.push(list_archive_content(uuid, ssh_connexion.clone(), &archive_name.archive).await?)
-    }
-    let mut logs_path = Vec::<String>::new();
-    for archive in archives_content{
-        logs_path.push(get_log_filename(archive, uuid)?);
-    } 
-    let mut logs = Logs{logs: Vec::<String>::new()};
-    
-    for i in 0..logs_path.len(){
-        let _ = retore_log_file(uuid, ssh_connexion.clone(), &archives.archives[i].archive, &logs_path[i]).await?;
-        let content = get_log_file(uuid, ssh_connexion.clone(), &archives.archives[i].archive, &logs_path[i]).await?;
-        let _ = delete_log_file(uuid, ssh_connexion.clone(), &archives.archives[i].archive, &logs_path[i]).await?;
-        logs.logs.push(content);
-    }
-    
-    return Ok(logs)
+/// Archives de logs du client, de la plus ancienne à la plus récente
+pub async fn list_log_archives(uuid: &String, ssh_connexion: Arc<Session>)->Result<Vec<ArchiveData>, APIError>{
+    let mut archives = list_archive(uuid, ssh_connexion).await?.archives;
+    archives.retain(|archive| archive.archive.ends_with("_logs"));
+    archives.sort_by(|a, b| a.time.cmp(&b.time));
+    Ok(archives)
 }
 
-fn extract_log_archive(archive: &mut Archives){
-    for i in (0..archive.archives.len()).step_by(1).rev(){
-        let filename = &archive.archives[i].archive;
-        let Some((_,log)) = filename.split_at_checked(filename.len()-5)else {
-            continue;
-        };
-        if log != "_logs"{
-            archive.archives.remove(i);
+/// Lit les fichiers de logs de plusieurs archives en un seul appel ssh par lot.
+/// Une archive illisible est renvoyée avec None sans faire échouer les autres.
+pub async fn read_log_archives(uuid: &String, ssh_connexion: Arc<Session>, archive_names: &[String])->Vec<(String, Option<String>)>{
+    let mut logs = Vec::new();
+    for batch in archive_names.chunks(READ_BATCH){
+        match read_log_batch(uuid, ssh_connexion.clone(), batch).await{
+            Ok(mut read)=>{
+                // Les archives absentes de la sortie du script sont considérées comme en échec
+                for name in batch{
+                    if !read.iter().any(|(archive, _)| archive == name){
+                        read.push((name.clone(), None));
+                    }
+                }
+                logs.append(&mut read);
+            },
+            Err(_)=>logs.extend(batch.iter().map(|name| (name.clone(), None)))
         }
     }
+    logs
 }
 
-fn get_log_filename(archive: ArchiveContent, uuid: &String)->Result<String, APIError>{
-    let Some((first_part, _)) =  archive.archive_name.split_at_checked(archive.archive_name.len()-5) else{
-        return Err(APIError::Script);
-    };
-    let log_filename = format!("{}_{}.log",first_part, uuid);
-    let mut log_path = None;
-    for file in archive.archive_content{
-        println!("file : {}", &file.path);
-        if file.path.contains(&log_filename){
-            log_path = Some(file.path);
-            break;
-        }
-    }
-    match log_path {
-        Some(path)=>Ok(path),
-        None=>{
-            println!("Lors du listing des logs, le fichier {} n'a pas été trouvé dans l'archive {}", log_filename, archive.archive_name);
-            Err(APIError::Script)}
-    }
-}
-
-async fn retore_log_file(uuid: &String, ssh_connexion:Arc<Session>, archive_name: &String, log_path: &String)->Result<(), APIError>{
-    // restoration du fichier
-    let script_name = String::from("/usr/local/sbin/restore.sh");
-    let output = match ssh_connexion.command("sudo").args([&script_name, uuid, archive_name, &log_path]).output().await{
+async fn read_log_batch(uuid: &String, ssh_connexion: Arc<Session>, batch: &[String])->Result<Vec<(String, Option<String>)>, APIError>{
+    let output = match ssh_connexion.command("sudo").arg(SCRIPT).arg(uuid).args(batch).output().await{
         Ok(o)=>o,
         Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
     };
-    let stdout = match String::from_utf8(output.stdout.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stdout UTF8 retore_log_file");
-            return Err(APIError::UTF8)
-        }
-    };
-    let stderr = match String::from_utf8(output.stderr.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stderr UTF8 retore_log_file");
-            return Err(APIError::UTF8)
-        }
-    };
     if ! output.status.success(){
-        println!("Erreur lors de la restoration du fichier de logs {}\nstdout {}\n stderr: {}", log_path ,&stdout, &stderr);
+        println!("Erreur lors de la lecture des logs pour le client {}\nstdout {}\n stderr: {}", uuid, String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
         return Err(APIError::Script)
     }
-    Ok(())
+    Ok(parse_read_logs(&output.stdout))
 }
 
-async fn get_log_file(uuid: &String, ssh_connexion:Arc<Session>, archive_name: &String, log_path: &String)->Result<String, APIError>{
-    // restoration du fichier
-     let Some((first_part, _)) =  archive_name.split_at_checked(archive_name.len()-5) else{
-        return Err(APIError::Script);
-    };
-    let output = match ssh_connexion.command("cat").arg(format!("/srv/repos/{}/restore/{}_{}.log", uuid, first_part, uuid)).output().await{
-        Ok(o)=>o,
-        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
-    };
-    let stdout = match String::from_utf8(output.stdout.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stdout UTF8 get_log_file");
-            return Err(APIError::UTF8)
+/// Découpe la sortie de read_logs.sh :
+/// "### ARCHIVE ok TAILLE\n" suivi de TAILLE octets, ou "### ARCHIVE failed\n"
+fn parse_read_logs(stdout: &[u8])->Vec<(String, Option<String>)>{
+    let mut logs = Vec::new();
+    let mut position = 0;
+    while position < stdout.len(){
+        let Some(end_of_line) = stdout[position..].iter().position(|byte| *byte == b'\n') else{
+            break;
+        };
+        let header = String::from_utf8_lossy(&stdout[position..position+end_of_line]).to_string();
+        position += end_of_line + 1;
+        let Some(header) = header.strip_prefix("### ") else{
+            println!("Sortie inattendue de read_logs.sh : {}", header);
+            break;
+        };
+        if let Some(archive) = header.strip_suffix(" failed"){
+            println!("Le fichier de logs de l'archive {} n'a pas pu être lu", archive);
+            logs.push((archive.to_string(), None));
+            continue;
         }
-    };
-    let stderr = match String::from_utf8(output.stderr.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stderr UTF8 get_log_file");
-            return Err(APIError::UTF8)
+        let mut parts = header.rsplitn(3, ' ');
+        let (Some(size), Some("ok"), Some(archive)) = (parts.next(), parts.next(), parts.next()) else{
+            println!("En-tête inattendu de read_logs.sh : {}", header);
+            break;
+        };
+        let Ok(size) = size.parse::<usize>() else{
+            println!("Taille invalide dans la sortie de read_logs.sh : {}", header);
+            break;
+        };
+        let Some(content) = stdout.get(position..position+size) else{
+            println!("Sortie de read_logs.sh tronquée pour l'archive {}", archive);
+            logs.push((archive.to_string(), None));
+            break;
+        };
+        position += size;
+        match String::from_utf8(content.to_vec()){
+            Ok(content)=>logs.push((archive.to_string(), Some(content))),
+            Err(_)=>{
+                println!("Erreur conversion UTF8 des logs de l'archive {}", archive);
+                logs.push((archive.to_string(), None))
+            }
         }
-    };
-    if ! output.status.success(){
-        println!("Erreur lors de la récupération du fichier {}\nstdout {}\n stderr: {}", log_path ,&stdout, &stderr);
-        return Err(APIError::Script)
     }
-    Ok(stdout)
+    logs
 }
-async fn delete_log_file(uuid: &String, ssh_connexion:Arc<Session>, archive_name: &String, log_path: &String)->Result<(), APIError>{
-    // restoration du fichier
-    let Some((first_part, _)) =  archive_name.split_at_checked(archive_name.len()-5) else{
-        return Err(APIError::Script);
-    };
-    let output = match ssh_connexion.command("rm").arg(format!("/srv/repos/{}/restore/{}_{}.log", uuid, first_part, uuid)).output().await{
-        Ok(o)=>o,
-        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
-    };
-    let stdout = match String::from_utf8(output.stdout.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stdout UTF8 delete_log_file");
-            return Err(APIError::UTF8)
-        }
-    };
-    let stderr = match String::from_utf8(output.stderr.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stderr UTF8 delete_log_file");
-            return Err(APIError::UTF8)
-        }
-    };
-    if ! output.status.success(){
-        println!("Erreur lors de la supression du fichier {}\nstdout {}\n stderr: {}", log_path ,&stdout, &stderr);
-        return Err(APIError::Script)
-    }
-    Ok(())
-}
\ No newline at end of file
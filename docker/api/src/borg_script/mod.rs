@@ -1,7 +1,18 @@
 pub mod create_user;
-pub mod install_client_key;
 pub mod list_archive;
 pub mod ssh_pub_key_server;
 pub mod restore;
-pub mod install_client_tunnel_key;
 pub mod log;
+pub mod diff;
+pub mod store_restore;
+pub mod share_file;
+pub mod purge_restore;
+pub mod delete_archive;
+pub mod prune;
+pub mod compact;
+pub mod info;
+pub mod check;
+pub mod last_transaction;
+
+pub mod tunnels;
+pub mod import_authorized_keys;
\ No newline at end of file
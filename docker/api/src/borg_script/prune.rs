@@ -0,0 +1,283 @@
+use chrono::NaiveDateTime;
+use openssh::Session;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use crate::borg_script::compact::{compaction, Compaction};
+use crate::borg_script::list_archive::{archive_device, Archives};
+use crate::database::retention_policies::RetentionPolicy;
+use crate::error::APIError;
+const SCRIPT: &str = "/usr/local/sbin/prune.sh";
+const LOGS_SUFFIX: &str = "_logs";
+
+/// Résultat de la politique de rétention sur les archives d'un dépôt
+#[derive(Debug, Serialize)]
+pub struct PrunePlan{
+    /// Archives de données conservées
+    pub keep: Vec<String>,
+    /// Archives de données supprimées, avec leur archive _logs
+    pub prune: Vec<PrunedArchive>
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrunedArchive{
+    pub archive: String,
+    pub logs: Option<String>
+}
+
+impl PrunePlan {
+    /// Noms de toutes les archives à supprimer, archives _logs comprises
+    pub fn archive_names(&self)->Vec<String>{
+        let mut names = Vec::new();
+        for pruned in &self.prune{
+            names.push(pruned.archive.clone());
+            if let Some(logs) = &pruned.logs{
+                names.push(logs.clone());
+            }
+        }
+        names
+    }
+}
+
+/// Calcule les archives à supprimer avec le même algorithme que borg prune :
+/// pour chaque règle, de la plus fine à la plus large, on garde l'archive la plus récente de chaque période.
+/// Seules les archives de données sont comptées, leur archive _logs suit leur sort (log.rs a besoin des deux).
+/// Chaque appareil a sa propre rétention, les archives sans appareil forment un groupe à part.
+/// Les archives verrouillées (ou dont l'archive _logs est verrouillée) sont toujours gardées.
+pub fn prune_plan(archives: &Archives, policy: &RetentionPolicy, locked: &[String])->PrunePlan{
+    let names: HashSet<&str> = archives.archives.iter().map(|archive| archive.archive.as_str()).collect();
+    let mut undated = Vec::new();
+    let mut data: Vec<(&str, NaiveDateTime)> = archives.archives.iter()
+    .filter(|archive| !archive.archive.ends_with(LOGS_SUFFIX))
+    .filter_map(|archive| match NaiveDateTime::parse_from_str(&archive.time, "%Y-%m-%dT%H:%M:%S%.f"){
+        Ok(time)=>Some((archive.archive.as_str(), time)),
+        Err(_)=>{
+            // Sans date l'archive ne peut pas être classée, elle est gardée
+            println!("Date invalide pour l'archive {} : {}", archive.archive, archive.time);
+            undated.push(archive.archive.clone());
+            None
+        }
+    })
+    .collect();
+    // La plus récente en premier
+    data.sort_by_key(|archive| std::cmp::Reverse(archive.1));
+
+    let mut devices: Vec<Option<&str>> = data.iter().map(|(archive, _)| archive_device(archive)).collect();
+    devices.sort();
+    devices.dedup();
+    let mut kept: HashSet<&str> = HashSet::new();
+    for device in devices{
+        let device_data: Vec<(&str, NaiveDateTime)> = data.iter().copied()
+        .filter(|(archive, _)| archive_device(archive) == device)
+        .collect();
+        kept.extend(kept_archives(&device_data, policy));
+    }
+
+    let mut plan = PrunePlan{keep: undated, prune: Vec::new()};
+    // Par sécurité une politique vide ne supprime rien
+    let apply = policy.keeps_something();
+    for (archive, _) in &data{
+        let logs = format!("{}{}", archive, LOGS_SUFFIX);
+        let is_locked = locked.iter().any(|lock| lock == archive || *lock == logs);
+        if !apply || kept.contains(archive) || is_locked{
+            plan.keep.push(archive.to_string());
+        }else{
+            plan.prune.push(PrunedArchive{
+                archive: archive.to_string(),
+                logs: names.contains(logs.as_str()).then_some(logs)
+            });
+        }
+    }
+    plan
+}
+
+/// Archives gardées par les règles de la politique, data va de la plus récente à la plus ancienne
+fn kept_archives<'a>(data: &[(&'a str, NaiveDateTime)], policy: &RetentionPolicy)->HashSet<&'a str>{
+    let rules = [
+        (policy.keep_hourly, "%Y-%m-%d %H"),
+        (policy.keep_daily, "%Y-%m-%d"),
+        (policy.keep_weekly, "%G-%V"),
+        (policy.keep_monthly, "%Y-%m"),
+        (policy.keep_yearly, "%Y")
+    ];
+    let mut kept = HashSet::new();
+    for (count, period_format) in rules{
+        if count == 0{
+            continue
+        }
+        let mut kept_by_rule = 0;
+        let mut last_period = None;
+        for (archive, time) in data{
+            let period = time.format(period_format).to_string();
+            if last_period.as_ref() == Some(&period){
+                continue
+            }
+            last_period = Some(period);
+            if kept.insert(*archive){
+                kept_by_rule += 1;
+                if kept_by_rule == count{
+                    break
+                }
+            }
+        }
+    }
+    kept
+}
+
+/// Supprime les archives du plan puis compacte le dépôt, renvoie les archives réellement supprimées
+pub async fn prune_archives(uuid: &String, plan: &PrunePlan, ssh_connexion: Arc<Session>)->Result<(Vec<String>, Compaction), APIError>{
+    let archives = plan.archive_names();
+    if archives.is_empty(){
+        return Ok((Vec::new(), Compaction::Done))
+    }
+    println!("Rétention : suppression de {} archives pour le client : {}", archives.len(), uuid);
+    let mut args = vec![String::from(SCRIPT), uuid.to_string()];
+    args.extend(archives);
+    let output = match ssh_connexion.command("sudo").args(args).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = match String::from_utf8(output.stdout.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stdout UTF8 prune");
+            return Err(APIError::UTF8)
+        }
+    };
+    let stderr = match String::from_utf8(output.stderr.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stderr UTF8 prune");
+            return Err(APIError::UTF8)
+        }
+    };
+    let Some(compaction) = compaction(output.status.code(), &stderr) else{
+        println!("Erreur lors de la rétention\nstdout {}\n stderr: {}", &stdout, &stderr);
+        return Err(APIError::Script)
+    };
+    Ok((stdout.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect(), compaction))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::borg_script::list_archive::ArchiveData;
+
+    fn archives(list: &[(&str, &str)])->Archives{
+        Archives{archives: list.iter().map(|(archive, time)| ArchiveData{
+            archive: archive.to_string(),
+            time: format!("{}.000000", time),
+            id: String::new(),
+            device: archive_device(archive).map(str::to_string)
+        }).collect()}
+    }
+
+    fn pruned(plan: &PrunePlan)->Vec<&str>{
+        let mut pruned: Vec<&str> = plan.prune.iter().map(|pruned| pruned.archive.as_str()).collect();
+        pruned.sort();
+        pruned
+    }
+
+    #[test]
+    fn hourly_keeps_the_latest_archive_of_each_hour(){
+        let archives = archives(&[
+            ("a", "2026-03-10T10:00:00"), ("b", "2026-03-10T10:30:00"),
+            ("c", "2026-03-10T11:00:00"), ("d", "2026-03-10T12:00:00")
+        ]);
+        let policy = RetentionPolicy{keep_hourly: 2, ..Default::default()};
+        assert_eq!(pruned(&prune_plan(&archives, &policy, &[])), ["a", "b"]);
+    }
+
+    #[test]
+    fn an_archive_kept_by_an_earlier_rule_does_not_count_for_the_next(){
+        let archives = archives(&[
+            ("d1", "2026-03-08T12:00:00"),
+            ("d2_morning", "2026-03-09T10:00:00"), ("d2_evening", "2026-03-09T20:00:00"),
+            ("d3_early", "2026-03-10T08:00:00"), ("d3_late", "2026-03-10T09:00:00")
+        ]);
+        // d3_late est gardée par l'heure, le jour 3 est consommé sans compter : daily garde d2_evening et d1
+        let policy = RetentionPolicy{keep_hourly: 1, keep_daily: 2, ..Default::default()};
+        let plan = prune_plan(&archives, &policy, &[]);
+        assert_eq!(pruned(&plan), ["d2_morning", "d3_early"]);
+        assert!(plan.keep.contains(&String::from("d1")));
+    }
+
+    #[test]
+    fn weekly_uses_iso_weeks_across_the_new_year(){
+        // Le lundi 29/12/2025 et le jeudi 01/01/2026 sont tous deux dans la semaine 2026-W01
+        let archives = archives(&[
+            ("w51", "2025-12-21T10:00:00"), ("w52", "2025-12-28T10:00:00"),
+            ("w01_monday", "2025-12-29T10:00:00"), ("w01_thursday", "2026-01-01T10:00:00")
+        ]);
+        let policy = RetentionPolicy{keep_weekly: 2, ..Default::default()};
+        assert_eq!(pruned(&prune_plan(&archives, &policy, &[])), ["w01_monday", "w51"]);
+    }
+
+    #[test]
+    fn monthly_keeps_the_latest_archive_of_each_month(){
+        let archives = archives(&[
+            ("january", "2026-01-10T10:00:00"), ("february", "2026-02-28T10:00:00"),
+            ("march_1", "2026-03-01T10:00:00"), ("march_15", "2026-03-15T10:00:00")
+        ]);
+        let policy = RetentionPolicy{keep_monthly: 2, ..Default::default()};
+        assert_eq!(pruned(&prune_plan(&archives, &policy, &[])), ["january", "march_1"]);
+    }
+
+    #[test]
+    fn each_device_has_its_own_retention(){
+        let archives = archives(&[
+            ("laptop@old", "2026-03-09T10:00:00"), ("laptop@new", "2026-03-10T10:00:00"),
+            ("desktop@only", "2026-01-01T10:00:00"),
+            ("plain_old", "2026-02-01T10:00:00"), ("plain_new", "2026-02-02T10:00:00")
+        ]);
+        let policy = RetentionPolicy{keep_daily: 1, ..Default::default()};
+        assert_eq!(pruned(&prune_plan(&archives, &policy, &[])), ["laptop@old", "plain_old"]);
+    }
+
+    #[test]
+    fn logs_follow_their_data_archive(){
+        let archives = archives(&[
+            ("old", "2026-03-08T10:00:00"), ("old_logs", "2026-03-08T10:00:05"),
+            ("older", "2026-03-07T10:00:00"),
+            ("new", "2026-03-10T10:00:00"), ("new_logs", "2026-03-10T10:00:05")
+        ]);
+        let policy = RetentionPolicy{keep_daily: 1, ..Default::default()};
+        let plan = prune_plan(&archives, &policy, &[]);
+        assert_eq!(plan.keep, ["new"]);
+        assert_eq!(plan.archive_names().len(), 3);
+        let old = plan.prune.iter().find(|pruned| pruned.archive == "old").unwrap();
+        assert_eq!(old.logs.as_deref(), Some("old_logs"));
+        let older = plan.prune.iter().find(|pruned| pruned.archive == "older").unwrap();
+        assert_eq!(older.logs, None);
+    }
+
+    #[test]
+    fn a_locked_logs_archive_keeps_its_data_archive(){
+        let archives = archives(&[
+            ("old", "2026-03-08T10:00:00"), ("old_logs", "2026-03-08T10:00:05"),
+            ("new", "2026-03-10T10:00:00")
+        ]);
+        let policy = RetentionPolicy{keep_daily: 1, ..Default::default()};
+        let plan = prune_plan(&archives, &policy, &[String::from("old_logs")]);
+        assert!(plan.prune.is_empty());
+        assert!(plan.keep.contains(&String::from("old")));
+    }
+
+    #[test]
+    fn an_empty_policy_deletes_nothing(){
+        let archives = archives(&[("a", "2026-03-08T10:00:00"), ("b", "2026-03-10T10:00:00")]);
+        let plan = prune_plan(&archives, &RetentionPolicy::default(), &[]);
+        assert!(plan.prune.is_empty());
+        assert_eq!(plan.keep.len(), 2);
+    }
+
+    #[test]
+    fn archives_without_a_valid_date_are_kept(){
+        let mut archives = archives(&[("old", "2026-03-08T10:00:00"), ("new", "2026-03-10T10:00:00")]);
+        archives.archives.push(ArchiveData{archive: String::from("undated"), time: String::from("hier"), id: String::new(), device: None});
+        let policy = RetentionPolicy{keep_daily: 1, ..Default::default()};
+        let plan = prune_plan(&archives, &policy, &[]);
+        assert_eq!(pruned(&plan), ["old"]);
+        assert!(plan.keep.contains(&String::from("undated")));
+    }
+}
@@ -0,0 +1,55 @@
+use openssh::Session;
+use std::sync::Arc;
+use crate::error::APIError;
+
+/// Élément supprimé d'un dossier restore
+#[derive(Debug)]
+pub struct PurgedEntry{
+    pub client: String,
+    pub size: u64,
+    pub name: String,
+    /// false si la suppression a échoué
+    pub removed: bool
+}
+
+/// Supprime les restaurations plus vieilles que max_age_minutes dans les dossiers de tous les clients
+pub async fn purge_restore(ssh_connexion: Arc<Session>, max_age_minutes: u64)->Result<Vec<PurgedEntry>, APIError>{
+    let output = match ssh_connexion.command("sudo").args([String::from("/usr/local/sbin/purge_restore.sh"), max_age_minutes.to_string()]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = match String::from_utf8(output.stdout.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stdout UTF8 purge_restore");
+            return Err(APIError::UTF8)
+        }
+    };
+    let stderr = match String::from_utf8(output.stderr.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stderr UTF8 purge_restore");
+            return Err(APIError::UTF8)
+        }
+    };
+    if ! output.status.success(){
+        println!("Erreur lors de la purge des restaurations\nstdout {}\n stderr: {}", &stdout, &stderr);
+        return Err(APIError::Script)
+    }
+
+    // Une ligne par élément : "<client> <taille> <nom>", ou "<client> failed <nom>" si la suppression a échoué
+    let mut purged = Vec::<PurgedEntry>::new();
+    for line in stdout.lines(){
+        let mut parts = line.splitn(3, ' ');
+        let (Some(client), Some(size), Some(name)) = (parts.next(), parts.next(), parts.next()) else{
+            continue;
+        };
+        purged.push(PurgedEntry{
+            client: client.to_string(),
+            size: size.parse().unwrap_or(0),
+            name: name.to_string(),
+            removed: size != "failed"
+        });
+    }
+    Ok(purged)
+}
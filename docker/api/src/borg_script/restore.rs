@@ -1,76 +1,151 @@
 use openssh::Session;
 use std::sync::Arc;
 use crate::error::APIError;
-use openssh_sftp_client::{file::File, Sftp};
+use crate::stream_http::{stream_http::BoxReader, tar_to_zip::tar_to_zip, remote_stream::RemoteStream, cleanup_reader::CleanupReader};
+use openssh_sftp_client::{file::TokioCompatFile, Sftp};
+use tokio::io::AsyncRead;
+use tokio::sync::watch;
+use uuid::Uuid;
 const CLIENT_DIRECTORY: &str = "/srv/repos";
 use serde::Deserialize;
 
-#[derive(Deserialize)]
-struct Restore{
-    archive_name:String
-}
+/// Les variantes sont essayées dans l'ordre, la plus précise en premier
 #[derive(Debug, Deserialize)]
-struct RestoreFile{
-    archive_name:String,
-    file_name:String
+#[serde(untagged)]
+enum RestoreRequest{
+    Paths{
+        archive_name:String,
+        paths:Vec<String>,
+        #[serde(default)]
+        format:RestoreFormat
+    },
+    File{
+        archive_name:String,
+        file_name:String,
+        /// Utilisé seulement si file_name est un dossier
+        #[serde(default)]
+        format:RestoreFormat
+    },
+    Archive{
+        archive_name:String,
+        #[serde(default)]
+        format:RestoreFormat
+    }
 }
 
-pub async fn dertermining_restore_mode(uuid: &String, body: &String, ssh_connexion: Arc<Session>, sftp_connexion:Arc<Sftp>)-> Result<(File, String), APIError>{
-    let restore_file_name : RestoreFile = match serde_json::from_str(body.as_str()){
-        Ok(restore_file_name)=>restore_file_name,
-        Err(_)=>{
-            let restore_archive_name :Restore = match serde_json::from_str(body.as_str()){
-                Ok(restore)=>restore,
-                Err(_)=>{
-                    println!("erreur determining");
-                    return Err(APIError::ValidInput)}
-            };
-            println!("c'est restore");
-            let file_name_only: Vec<&str> = restore_archive_name.archive_name.split("\\").collect();
-            let file_name_only = file_name_only[file_name_only.len()-1];
-            return Ok((restore(&uuid, &restore_archive_name.archive_name, ssh_connexion.clone(), sftp_connexion.clone()).await?, format!("{}.tar.gz", file_name_only)))
-        }
-    };
-    println!("c'est restore_file");
-    return Ok((restore_file(&uuid, &restore_file_name.archive_name, &restore_file_name.file_name, ssh_connexion.clone(), sftp_connexion.clone()).await?, restore_file_name.file_name))
-    
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreFormat{
+    #[serde(rename = "tar")]
+    Tar,
+    #[default]
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    #[serde(rename = "zip")]
+    Zip
 }
 
-pub async fn restore(uuid: &String, archive:&String, ssh_connexion: Arc<Session>, sftp_connexion: Arc<Sftp>)->Result<File, APIError>{
-    let output = match ssh_connexion.command("sudo").args(["/usr/local/sbin/restore.sh", uuid.as_str(), archive.as_str()]).output().await{
-        Ok(o)=>o,
-        Err(_)=>{println!("Erreur ssh command restore");return Err(APIError::Ssh)}
-    };
-    let stdout = match String::from_utf8(output.stdout.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stdout UTF8 restore");
-            return Err(APIError::UTF8)
+impl RestoreFormat {
+    pub fn extension(&self)->&'static str{
+        match self {
+            RestoreFormat::Tar=>"tar",
+            RestoreFormat::TarGz=>"tar.gz",
+            RestoreFormat::TarZst=>"tar.zst",
+            RestoreFormat::Zip=>"zip"
         }
-    };
-    let stderr = match String::from_utf8(output.stderr.clone()){
-        Ok(out)=>out,
-        Err(_)=>{
-            println!("Erreur conversion stderr UTF8 restore");
-            return Err(APIError::UTF8)
+    }
+
+    /// Format demandé aux scripts serveur, le zip est construit par l'API à partir d'un tar
+    fn server_format(&self)->&'static str{
+        match self {
+            RestoreFormat::Zip=>"tar",
+            _=>self.extension()
         }
-    };
-    if ! output.status.success(){
-        println!("Erreur lors du restore\nstdout {}\n stderr: {}", &stdout, &stderr);
-        return Err(APIError::Script)
     }
-    let file_restore_path = format!("{}/{}/restore/{}.tar.gz",CLIENT_DIRECTORY, uuid, archive);
-    match sftp_connexion.open(file_restore_path).await{
-        Ok(f)=>return Ok(f),
+
+    /// Transforme l'archive produite par le serveur dans le format demandé
+    fn reader(&self, archive: impl AsyncRead + Unpin + 'static)->BoxReader{
+        match self {
+            RestoreFormat::Zip=>Box::pin(tar_to_zip(archive)),
+            _=>Box::pin(archive)
+        }
+    }
+}
+
+fn parse_restore_request(body: &str)->Result<RestoreRequest, APIError>{
+    match serde_json::from_str(body){
+        Ok(restore_request)=>Ok(restore_request),
         Err(_)=>{
-            println!("Erreur Sftp connexion restore");
-            return Err(APIError::Sftp)
+            println!("erreur determining");
+            Err(APIError::ValidInput)
+        }
+    }
+}
+
+/// Vérifie le corps d'une demande de restauration et renvoie le nom de l'archive concernée
+pub fn restore_archive_name(body: &str)->Result<String, APIError>{
+    match parse_restore_request(body)? {
+        RestoreRequest::Paths{archive_name, ..}=>Ok(archive_name),
+        RestoreRequest::File{archive_name, ..}=>Ok(archive_name),
+        RestoreRequest::Archive{archive_name, ..}=>Ok(archive_name)
+    }
+}
+
+/// progress reçoit l'avancement de borg en pourcentage (exports d'archive ou de chemins uniquement)
+pub async fn dertermining_restore_mode(uuid: &String, body: &str, ssh_connexion: Arc<Session>, sftp_connexion:Arc<Sftp>, progress: Option<watch::Sender<f64>>)-> Result<(BoxReader, String), APIError>{
+    let restore_request = parse_restore_request(body)?;
+    match restore_request {
+        RestoreRequest::Paths{archive_name, paths, format}=>{
+            println!("c'est restore_paths");
+            let stream = restore_paths(uuid, &archive_name, &paths, format, ssh_connexion, progress).await?;
+            Ok((format.reader(stream), format!("{}.{}", archive_file_name(&archive_name), format.extension())))
+        },
+        RestoreRequest::File{archive_name, file_name, format}=>{
+            println!("c'est restore_file");
+            restore_file_stream(uuid, &archive_name, &file_name, format, ssh_connexion, sftp_connexion).await
+        },
+        RestoreRequest::Archive{archive_name, format}=>{
+            println!("c'est restore");
+            let stream = restore(uuid, &archive_name, format, ssh_connexion, progress).await?;
+            Ok((format.reader(stream), format!("{}.{}", archive_file_name(&archive_name), format.extension())))
         }
     }
 }
 
-pub async fn restore_file(uuid: &String, archive: &String, file_name:&String, ssh_connexion: Arc<Session>, sftp_connexion: Arc<Sftp>)->Result<File, APIError>{
-    let output = match ssh_connexion.command("sudo").args(["/usr/local/sbin/restore.sh", uuid, archive, &file_name]).output().await{
+/// Fichier tel quel ou dossier dans le format demandé, avec son nom de téléchargement
+pub async fn restore_file_stream(uuid: &String, archive: &str, file_name: &str, format: RestoreFormat, ssh_connexion: Arc<Session>, sftp_connexion: Arc<Sftp>)-> Result<(BoxReader, String), APIError>{
+    let (file, download_name, is_directory) = restore_file(uuid, archive, file_name, format, ssh_connexion, sftp_connexion).await?;
+    if is_directory{
+        return Ok((format.reader(file), download_name))
+    }
+    Ok((Box::pin(file), download_name))
+}
+
+fn archive_file_name(archive_name: &str)->&str{
+    let file_name_only: Vec<&str> = archive_name.split("\\").collect();
+    file_name_only[file_name_only.len()-1]
+}
+
+/// Exporte l'archive complète
+pub async fn restore(uuid: &String, archive: &String, format: RestoreFormat, ssh_connexion: Arc<Session>, progress: Option<watch::Sender<f64>>)->Result<RemoteStream, APIError>{
+    export_tar(uuid, archive, &[], format, ssh_connexion, progress).await
+}
+
+/// Restaure un fichier ou un dossier de l'archive.
+/// Un dossier est renvoyé sous forme d'archive (tar.gz par défaut) portant son nom, le nom de téléchargement est retourné avec le fichier.
+/// Le booléen indique si la cible était un dossier.
+/// Le fichier préparé sur le serveur est supprimé lorsque le lecteur renvoyé est libéré.
+pub async fn restore_file(uuid: &String, archive: &str, file_name:&str, format: RestoreFormat, ssh_connexion: Arc<Session>, sftp_connexion: Arc<Sftp>)->Result<(CleanupReader, String, bool), APIError>{
+    // borg ne stocke pas le / de tête
+    let target = file_name.trim_start_matches('/').trim_end_matches('/');
+    if target.is_empty(){
+        println!("Cible vide pour restore_file");
+        return Err(APIError::ValidInput)
+    }
+    // Nom unique pour que deux restaurations simultanées ne s'écrasent pas
+    let restore_name = Uuid::new_v4().simple().to_string();
+    let output = match ssh_connexion.command("sudo").args(["/usr/local/sbin/restore.sh", uuid, archive, target, &restore_name, format.server_format()]).output().await{
         Ok(o)=>o,
         Err(_)=>{println!("Erreur ssh command restore");return Err(APIError::Ssh)}
     };
@@ -96,15 +171,50 @@ pub async fn restore_file(uuid: &String, archive: &String, file_name:&String, ss
         
         return Err(APIError::NoFile)
     }
-    let file_name_only: Vec<&str> = file_name.split("/").collect();
+    let file_name_only: Vec<&str> = target.split("/").collect();
     let file_name_only = file_name_only[file_name_only.len()-1];
     println!("{}",&file_name_only);
-    let file_restore_path = format!("{}/{}/restore/{}",CLIENT_DIRECTORY, uuid, file_name_only);
+    // restore.sh termine par "directory" ou "file"
+    let is_directory = stdout.lines().last().map(|line| line.trim()) == Some("directory");
+    let (file_restore_path, download_name) = if is_directory{
+        (
+            format!("{}/{}/restore/{}.{}",CLIENT_DIRECTORY, uuid, restore_name, format.server_format()),
+            format!("{}.{}", file_name_only, format.extension())
+        )
+    }else{
+        (
+            format!("{}/{}/restore/{}",CLIENT_DIRECTORY, uuid, restore_name),
+            file_name_only.to_string()
+        )
+    };
+    let cleanup_args = vec![String::from("/usr/local/sbin/cleanup_restore.sh"), uuid.to_string(), restore_name];
     match sftp_connexion.open(file_restore_path).await{
-        Ok(f)=>return Ok(f),
+        Ok(f)=>Ok((CleanupReader::new(Box::pin(TokioCompatFile::from(f)), ssh_connexion, cleanup_args), download_name, is_directory)),
         Err(_)=>{
             println!("Erreur Sftp connexion restore");
-            return Err(APIError::Sftp)
+            let _ = ssh_connexion.command("sudo").args(&cleanup_args).status().await;
+            Err(APIError::Sftp)
         }
     }
-}
\ No newline at end of file
+}
+
+pub async fn restore_paths(uuid: &String, archive: &String, paths: &[String], format: RestoreFormat, ssh_connexion: Arc<Session>, progress: Option<watch::Sender<f64>>)->Result<RemoteStream, APIError>{
+    if paths.is_empty() || paths.iter().any(|path| path.trim().is_empty()){
+        println!("Liste de chemins vide pour restore_paths");
+        return Err(APIError::ValidInput)
+    }
+    export_tar(uuid, archive, paths, format, ssh_connexion, progress).await
+}
+
+/// Exporte les chemins demandés (toute l'archive si la liste est vide) avec borg export-tar.
+/// L'archive n'est pas écrite sur le serveur, elle est lue directement sur la sortie du script.
+async fn export_tar(uuid: &String, archive: &String, paths: &[String], format: RestoreFormat, ssh_connexion: Arc<Session>, progress: Option<watch::Sender<f64>>)->Result<RemoteStream, APIError>{
+    println!("Export de {} pour le client : {}", archive, uuid);
+    // Identifiant unique pour pouvoir annuler cet export sans toucher aux autres
+    let stream_id = Uuid::new_v4().simple().to_string();
+    let mut args = vec![String::from("/usr/local/sbin/export_tar.sh"), uuid.to_string(), archive.to_string(), stream_id.clone(), format.server_format().to_string()];
+    // borg ne stocke pas le / de tête
+    args.extend(paths.iter().map(|path| path.trim_start_matches('/').to_string()));
+    let cancel_args = vec![String::from("/usr/local/sbin/cancel_export.sh"), uuid.to_string(), stream_id];
+    RemoteStream::spawn(ssh_connexion, args, cancel_args, progress).await
+}
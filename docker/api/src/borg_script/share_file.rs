@@ -0,0 +1,37 @@
+use openssh::Session;
+use openssh_sftp_client::{Sftp, file::File};
+use std::sync::Arc;
+use crate::error::APIError;
+use super::store_restore::{open_staged, valid_restore_id};
+const CLIENT_DIRECTORY: &str = "/srv/repos";
+const SCRIPT: &str = "/usr/local/sbin/share_file.sh";
+
+/// Déplace une restauration préparée dans le dossier shared du client, que purge_restore.sh ne nettoie pas
+pub async fn keep_shared(uuid: &String, restore_id: &str, ssh_connexion: Arc<Session>)->Result<(), APIError>{
+    share_file(uuid, restore_id, "keep", ssh_connexion).await
+}
+
+/// Supprime le fichier d'un lien de partage qui ne peut plus servir
+pub async fn remove_shared(uuid: &String, restore_id: &str, ssh_connexion: Arc<Session>)->Result<(), APIError>{
+    share_file(uuid, restore_id, "remove", ssh_connexion).await
+}
+
+async fn share_file(uuid: &String, restore_id: &str, action: &str, ssh_connexion: Arc<Session>)->Result<(), APIError>{
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, uuid, restore_id, action]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    if ! output.status.success(){
+        println!("Erreur share_file.sh {} pour {} ({})\nstderr: {}", action, uuid, restore_id, String::from_utf8_lossy(&output.stderr));
+        return Err(APIError::Script)
+    }
+    Ok(())
+}
+
+/// Fichier d'un lien de partage : déjà déchiffré, la clé du dépôt n'est pas nécessaire
+pub async fn open_shared(uuid: &String, restore_id: &str, sftp_connexion: Arc<Sftp>)->Result<(File, u64, String), APIError>{
+    if ! valid_restore_id(restore_id){
+        return Err(APIError::ValidInput)
+    }
+    open_staged(format!("{}/{}/shared/{}", CLIENT_DIRECTORY, uuid, restore_id), restore_id, sftp_connexion).await
+}
@@ -0,0 +1,128 @@
+use openssh::{Session, Stdio};
+use openssh_sftp_client::{Sftp, file::{File, TokioCompatFile}};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
+use crate::borg_script::restore::dertermining_restore_mode;
+use crate::error::APIError;
+use crate::stream_http::stream_http::BoxReader;
+const CLIENT_DIRECTORY: &str = "/srv/repos";
+const SCRIPT: &str = "/usr/local/sbin/store_restore.sh";
+
+/// Restauration préparée sur le serveur, téléchargeable par morceaux
+#[derive(Debug, Serialize)]
+pub struct RestoreArtifact{
+    pub restore_id: String,
+    pub file_name: String,
+    pub size: u64,
+    pub etag: String
+}
+
+/// Le fichier ne change jamais une fois préparé, l'identifiant suffit comme ETag
+pub fn restore_etag(restore_id: &str)->String{
+    format!("\"{}\"", restore_id)
+}
+
+/// L'identifiant est utilisé dans un chemin, il doit venir de l'API
+pub fn valid_restore_id(restore_id: &str)->bool{
+    restore_id.len() == 32 && restore_id.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+}
+
+/// Produit la restauration demandée (même corps que get_restore) et l'enregistre dans le dossier restore du client
+pub async fn stage_restore(uuid: &String, body: &str, ssh_connexion: Arc<Session>, sftp_connexion: Arc<Sftp>)->Result<RestoreArtifact, APIError>{
+    let (reader, file_name) = dertermining_restore_mode(uuid, body, ssh_connexion.clone(), sftp_connexion, None).await?;
+    store_restore(uuid, reader, file_name, ssh_connexion).await
+}
+
+/// Enregistre le flux d'une restauration dans le dossier restore du client
+pub async fn store_restore(uuid: &String, mut reader: BoxReader, file_name: String, ssh_connexion: Arc<Session>)->Result<RestoreArtifact, APIError>{
+    let restore_id = Uuid::new_v4().simple().to_string();
+    println!("Préparation de la restauration {} pour le client : {}", restore_id, uuid);
+
+    let mut child = match ssh_connexion.clone().arc_command("sudo").args([SCRIPT, uuid, &restore_id, "write"]).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().await{
+        Ok(child)=>child,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let Some(mut stdin) = child.stdin().take() else{
+        println!("Entrée du script store_restore indisponible");
+        return Err(APIError::Ssh)
+    };
+    let copy = tokio::io::copy(&mut reader, &mut stdin).await;
+    let _ = stdin.shutdown().await;
+    drop(stdin);
+    let written = match child.wait().await{
+        Ok(status) if status.success()=>copy,
+        _=>Err(std::io::Error::other("store_restore.sh write a échoué"))
+    };
+    if let Err(e) = written{
+        println!("Erreur lors de la préparation de la restauration {} : {}", restore_id, e);
+        let _ = ssh_connexion.command("sudo").args([SCRIPT, uuid, &restore_id, "abort"]).status().await;
+        return Err(APIError::Write)
+    }
+
+    let output = match ssh_connexion.command("sudo").args([SCRIPT, uuid, &restore_id, "commit", &file_name]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = match String::from_utf8(output.stdout.clone()){
+        Ok(out)=>out,
+        Err(_)=>{
+            println!("Erreur conversion stdout UTF8 stage_restore");
+            return Err(APIError::UTF8)
+        }
+    };
+    if ! output.status.success(){
+        println!("Erreur lors du commit de la restauration {}\nstdout {}", restore_id, &stdout);
+        return Err(APIError::Script)
+    }
+    let size = match stdout.trim().parse::<u64>(){
+        Ok(size)=>size,
+        Err(_)=>{
+            println!("Taille invalide renvoyée par store_restore.sh : {}", stdout);
+            return Err(APIError::Usize)
+        }
+    };
+    println!("Restauration {} prête ({} octets)", restore_id, size);
+    Ok(RestoreArtifact{
+        etag: restore_etag(&restore_id),
+        restore_id,
+        file_name,
+        size
+    })
+}
+
+/// Ouvre une restauration préparée : fichier, taille et nom de téléchargement
+pub async fn open_restore(uuid: &String, restore_id: &str, sftp_connexion: Arc<Sftp>)->Result<(File, u64, String), APIError>{
+    if ! valid_restore_id(restore_id){
+        return Err(APIError::ValidInput)
+    }
+    open_staged(format!("{}/{}/restore/{}", CLIENT_DIRECTORY, uuid, restore_id), restore_id, sftp_connexion).await
+}
+
+/// Fichier préparé sur le serveur, sa taille et le nom de téléchargement enregistré à côté (<fichier>.name)
+pub(super) async fn open_staged(restore_path: String, restore_id: &str, sftp_connexion: Arc<Sftp>)->Result<(File, u64, String), APIError>{
+    let mut file_name = String::new();
+    match sftp_connexion.open(format!("{}.name", restore_path)).await{
+        Ok(f)=>{
+            let mut name_file = Box::pin(TokioCompatFile::from(f));
+            if name_file.read_to_string(&mut file_name).await.is_err(){
+                println!("Erreur lecture du nom de la restauration {}", restore_id);
+                return Err(APIError::UTF8)
+            }
+        },
+        Err(_)=>return Err(APIError::NoFile)
+    };
+    let mut file = match sftp_connexion.open(restore_path).await{
+        Ok(f)=>f,
+        Err(_)=>return Err(APIError::NoFile)
+    };
+    let size = match file.metadata().await{
+        Ok(metadata)=>metadata.len().unwrap_or(0),
+        Err(_)=>{
+            println!("Erreur métadonnées de la restauration {}", restore_id);
+            return Err(APIError::Metadata)
+        }
+    };
+    Ok((file, size, file_name))
+}
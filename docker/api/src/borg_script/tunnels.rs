@@ -0,0 +1,45 @@
+use openssh::Session;
+use serde::Serialize;
+use std::sync::Arc;
+use crate::error::APIError;
+const SESSIONS_SCRIPT: &str = "/usr/local/sbin/tunnel_sessions.sh";
+const KILL_SCRIPT: &str = "/usr/local/sbin/kill_tunnel.sh";
+
+/// Session sshd de l'utilisateur tunnel qui écoute sur un port de reverse tunnel
+#[derive(Debug, Serialize)]
+pub struct TunnelSession{
+    pub port: u16,
+    pub pid: u32
+}
+
+/// Reverse tunnels actuellement ouverts sur le serveur borg
+pub async fn tunnel_sessions(ssh_connexion: Arc<Session>)->Result<Vec<TunnelSession>, APIError>{
+    let output = match ssh_connexion.command("sudo").arg(SESSIONS_SCRIPT).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if ! output.status.success(){
+        println!("Erreur lors de la liste des tunnels\nstdout {}\n stderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+        return Err(APIError::Script)
+    }
+    // Une ligne par session : "<port> <pid>"
+    Ok(stdout.lines().filter_map(|line| {
+        let (port, pid) = line.trim().split_once(' ')?;
+        Some(TunnelSession{port: port.parse().ok()?, pid: pid.parse().ok()?})
+    }).collect())
+}
+
+/// Ferme la session qui tient le port, renvoie false s'il n'y en avait pas
+pub async fn kill_tunnel(port: u16, ssh_connexion: Arc<Session>)->Result<bool, APIError>{
+    let output = match ssh_connexion.command("sudo").args([KILL_SCRIPT, &port.to_string()]).output().await{
+        Ok(o)=>o,
+        Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if ! output.status.success(){
+        println!("Erreur lors de la fermeture du tunnel sur le port {}\nstdout {}\n stderr: {}", port, stdout, String::from_utf8_lossy(&output.stderr));
+        return Err(APIError::Script)
+    }
+    Ok(!stdout.trim().starts_with("NOT_FOUND"))
+}
@@ -0,0 +1,31 @@
+use sqlx::MySqlPool;
+use std::env;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Noms d'utilisateurs administrateurs, séparés par des virgules dans ADMIN_USERS (aucun si absent)
+fn admin_usernames()->Vec<String>{
+    env::var("ADMIN_USERS").unwrap_or_default()
+    .split(',')
+    .map(|username| username.trim().to_string())
+    .filter(|username| !username.is_empty())
+    .collect()
+}
+
+/// Erreur NotAdmin si l'utilisateur n'est pas déclaré dans ADMIN_USERS
+pub async fn require_admin(db: &MySqlPool, user_id: &str)->Result<(), APIError>{
+    let admins = admin_usernames();
+    if admins.is_empty(){
+        return Err(APIError::NotAdmin)
+    }
+    let username: Option<(String,)> = sqlx::query_as("SELECT username FROM Credentials WHERE id=?")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    match username{
+        Some((username,)) if admins.contains(&username)=>Ok(()),
+        _=>{
+            println!("Accès administrateur refusé pour l'utilisateur : {}", user_id);
+            Err(APIError::NotAdmin)
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Statistiques d'une archive comparées à l'historique (table ArchiveAnalyses)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ArchiveAnalysis{
+    pub archive_name: String,
+    pub nfiles: u64,
+    pub original_size: u64,
+    pub deduplicated_size: u64,
+    /// Fichiers ajoutés, modifiés et supprimés depuis l'archive précédente
+    pub added: u64,
+    pub modified: u64,
+    pub removed: u64,
+    pub suspicious: bool,
+    /// Raisons de l'alerte séparées par des ;
+    pub reasons: Option<String>,
+    pub analyzed_at: u64
+}
+
+/// Noms des archives déjà analysées
+pub async fn analyzed_archives(db: &MySqlPool, user_id: &str)->Result<Vec<String>, APIError>{
+    let names: Vec<(String,)> = sqlx::query_as("SELECT archive_name FROM ArchiveAnalyses WHERE user_id=?")
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(names.into_iter().map(|(name,)| name).collect())
+}
+
+/// Dernières archives saines, référence pour les comparaisons
+pub async fn healthy_history(db: &MySqlPool, user_id: &str, limit: u32)->Result<Vec<ArchiveAnalysis>, APIError>{
+    sqlx::query_as("SELECT archive_name, nfiles, original_size, deduplicated_size, added, modified, removed, suspicious, reasons, analyzed_at \
+    FROM ArchiveAnalyses WHERE user_id=? AND suspicious=0 ORDER BY analyzed_at DESC LIMIT ?")
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Archives signalées comme suspectes, la plus récente en premier
+pub async fn suspicious_archives(db: &MySqlPool, user_id: &str, limit: u32)->Result<Vec<ArchiveAnalysis>, APIError>{
+    sqlx::query_as("SELECT archive_name, nfiles, original_size, deduplicated_size, added, modified, removed, suspicious, reasons, analyzed_at \
+    FROM ArchiveAnalyses WHERE user_id=? AND suspicious=1 ORDER BY analyzed_at DESC LIMIT ?")
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+pub async fn save_analysis(db: &MySqlPool, user_id: &str, analysis: &ArchiveAnalysis)->Result<(), APIError>{
+    sqlx::query("INSERT INTO ArchiveAnalyses (user_id, archive_name, nfiles, original_size, deduplicated_size, added, modified, removed, suspicious, reasons, analyzed_at) \
+    VALUES(?,?,?,?,?,?,?,?,?,?,?)")
+    .bind(user_id)
+    .bind(&analysis.archive_name)
+    .bind(analysis.nfiles)
+    .bind(analysis.original_size)
+    .bind(analysis.deduplicated_size)
+    .bind(analysis.added)
+    .bind(analysis.modified)
+    .bind(analysis.removed)
+    .bind(analysis.suspicious)
+    .bind(&analysis.reasons)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
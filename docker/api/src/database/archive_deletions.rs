@@ -0,0 +1,24 @@
+use jsonwebtoken::get_current_timestamp;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+pub const DELETED: &str = "deleted";
+pub const LOCKED: &str = "locked";
+pub const WRONG_PASSWORD: &str = "wrong_password";
+pub const FAILED: &str = "failed";
+/// Supprimée par la politique de rétention
+pub const PRUNED: &str = "pruned";
+
+/// Trace une demande de suppression d'archive, qu'elle ait abouti ou non.
+/// deleted_archives contient les archives réellement supprimées (l'archive et son archive _logs).
+pub async fn record_deletion(db: &MySqlPool, user_id: &str, archive_name: &str, status: &str, deleted_archives: Option<&[String]>)->Result<(), APIError>{
+    sqlx::query("INSERT INTO ArchiveDeletions (user_id, archive_name, status, deleted_archives, created_at) VALUES(?,?,?,?,?)")
+    .bind(user_id)
+    .bind(archive_name)
+    .bind(status)
+    .bind(deleted_archives.map(|archives| archives.join(",")))
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
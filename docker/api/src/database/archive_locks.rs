@@ -0,0 +1,47 @@
+use jsonwebtoken::get_current_timestamp;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Suffixe de l'archive de logs qui accompagne chaque archive de données
+const LOGS_SUFFIX: &str = "_logs";
+
+/// Vrai si l'archive ou son archive compagne est sous verrou de rétention (sans date de fin ou pas encore expiré).
+/// Une archive et son archive _logs sont supprimées ensemble, le verrou de l'une protège l'autre.
+pub async fn is_locked(db: &MySqlPool, user_id: &str, archive_name: &str)->Result<bool, APIError>{
+    let data_archive = archive_name.strip_suffix(LOGS_SUFFIX).unwrap_or(archive_name);
+    let logs_archive = format!("{}{}", data_archive, LOGS_SUFFIX);
+    let lock: Option<(String,)> = sqlx::query_as("SELECT archive_name FROM ArchiveLocks \
+    WHERE user_id=? AND archive_name IN (?,?) AND (locked_until IS NULL OR locked_until > ?) LIMIT 1")
+    .bind(user_id)
+    .bind(data_archive)
+    .bind(&logs_archive)
+    .bind(get_current_timestamp())
+    .fetch_optional(db).await.map_err(database_error)?;
+    Ok(lock.is_some())
+}
+
+/// Archives de l'utilisateur actuellement sous verrou de rétention
+pub async fn locked_archives(db: &MySqlPool, user_id: &str)->Result<Vec<String>, APIError>{
+    let locks: Vec<(String,)> = sqlx::query_as("SELECT archive_name FROM ArchiveLocks \
+    WHERE user_id=? AND (locked_until IS NULL OR locked_until > ?)")
+    .bind(user_id)
+    .bind(get_current_timestamp())
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(locks.into_iter().map(|(archive_name,)| archive_name).collect())
+}
+
+/// Verrouille les archives jusqu'à locked_until. Un verrou existant n'est jamais raccourci.
+pub async fn lock_archives(db: &MySqlPool, user_id: &str, archive_names: &[String], locked_until: u64, reason: &str)->Result<(), APIError>{
+    for archive_name in archive_names{
+        sqlx::query("INSERT INTO ArchiveLocks (user_id, archive_name, reason, locked_until, created_at) VALUES(?,?,?,?,?) \
+        ON DUPLICATE KEY UPDATE locked_until=IF(locked_until IS NULL, NULL, GREATEST(locked_until, VALUES(locked_until)))")
+        .bind(user_id)
+        .bind(archive_name)
+        .bind(reason)
+        .bind(locked_until)
+        .bind(get_current_timestamp())
+        .execute(db).await.map_err(database_error)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+pub const SUCCESS: &str = "success";
+/// borg a terminé avec des avertissements (code 1) ou des fichiers en erreur
+pub const WARNING: &str = "warning";
+pub const ERROR: &str = "error";
+
+/// Rapport envoyé par le client à la fin d'une sauvegarde
+#[derive(Debug, Deserialize)]
+pub struct BackupReport{
+    pub archive_name: String,
+    #[serde(default)]
+    pub archive_id: Option<String>,
+    /// Code de retour de borg create : 0 succès, 1 avertissements, 2 erreur
+    pub exit_code: i32,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    /// Durée en secondes
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub stats: BackupStats,
+    #[serde(default)]
+    pub files: Vec<FileChange>,
+    #[serde(default)]
+    pub warnings: Vec<String>
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BackupStats{
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub deduplicated_size: u64,
+    pub nfiles: u64
+}
+
+/// Fichier ajouté (A), modifié (M), supprimé (D) ou en erreur (E)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileChange{
+    pub status: String,
+    pub path: String
+}
+
+/// Résumé d'un rapport pour le tableau de bord, sans la liste des fichiers
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BackupReportSummary{
+    pub archive_name: String,
+    pub archive_id: Option<String>,
+    pub status: String,
+    pub exit_code: i32,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub duration: f64,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub deduplicated_size: u64,
+    pub nfiles: u64,
+    pub count_added: u32,
+    pub count_modified: u32,
+    pub count_deleted: u32,
+    pub count_error: u32,
+    pub count_warning: u32,
+    pub created_at: u64
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupReportDetail{
+    #[serde(flatten)]
+    pub summary: BackupReportSummary,
+    pub files: Vec<FileChange>,
+    pub warnings: Vec<String>
+}
+
+const SUMMARY_COLUMNS: &str = "archive_name, archive_id, status, exit_code, start_time, end_time, duration, original_size, compressed_size, deduplicated_size, nfiles, \
+count_added, count_modified, count_deleted, count_error, count_warning, created_at";
+
+fn count_status(files: &[FileChange], status: &str)->u32{
+    files.iter().filter(|file| file.status == status).count() as u32
+}
+
+/// Enregistre le rapport, un rapport renvoyé pour la même archive remplace le précédent
+pub async fn save_report(db: &MySqlPool, user_id: &str, report: &BackupReport)->Result<(), APIError>{
+    let count_error = count_status(&report.files, "E");
+    let status = match report.exit_code{
+        0 if count_error == 0 => SUCCESS,
+        0 | 1 => WARNING,
+        _ => ERROR
+    };
+    let Ok(files) = serde_json::to_string(&report.files) else{
+        return Err(APIError::Json)
+    };
+    let Ok(warnings) = serde_json::to_string(&report.warnings) else{
+        return Err(APIError::Json)
+    };
+    sqlx::query("INSERT INTO BackupReports (user_id, archive_name, archive_id, status, exit_code, start_time, end_time, duration, original_size, compressed_size, deduplicated_size, nfiles, \
+    count_added, count_modified, count_deleted, count_error, count_warning, files, warnings, created_at) VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?) \
+    ON DUPLICATE KEY UPDATE archive_id=VALUES(archive_id), status=VALUES(status), exit_code=VALUES(exit_code), start_time=VALUES(start_time), end_time=VALUES(end_time), duration=VALUES(duration), \
+    original_size=VALUES(original_size), compressed_size=VALUES(compressed_size), deduplicated_size=VALUES(deduplicated_size), nfiles=VALUES(nfiles), \
+    count_added=VALUES(count_added), count_modified=VALUES(count_modified), count_deleted=VALUES(count_deleted), count_error=VALUES(count_error), \
+    count_warning=VALUES(count_warning), files=VALUES(files), warnings=VALUES(warnings), created_at=VALUES(created_at)")
+    .bind(user_id)
+    .bind(&report.archive_name)
+    .bind(&report.archive_id)
+    .bind(status)
+    .bind(report.exit_code)
+    .bind(&report.start_time)
+    .bind(&report.end_time)
+    .bind(report.duration)
+    .bind(report.stats.original_size)
+    .bind(report.stats.compressed_size)
+    .bind(report.stats.deduplicated_size)
+    .bind(report.stats.nfiles)
+    .bind(count_status(&report.files, "A"))
+    .bind(count_status(&report.files, "M"))
+    .bind(count_status(&report.files, "D"))
+    .bind(count_error)
+    .bind(report.warnings.len() as u32)
+    .bind(files)
+    .bind(warnings)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Derniers rapports de l'utilisateur, le plus récent en premier
+/// device limite aux archives <device>@<date> de cet appareil
+pub async fn list_reports(db: &MySqlPool, user_id: &str, device: Option<&str>, limit: u32)->Result<Vec<BackupReportSummary>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM BackupReports WHERE user_id=? AND (? IS NULL OR LEFT(archive_name, CHAR_LENGTH(?)+1)=CONCAT(?, '@')) \
+    ORDER BY created_at DESC, id DESC LIMIT ?", SUMMARY_COLUMNS))
+    .bind(user_id)
+    .bind(device)
+    .bind(device)
+    .bind(device)
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+pub async fn get_report(db: &MySqlPool, user_id: &str, archive_name: &str)->Result<BackupReportDetail, APIError>{
+    let summary: Option<BackupReportSummary> = sqlx::query_as(&format!("SELECT {} FROM BackupReports WHERE user_id=? AND archive_name=?", SUMMARY_COLUMNS))
+    .bind(user_id)
+    .bind(archive_name)
+    .fetch_optional(db).await.map_err(database_error)?;
+    let Some(summary) = summary else{
+        return Err(APIError::NoFile)
+    };
+    let (files, warnings): (String, String) = sqlx::query_as("SELECT files, warnings FROM BackupReports WHERE user_id=? AND archive_name=?")
+    .bind(user_id)
+    .bind(archive_name)
+    .fetch_one(db).await.map_err(database_error)?;
+    let (Ok(files), Ok(warnings)) = (serde_json::from_str(&files), serde_json::from_str(&warnings)) else{
+        return Err(APIError::Json)
+    };
+    Ok(BackupReportDetail{summary, files, warnings})
+}
+
+/// Dernier rapport reçu, pour la détection des sauvegardes en échec
+#[derive(Debug, sqlx::FromRow)]
+pub struct LastReport{
+    pub id: u64,
+    pub archive_name: String,
+    pub status: String,
+    pub exit_code: i32
+}
+
+pub async fn last_report(db: &MySqlPool, user_id: &str)->Result<Option<LastReport>, APIError>{
+    sqlx::query_as("SELECT id, archive_name, status, exit_code FROM BackupReports WHERE user_id=? ORDER BY created_at DESC, id DESC LIMIT 1")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+/// Date du dernier rapport d'une sauvegarde qui a produit une archive
+pub async fn last_successful_report_at(db: &MySqlPool, user_id: &str)->Result<Option<u64>, APIError>{
+    let (created_at,): (Option<u64>,) = sqlx::query_as("SELECT MAX(created_at) FROM BackupReports WHERE user_id=? AND status<>?")
+    .bind(user_id)
+    .bind(ERROR)
+    .fetch_one(db).await.map_err(database_error)?;
+    Ok(created_at)
+}
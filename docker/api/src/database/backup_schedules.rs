@@ -0,0 +1,91 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Fréquence de sauvegarde attendue et destinataires des alertes (table BackupSchedules)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(default)]
+pub struct BackupSchedule{
+    /// Une alerte part si aucune sauvegarde n'a eu lieu depuis ce nombre d'heures
+    pub interval_hours: u32,
+    pub enabled: bool,
+    pub email: Option<String>,
+    /// Date de la dernière archive vue par list_archive
+    #[serde(skip_deserializing)]
+    pub last_archive_at: Option<u64>
+}
+
+impl Default for BackupSchedule {
+    fn default()->Self{
+        BackupSchedule{interval_hours: 24, enabled: false, email: None, last_archive_at: None}
+    }
+}
+
+/// Planning suivi par la vérification périodique
+#[derive(Debug, sqlx::FromRow)]
+pub struct MonitoredSchedule{
+    pub user_id: String,
+    pub interval_hours: u32,
+    pub email: Option<String>,
+    pub last_archive_at: Option<u64>,
+    /// Dernière sauvegarde connue pour laquelle l'alerte de retard est déjà partie
+    pub missed_alert_for: Option<u64>,
+    /// Dernier rapport en échec déjà signalé
+    pub failed_alert_for: Option<u64>,
+    pub created_at: u64
+}
+
+/// Planning de l'utilisateur, un planning désactivé s'il n'en a pas
+pub async fn get_schedule(db: &MySqlPool, user_id: &str)->Result<BackupSchedule, APIError>{
+    let schedule: Option<BackupSchedule> = sqlx::query_as("SELECT interval_hours, enabled, email, last_archive_at FROM BackupSchedules WHERE user_id=?")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    Ok(schedule.unwrap_or_default())
+}
+
+pub async fn save_schedule(db: &MySqlPool, user_id: &str, schedule: &BackupSchedule)->Result<(), APIError>{
+    let now = get_current_timestamp();
+    sqlx::query("INSERT INTO BackupSchedules (user_id, interval_hours, enabled, email, created_at, updated_at) VALUES(?,?,?,?,?,?) \
+    ON DUPLICATE KEY UPDATE interval_hours=VALUES(interval_hours), enabled=VALUES(enabled), email=VALUES(email), updated_at=VALUES(updated_at)")
+    .bind(user_id)
+    .bind(schedule.interval_hours)
+    .bind(schedule.enabled)
+    .bind(&schedule.email)
+    .bind(now)
+    .bind(now)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Ne fait jamais reculer la date connue
+pub async fn record_last_archive(db: &MySqlPool, user_id: &str, archive_at: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE BackupSchedules SET last_archive_at=GREATEST(COALESCE(last_archive_at, 0), ?) WHERE user_id=?")
+    .bind(archive_at)
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn list_enabled(db: &MySqlPool)->Result<Vec<MonitoredSchedule>, APIError>{
+    sqlx::query_as("SELECT user_id, interval_hours, email, last_archive_at, missed_alert_for, failed_alert_for, created_at \
+    FROM BackupSchedules WHERE enabled=1")
+    .fetch_all(db).await.map_err(database_error)
+}
+
+pub async fn set_missed_alert_for(db: &MySqlPool, user_id: &str, last_backup_at: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE BackupSchedules SET missed_alert_for=? WHERE user_id=?")
+    .bind(last_backup_at)
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn set_failed_alert_for(db: &MySqlPool, user_id: &str, report_id: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE BackupSchedules SET failed_alert_for=? WHERE user_id=?")
+    .bind(report_id)
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+pub const QUEUED: &str = "queued";
+pub const RUNNING: &str = "running";
+/// Dépôt sain
+pub const HEALTHY: &str = "healthy";
+/// borg check a trouvé des erreurs
+pub const CORRUPTED: &str = "corrupted";
+/// Vérification impossible ou interrompue
+pub const FAILED: &str = "failed";
+
+/// Le rapport est stocké dans une colonne text (64 Kio)
+const MAX_OUTPUT_LEN: usize = 60_000;
+
+pub const MANUAL: &str = "manual";
+pub const SCHEDULED: &str = "scheduled";
+
+/// Vérification d'un dépôt (table CheckRuns)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CheckRun{
+    pub id: u64,
+    /// repository ou verify_data
+    pub mode: String,
+    /// manual ou scheduled
+    pub triggered_by: String,
+    /// queued, running, healthy, corrupted ou failed
+    pub status: String,
+    /// Rapport de borg check, absent de la liste des vérifications
+    pub output: Option<String>,
+    pub created_at: u64,
+    pub finished_at: Option<u64>
+}
+
+pub async fn insert_run(db: &MySqlPool, user_id: &str, mode: &str, triggered_by: &str)->Result<u64, APIError>{
+    let result = sqlx::query("INSERT INTO CheckRuns (user_id, mode, triggered_by, status, created_at) VALUES(?,?,?,?,?)")
+    .bind(user_id)
+    .bind(mode)
+    .bind(triggered_by)
+    .bind(QUEUED)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+pub async fn set_running(db: &MySqlPool, run_id: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE CheckRuns SET status=? WHERE id=?")
+    .bind(RUNNING)
+    .bind(run_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn finish_run(db: &MySqlPool, run_id: u64, status: &str, output: &str)->Result<(), APIError>{
+    // La fin du rapport contient le résumé de borg, c'est elle qui est gardée
+    let mut start = output.len().saturating_sub(MAX_OUTPUT_LEN);
+    while !output.is_char_boundary(start){
+        start += 1;
+    }
+    sqlx::query("UPDATE CheckRuns SET status=?, output=?, finished_at=? WHERE id=?")
+    .bind(status)
+    .bind(&output[start..])
+    .bind(get_current_timestamp())
+    .bind(run_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn get_run(db: &MySqlPool, user_id: &str, run_id: u64)->Result<CheckRun, APIError>{
+    let run: Option<CheckRun> = sqlx::query_as("SELECT id, mode, triggered_by, status, output, created_at, finished_at \
+    FROM CheckRuns WHERE id=? AND user_id=?")
+    .bind(run_id)
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    run.ok_or(APIError::NoFile)
+}
+
+/// Dernières vérifications, sans leur rapport
+pub async fn list_runs(db: &MySqlPool, user_id: &str, limit: u32)->Result<Vec<CheckRun>, APIError>{
+    sqlx::query_as("SELECT id, mode, triggered_by, status, NULL AS output, created_at, finished_at \
+    FROM CheckRuns WHERE user_id=? ORDER BY id DESC LIMIT ?")
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Au démarrage de l'API, les vérifications en cours ne reprendront jamais
+pub async fn fail_interrupted_runs(db: &MySqlPool)->Result<u64, APIError>{
+    let result = sqlx::query("UPDATE CheckRuns SET status=?, output=?, finished_at=? WHERE status IN (?,?)")
+    .bind(FAILED)
+    .bind("interrompue par un redémarrage de l'API")
+    .bind(get_current_timestamp())
+    .bind(QUEUED)
+    .bind(RUNNING)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected())
+}
+
+/// Identifiants des utilisateurs à vérifier par la planification
+pub async fn list_user_ids(db: &MySqlPool)->Result<Vec<String>, APIError>{
+    let ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM Credentials")
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
@@ -0,0 +1,66 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Ordinateur sauvegardé dans le dépôt du compte (table Devices)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Device{
+    pub id: u64,
+    /// Préfixe des archives de l'appareil : <name>@<date>
+    pub name: String,
+    /// Empreintes des dernières clés SSH actives de l'appareil (table SshKeys)
+    pub borg_key_fingerprint: Option<String>,
+    pub tunnel_key_fingerprint: Option<String>,
+    pub created_at: u64
+}
+
+const DEVICE_COLUMNS: &str = "d.id, d.name, \
+    (SELECT k.fingerprint FROM SshKeys k WHERE k.device_id=d.id AND k.purpose='borg' AND k.revoked_at IS NULL ORDER BY k.created_at DESC, k.id DESC LIMIT 1) AS borg_key_fingerprint, \
+    (SELECT k.fingerprint FROM SshKeys k WHERE k.device_id=d.id AND k.purpose='tunnel' AND k.revoked_at IS NULL ORDER BY k.created_at DESC, k.id DESC LIMIT 1) AS tunnel_key_fingerprint, \
+    d.created_at";
+
+/// Nom utilisable dans un nom d'archive borg et dans les scripts
+pub fn valid_device_name(name: &str)->bool{
+    !name.is_empty() && name.len() <= 32 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Enregistre l'appareil, ou renvoie celui qui porte déjà ce nom (réinstallation du client)
+pub async fn register_device(db: &MySqlPool, user_id: &str, name: &str)->Result<Device, APIError>{
+    sqlx::query("INSERT INTO Devices (user_id, name, created_at) VALUES(?,?,?) ON DUPLICATE KEY UPDATE id=id")
+    .bind(user_id)
+    .bind(name)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    sqlx::query_as(&format!("SELECT {} FROM Devices d WHERE d.user_id=? AND d.name=?", DEVICE_COLUMNS))
+    .bind(user_id)
+    .bind(name)
+    .fetch_one(db).await.map_err(database_error)
+}
+
+pub async fn list_devices(db: &MySqlPool, user_id: &str)->Result<Vec<Device>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM Devices d WHERE d.user_id=? ORDER BY d.name", DEVICE_COLUMNS))
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+pub async fn get_device(db: &MySqlPool, user_id: &str, device_id: u64)->Result<Device, APIError>{
+    let device: Option<Device> = sqlx::query_as(&format!("SELECT {} FROM Devices d WHERE d.user_id=? AND d.id=?", DEVICE_COLUMNS))
+    .bind(user_id)
+    .bind(device_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    device.ok_or(APIError::NoFile)
+}
+
+/// Les archives de l'appareil restent dans le dépôt, ses clés doivent être révoquées avant
+pub async fn delete_device(db: &MySqlPool, user_id: &str, device_id: u64)->Result<(), APIError>{
+    let result = sqlx::query("DELETE FROM Devices WHERE user_id=? AND id=?")
+    .bind(user_id)
+    .bind(device_id)
+    .execute(db).await.map_err(database_error)?;
+    if result.rows_affected() == 0{
+        return Err(APIError::NoFile)
+    }
+    Ok(())
+}
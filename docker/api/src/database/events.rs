@@ -0,0 +1,40 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+pub const WARNING: &str = "warning";
+pub const CRITICAL: &str = "critical";
+
+/// Évènement à présenter à l'utilisateur (table Events)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Event{
+    pub id: u64,
+    /// info, warning ou critical
+    pub severity: String,
+    /// Origine de l'évènement, par exemple check_corrupted
+    pub kind: String,
+    pub message: String,
+    pub created_at: u64
+}
+
+pub async fn record_event(db: &MySqlPool, user_id: &str, severity: &str, kind: &str, message: &str)->Result<(), APIError>{
+    println!("Évènement {} {} pour {} : {}", severity, kind, user_id, message);
+    sqlx::query("INSERT INTO Events (user_id, severity, kind, message, created_at) VALUES(?,?,?,?,?)")
+    .bind(user_id)
+    .bind(severity)
+    .bind(kind)
+    .bind(message)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Derniers évènements de l'utilisateur, le plus récent en premier
+pub async fn list_events(db: &MySqlPool, user_id: &str, limit: u32)->Result<Vec<Event>, APIError>{
+    sqlx::query_as("SELECT id, severity, kind, message, created_at FROM Events WHERE user_id=? ORDER BY created_at DESC, id DESC LIMIT ?")
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
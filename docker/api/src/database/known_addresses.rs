@@ -0,0 +1,22 @@
+use jsonwebtoken::get_current_timestamp;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Enregistre l'adresse de connexion (table KnownAddresses).
+/// true si l'adresse est nouvelle alors que l'utilisateur s'était déjà connecté d'ailleurs.
+pub async fn remember_address(db: &MySqlPool, user_id: &str, ip: &str)->Result<bool, APIError>{
+    let (known,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM KnownAddresses WHERE user_id=?")
+    .bind(user_id)
+    .fetch_one(db).await.map_err(database_error)?;
+    let now = get_current_timestamp();
+    let result = sqlx::query("INSERT INTO KnownAddresses (user_id, ip, first_seen, last_seen) VALUES(?,?,?,?) \
+    ON DUPLICATE KEY UPDATE last_seen=VALUES(last_seen)")
+    .bind(user_id)
+    .bind(ip)
+    .bind(now)
+    .bind(now)
+    .execute(db).await.map_err(database_error)?;
+    // MariaDB compte 1 ligne pour une insertion et 2 pour une mise à jour
+    Ok(result.rows_affected() == 1 && known > 0)
+}
@@ -0,0 +1,42 @@
+use jsonwebtoken::get_current_timestamp;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Ids des archives dont les logs sont en cache
+pub async fn cached_archive_ids(db: &MySqlPool, user_id: &str)->Result<Vec<String>, APIError>{
+    let ids: Vec<(String,)> = sqlx::query_as("SELECT archive_id FROM LogCache WHERE user_id=?")
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Contenu d'un fichier de logs déjà lu, indexé par l'id borg de l'archive
+pub async fn get_cached_log(db: &MySqlPool, user_id: &str, archive_id: &str)->Result<Option<String>, APIError>{
+    let content: Option<(String,)> = sqlx::query_as("SELECT content FROM LogCache WHERE user_id=? AND archive_id=?")
+    .bind(user_id)
+    .bind(archive_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    Ok(content.map(|(content,)| content))
+}
+
+pub async fn save_log(db: &MySqlPool, user_id: &str, archive_id: &str, archive_name: &str, content: &str)->Result<(), APIError>{
+    sqlx::query("INSERT INTO LogCache (user_id, archive_id, archive_name, content, created_at) VALUES(?,?,?,?,?) \
+    ON DUPLICATE KEY UPDATE archive_name=VALUES(archive_name), content=VALUES(content), created_at=VALUES(created_at)")
+    .bind(user_id)
+    .bind(archive_id)
+    .bind(archive_name)
+    .bind(content)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Les archives supprimées ou élaguées n'ont plus besoin de leurs logs en cache
+pub async fn forget_log(db: &MySqlPool, user_id: &str, archive_id: &str)->Result<(), APIError>{
+    sqlx::query("DELETE FROM LogCache WHERE user_id=? AND archive_id=?")
+    .bind(user_id)
+    .bind(archive_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
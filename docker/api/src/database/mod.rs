@@ -0,0 +1,27 @@
+use crate::error::APIError;
+pub mod restore_jobs;
+pub mod archive_locks;
+pub mod archive_deletions;
+pub mod retention_policies;
+pub mod quotas;
+pub mod check_runs;
+pub mod events;
+pub mod archive_analyses;
+pub mod log_cache;
+pub mod backup_reports;
+pub mod backup_schedules;
+pub mod webhooks;
+pub mod known_addresses;
+pub mod devices;
+pub mod ssh_keys;
+pub mod tunnel_leases;
+pub mod admins;
+pub mod organisations;
+pub mod share_links;
+pub mod restore_purges;
+
+/// Les erreurs sqlx sont affichées dans les logs, le client ne reçoit que APIError::Database
+fn database_error(e: sqlx::Error)->APIError{
+    println!("Erreur base de données : {}", e);
+    APIError::Database
+}
@@ -0,0 +1,276 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use crate::authentification::auth::WrappedKeys;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Rôle d'un membre, chaque rôle a les droits du précédent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role{
+    /// Liste les archives, les logs et l'usage du dépôt
+    Viewer,
+    /// Télécharge et restaure des fichiers
+    Restorer,
+    /// Gère les clés SSH, les appareils, la rétention et les membres
+    Owner
+}
+
+impl Role{
+    pub fn as_str(&self)->&'static str{
+        match self{
+            Role::Viewer=>"viewer",
+            Role::Restorer=>"restorer",
+            Role::Owner=>"owner"
+        }
+    }
+
+    pub fn parse(role: &str)->Option<Role>{
+        match role{
+            "viewer"=>Some(Role::Viewer),
+            "restorer"=>Some(Role::Restorer),
+            "owner"=>Some(Role::Owner),
+            _=>None
+        }
+    }
+}
+
+/// Organisation vue par un de ses membres (tables Organisations et OrganisationMembers)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Organisation{
+    pub id: u64,
+    pub name: String,
+    /// Dépôt partagé : celui du fondateur, à passer en paramètre repository des routes
+    pub repository_id: String,
+    /// Rôle de l'utilisateur dans l'organisation
+    pub role: String,
+    pub created_at: u64
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Member{
+    pub user_id: String,
+    pub username: String,
+    pub role: String,
+    pub created_at: u64
+}
+
+/// Appartenance d'un utilisateur à l'organisation qui partage un dépôt
+#[derive(Debug, sqlx::FromRow)]
+pub struct Membership{
+    pub repository_id: String,
+    pub role: String,
+    /// Clés du dépôt chiffrées avec le kdf du membre, NULL pour le fondateur (clés dans Credentials)
+    pub encrypt_master_key_1: Option<String>,
+    pub encrypt_master_key_2: Option<String>
+}
+
+/// Invitation en attente, le jeton n'est connu que de son destinataire
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Invitation{
+    pub id: u64,
+    pub role: String,
+    pub created_by: String,
+    pub created_at: u64,
+    pub expires_at: u64
+}
+
+/// Invitation retrouvée par son jeton, avec les clés du dépôt chiffrées par le jeton
+#[derive(Debug, sqlx::FromRow)]
+pub struct PendingInvitation{
+    pub id: u64,
+    pub organisation_id: u64,
+    pub role: String,
+    pub encrypt_master_key_1: String,
+    pub encrypt_master_key_2: String
+}
+
+const MEMBERSHIP_COLUMNS: &str = "o.repository_id, m.role, m.encrypt_master_key_1, m.encrypt_master_key_2";
+
+/// Nom affiché de l'organisation
+pub fn valid_organisation_name(name: &str)->bool{
+    !name.trim().is_empty() && name.chars().count() <= 64 && !name.chars().any(char::is_control)
+}
+
+/// Crée l'organisation autour du dépôt du fondateur, qui en devient owner. Erreur AlreadyExist si le dépôt est déjà partagé
+pub async fn create_organisation(db: &MySqlPool, name: &str, repository_id: &str)->Result<u64, APIError>{
+    let now = get_current_timestamp();
+    let mut transaction = db.begin().await.map_err(database_error)?;
+    let result = match sqlx::query("INSERT INTO Organisations (name, repository_id, created_at) VALUES(?,?,?)")
+    .bind(name)
+    .bind(repository_id)
+    .bind(now)
+    .execute(&mut *transaction).await{
+        Ok(result)=>result,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation()=>return Err(APIError::AlreadyExist),
+        Err(e)=>return Err(database_error(e))
+    };
+    let organisation_id = result.last_insert_id();
+    sqlx::query("INSERT INTO OrganisationMembers (organisation_id, user_id, role, created_at) VALUES(?,?,?,?)")
+    .bind(organisation_id)
+    .bind(repository_id)
+    .bind(Role::Owner.as_str())
+    .bind(now)
+    .execute(&mut *transaction).await.map_err(database_error)?;
+    transaction.commit().await.map_err(database_error)?;
+    Ok(organisation_id)
+}
+
+pub async fn list_organisations(db: &MySqlPool, user_id: &str)->Result<Vec<Organisation>, APIError>{
+    sqlx::query_as("SELECT o.id, o.name, o.repository_id, m.role, o.created_at \
+    FROM OrganisationMembers m JOIN Organisations o ON o.id=m.organisation_id WHERE m.user_id=? ORDER BY o.name")
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+pub async fn delete_organisation(db: &MySqlPool, organisation_id: u64)->Result<(), APIError>{
+    sqlx::query("DELETE FROM Organisations WHERE id=?")
+    .bind(organisation_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Dépôts des autres utilisateurs partagés avec l'utilisateur, pour les travaux lancés à sa connexion
+pub async fn shared_memberships(db: &MySqlPool, user_id: &str)->Result<Vec<Membership>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM OrganisationMembers m JOIN Organisations o ON o.id=m.organisation_id \
+    WHERE m.user_id=? AND o.repository_id<>m.user_id", MEMBERSHIP_COLUMNS))
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// None si l'utilisateur n'est pas membre de l'organisation qui partage ce dépôt
+pub async fn membership_by_repository(db: &MySqlPool, repository_id: &str, user_id: &str)->Result<Option<Membership>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM OrganisationMembers m JOIN Organisations o ON o.id=m.organisation_id \
+    WHERE o.repository_id=? AND m.user_id=?", MEMBERSHIP_COLUMNS))
+    .bind(repository_id)
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+pub async fn membership_by_organisation(db: &MySqlPool, organisation_id: u64, user_id: &str)->Result<Option<Membership>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM OrganisationMembers m JOIN Organisations o ON o.id=m.organisation_id \
+    WHERE m.organisation_id=? AND m.user_id=?", MEMBERSHIP_COLUMNS))
+    .bind(organisation_id)
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+pub async fn list_members(db: &MySqlPool, organisation_id: u64)->Result<Vec<Member>, APIError>{
+    sqlx::query_as("SELECT m.user_id, c.username, m.role, m.created_at \
+    FROM OrganisationMembers m JOIN Credentials c ON c.id=m.user_id WHERE m.organisation_id=? ORDER BY c.username")
+    .bind(organisation_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Renvoie false si l'utilisateur n'est pas membre
+pub async fn set_member_role(db: &MySqlPool, organisation_id: u64, user_id: &str, role: Role)->Result<bool, APIError>{
+    let result = sqlx::query("UPDATE OrganisationMembers SET role=? WHERE organisation_id=? AND user_id=?")
+    .bind(role.as_str())
+    .bind(organisation_id)
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Les clés chiffrées pour le membre sont supprimées avec lui. Renvoie false s'il n'était pas membre
+pub async fn remove_member(db: &MySqlPool, organisation_id: u64, user_id: &str)->Result<bool, APIError>{
+    let result = sqlx::query("DELETE FROM OrganisationMembers WHERE organisation_id=? AND user_id=?")
+    .bind(organisation_id)
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Enregistre l'invitation et purge les invitations expirées. Seule l'empreinte du jeton est gardée
+pub async fn create_invitation(db: &MySqlPool, organisation_id: u64, role: Role, token_hash: &str, keys: &WrappedKeys, created_by: &str, expires_at: u64)->Result<u64, APIError>{
+    let now = get_current_timestamp();
+    sqlx::query("DELETE FROM OrganisationInvitations WHERE expires_at<=?")
+    .bind(now)
+    .execute(db).await.map_err(database_error)?;
+    let result = sqlx::query("INSERT INTO OrganisationInvitations \
+    (organisation_id, role, token_hash, encrypt_master_key_1, encrypt_master_key_2, created_by, created_at, expires_at) VALUES(?,?,?,?,?,?,?,?)")
+    .bind(organisation_id)
+    .bind(role.as_str())
+    .bind(token_hash)
+    .bind(&keys.encrypt_master_key_1)
+    .bind(&keys.encrypt_master_key_2)
+    .bind(created_by)
+    .bind(now)
+    .bind(expires_at)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+pub async fn list_invitations(db: &MySqlPool, organisation_id: u64)->Result<Vec<Invitation>, APIError>{
+    sqlx::query_as("SELECT id, role, created_by, created_at, expires_at FROM OrganisationInvitations \
+    WHERE organisation_id=? AND expires_at>? ORDER BY created_at DESC")
+    .bind(organisation_id)
+    .bind(get_current_timestamp())
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Renvoie false si l'invitation n'existe pas
+pub async fn delete_invitation(db: &MySqlPool, organisation_id: u64, invitation_id: u64)->Result<bool, APIError>{
+    let result = sqlx::query("DELETE FROM OrganisationInvitations WHERE organisation_id=? AND id=?")
+    .bind(organisation_id)
+    .bind(invitation_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Invitation non expirée qui correspond à l'empreinte du jeton
+pub async fn find_invitation(db: &MySqlPool, token_hash: &str)->Result<Option<PendingInvitation>, APIError>{
+    sqlx::query_as("SELECT id, organisation_id, role, encrypt_master_key_1, encrypt_master_key_2 \
+    FROM OrganisationInvitations WHERE token_hash=? AND expires_at>?")
+    .bind(token_hash)
+    .bind(get_current_timestamp())
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+/// Consomme l'invitation et ajoute le membre dans la même transaction : un jeton ne sert qu'une fois.
+/// Erreur NoFile si l'invitation vient d'être utilisée, AlreadyExist si l'utilisateur est déjà membre
+pub async fn accept_invitation(db: &MySqlPool, invitation: &PendingInvitation, user_id: &str, keys: &WrappedKeys)->Result<(), APIError>{
+    let mut transaction = db.begin().await.map_err(database_error)?;
+    let deleted = sqlx::query("DELETE FROM OrganisationInvitations WHERE id=?")
+    .bind(invitation.id)
+    .execute(&mut *transaction).await.map_err(database_error)?;
+    if deleted.rows_affected() == 0{
+        return Err(APIError::NoFile)
+    }
+    match sqlx::query("INSERT INTO OrganisationMembers \
+    (organisation_id, user_id, role, encrypt_master_key_1, encrypt_master_key_2, created_at) VALUES(?,?,?,?,?,?)")
+    .bind(invitation.organisation_id)
+    .bind(user_id)
+    .bind(&invitation.role)
+    .bind(&keys.encrypt_master_key_1)
+    .bind(&keys.encrypt_master_key_2)
+    .bind(get_current_timestamp())
+    .execute(&mut *transaction).await{
+        Ok(_)=>(),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation()=>return Err(APIError::AlreadyExist),
+        Err(e)=>return Err(database_error(e))
+    };
+    transaction.commit().await.map_err(database_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn roles_are_ordered_by_rights(){
+        assert!(Role::Viewer < Role::Restorer);
+        assert!(Role::Restorer < Role::Owner);
+    }
+
+    #[test]
+    fn roles_round_trip(){
+        for role in [Role::Viewer, Role::Restorer, Role::Owner]{
+            assert_eq!(Role::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(Role::parse("Owner"), None);
+        assert_eq!(Role::parse("admin"), None);
+    }
+}
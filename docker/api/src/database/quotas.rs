@@ -0,0 +1,76 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use std::env;
+use crate::borg_script::info::RepositoryStats;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Consommation d'un utilisateur comparée à son quota, en octets
+#[derive(Debug, Default, Serialize, sqlx::FromRow)]
+pub struct StorageUsage{
+    /// None : pas de limite
+    pub quota_bytes: Option<u64>,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub deduplicated_size: u64,
+    /// Le client doit refuser les nouvelles sauvegardes
+    pub over_quota: bool,
+    /// Date de la dernière mesure, None si le dépôt n'a jamais été mesuré
+    pub measured_at: Option<u64>
+}
+
+/// Quota appliqué aux utilisateurs sans quota propre (DEFAULT_QUOTA_GB, illimité si absent)
+fn default_quota()->Option<u64>{
+    let value = env::var("DEFAULT_QUOTA_GB").ok()?;
+    match value.parse::<u64>(){
+        Ok(0)=>None,
+        Ok(gigabytes)=>Some(gigabytes*1024*1024*1024),
+        Err(_)=>{
+            println!("DEFAULT_QUOTA_GB invalide : {}", value);
+            None
+        }
+    }
+}
+
+/// Dernière consommation connue
+pub async fn get_usage(db: &MySqlPool, user_id: &str)->Result<StorageUsage, APIError>{
+    let usage: Option<StorageUsage> = sqlx::query_as("SELECT quota_bytes, original_size, compressed_size, deduplicated_size, over_quota, measured_at \
+    FROM Quotas WHERE user_id=?")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    let mut usage = usage.unwrap_or_default();
+    if usage.quota_bytes.is_none(){
+        usage.quota_bytes = default_quota();
+    }
+    Ok(usage)
+}
+
+/// Enregistre une nouvelle mesure et signale l'utilisateur s'il dépasse son quota.
+/// La place réellement occupée (taille dédupliquée) est comparée au quota.
+pub async fn save_usage(db: &MySqlPool, user_id: &str, stats: &RepositoryStats)->Result<StorageUsage, APIError>{
+    let quota_bytes = get_usage(db, user_id).await?.quota_bytes;
+    let over_quota = quota_bytes.is_some_and(|quota| stats.deduplicated_size > quota);
+    let measured_at = get_current_timestamp();
+    sqlx::query("INSERT INTO Quotas (user_id, original_size, compressed_size, deduplicated_size, over_quota, measured_at) VALUES(?,?,?,?,?,?) \
+    ON DUPLICATE KEY UPDATE original_size=VALUES(original_size), compressed_size=VALUES(compressed_size), \
+    deduplicated_size=VALUES(deduplicated_size), over_quota=VALUES(over_quota), measured_at=VALUES(measured_at)")
+    .bind(user_id)
+    .bind(stats.original_size)
+    .bind(stats.compressed_size)
+    .bind(stats.deduplicated_size)
+    .bind(over_quota)
+    .bind(measured_at)
+    .execute(db).await.map_err(database_error)?;
+    if over_quota{
+        println!("Quota dépassé pour {} : {} octets", user_id, stats.deduplicated_size);
+    }
+    Ok(StorageUsage{
+        quota_bytes,
+        original_size: stats.original_size,
+        compressed_size: stats.compressed_size,
+        deduplicated_size: stats.deduplicated_size,
+        over_quota,
+        measured_at: Some(measured_at)
+    })
+}
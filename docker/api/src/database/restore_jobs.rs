@@ -0,0 +1,102 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+pub const QUEUED: &str = "queued";
+pub const RUNNING: &str = "running";
+pub const READY: &str = "ready";
+pub const FAILED: &str = "failed";
+
+/// Restauration lancée en tâche de fond, table RestoreJobs
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RestoreJob{
+    pub job_id: String,
+    pub archive_name: String,
+    /// queued, running, ready ou failed
+    pub status: String,
+    /// Avancement de borg en pourcentage
+    pub progress: f64,
+    /// Identifiant de la restauration préparée, une fois la tâche terminée
+    pub restore_id: Option<String>,
+    pub file_name: Option<String>,
+    pub size: Option<u64>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64
+}
+
+pub async fn insert_job(db: &MySqlPool, job_id: &str, user_id: &str, archive_name: &str)->Result<(), APIError>{
+    let now = get_current_timestamp();
+    sqlx::query("INSERT INTO RestoreJobs (job_id, user_id, archive_name, status, created_at, updated_at) VALUES(?,?,?,?,?,?)")
+    .bind(job_id)
+    .bind(user_id)
+    .bind(archive_name)
+    .bind(QUEUED)
+    .bind(now)
+    .bind(now)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn get_job(db: &MySqlPool, user_id: &str, job_id: &str)->Result<RestoreJob, APIError>{
+    let job: Option<RestoreJob> = sqlx::query_as("SELECT job_id, archive_name, status, progress, restore_id, file_name, size, error, created_at, updated_at \
+    FROM RestoreJobs WHERE job_id=? AND user_id=?")
+    .bind(job_id)
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    job.ok_or(APIError::NoFile)
+}
+
+pub async fn set_status(db: &MySqlPool, job_id: &str, status: &str)->Result<(), APIError>{
+    sqlx::query("UPDATE RestoreJobs SET status=?, updated_at=? WHERE job_id=?")
+    .bind(status)
+    .bind(get_current_timestamp())
+    .bind(job_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn set_progress(db: &MySqlPool, job_id: &str, progress: f64)->Result<(), APIError>{
+    sqlx::query("UPDATE RestoreJobs SET progress=?, updated_at=? WHERE job_id=?")
+    .bind(progress)
+    .bind(get_current_timestamp())
+    .bind(job_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn set_ready(db: &MySqlPool, job_id: &str, restore_id: &str, file_name: &str, size: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE RestoreJobs SET status=?, progress=100, restore_id=?, file_name=?, size=?, updated_at=? WHERE job_id=?")
+    .bind(READY)
+    .bind(restore_id)
+    .bind(file_name)
+    .bind(size)
+    .bind(get_current_timestamp())
+    .bind(job_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn set_failed(db: &MySqlPool, job_id: &str, error: &str)->Result<(), APIError>{
+    sqlx::query("UPDATE RestoreJobs SET status=?, error=?, updated_at=? WHERE job_id=?")
+    .bind(FAILED)
+    .bind(error)
+    .bind(get_current_timestamp())
+    .bind(job_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Au démarrage de l'API, les tâches qui tournaient encore ne reprendront jamais
+pub async fn fail_interrupted_jobs(db: &MySqlPool)->Result<u64, APIError>{
+    let result = sqlx::query("UPDATE RestoreJobs SET status=?, error=?, updated_at=? WHERE status IN (?,?)")
+    .bind(FAILED)
+    .bind("interrompue par un redémarrage de l'API")
+    .bind(get_current_timestamp())
+    .bind(QUEUED)
+    .bind(RUNNING)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected())
+}
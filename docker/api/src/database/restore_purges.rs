@@ -0,0 +1,37 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Passage du nettoyage des restaurations (table RestorePurges)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RestorePurge{
+    pub id: u64,
+    pub ran_at: u64,
+    /// Éléments supprimés
+    pub purged: u32,
+    pub freed_bytes: u64,
+    /// Éléments qui n'ont pas pu être supprimés
+    pub failed: u32,
+    /// Erreur qui a empêché le passage (ssh, script)
+    pub error: Option<String>
+}
+
+pub async fn record_purge(db: &MySqlPool, purged: u32, freed_bytes: u64, failed: u32, error: Option<&str>)->Result<(), APIError>{
+    sqlx::query("INSERT INTO RestorePurges (ran_at, purged, freed_bytes, failed, error) VALUES(?,?,?,?,?)")
+    .bind(get_current_timestamp())
+    .bind(purged)
+    .bind(freed_bytes)
+    .bind(failed)
+    .bind(error)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Derniers passages, le plus récent en premier
+pub async fn list_purges(db: &MySqlPool, limit: u32)->Result<Vec<RestorePurge>, APIError>{
+    sqlx::query_as("SELECT id, ran_at, purged, freed_bytes, failed, error FROM RestorePurges ORDER BY ran_at DESC, id DESC LIMIT ?")
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
@@ -0,0 +1,106 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Au-delà, la règle n'a plus de sens et sert surtout à contourner la validation
+const MAX_KEEP: u32 = 10_000;
+
+/// Règles de rétention d'un utilisateur, mêmes règles que les options --keep-* de borg prune
+#[derive(Debug, Default, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(default)]
+pub struct RetentionPolicy{
+    pub keep_hourly: u32,
+    pub keep_daily: u32,
+    pub keep_weekly: u32,
+    pub keep_monthly: u32,
+    pub keep_yearly: u32,
+    /// Application automatique par l'API
+    pub enabled: bool,
+    #[serde(skip_deserializing)]
+    pub last_applied_at: Option<u64>
+}
+
+impl RetentionPolicy {
+    /// Une politique sans aucune règle supprimerait toutes les archives
+    pub fn keeps_something(&self)->bool{
+        self.rules().iter().any(|keep| *keep > 0)
+    }
+
+    /// Aucune règle au-delà de MAX_KEEP, et au moins une règle si la politique est active
+    pub fn is_valid(&self)->bool{
+        ! self.rules().iter().any(|keep| *keep > MAX_KEEP) && (! self.enabled || self.keeps_something())
+    }
+
+    fn rules(&self)->[u32; 5]{
+        [self.keep_hourly, self.keep_daily, self.keep_weekly, self.keep_monthly, self.keep_yearly]
+    }
+}
+
+/// Politique de l'utilisateur, une politique vide et désactivée s'il n'en a pas
+pub async fn get_policy(db: &MySqlPool, user_id: &str)->Result<RetentionPolicy, APIError>{
+    let policy: Option<RetentionPolicy> = sqlx::query_as("SELECT keep_hourly, keep_daily, keep_weekly, keep_monthly, keep_yearly, enabled, last_applied_at \
+    FROM RetentionPolicies WHERE user_id=?")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    Ok(policy.unwrap_or_default())
+}
+
+/// scheduler_key : clé 2 du dépôt chiffrée pour l'application planifiée, None la supprime
+pub async fn save_policy(db: &MySqlPool, user_id: &str, policy: &RetentionPolicy, scheduler_key: Option<&str>)->Result<(), APIError>{
+    sqlx::query("INSERT INTO RetentionPolicies (user_id, keep_hourly, keep_daily, keep_weekly, keep_monthly, keep_yearly, enabled, encrypt_master_key_2, updated_at) \
+    VALUES(?,?,?,?,?,?,?,?,?) ON DUPLICATE KEY UPDATE keep_hourly=VALUES(keep_hourly), keep_daily=VALUES(keep_daily), keep_weekly=VALUES(keep_weekly), \
+    keep_monthly=VALUES(keep_monthly), keep_yearly=VALUES(keep_yearly), enabled=VALUES(enabled), encrypt_master_key_2=VALUES(encrypt_master_key_2), updated_at=VALUES(updated_at)")
+    .bind(user_id)
+    .bind(policy.keep_hourly)
+    .bind(policy.keep_daily)
+    .bind(policy.keep_weekly)
+    .bind(policy.keep_monthly)
+    .bind(policy.keep_yearly)
+    .bind(policy.enabled)
+    .bind(scheduler_key)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Dépôts dont la politique active n'a pas tourné depuis interval secondes et dont la clé a été confiée au planificateur
+pub async fn due_policies(db: &MySqlPool, interval: u64)->Result<Vec<String>, APIError>{
+    let due: Vec<(String,)> = sqlx::query_as("SELECT user_id FROM RetentionPolicies \
+    WHERE enabled=1 AND encrypt_master_key_2 IS NOT NULL AND (last_applied_at IS NULL OR last_applied_at <= ?)")
+    .bind(get_current_timestamp().saturating_sub(interval))
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(due.into_iter().map(|(user_id,)| user_id).collect())
+}
+
+pub async fn scheduler_key(db: &MySqlPool, user_id: &str)->Result<Option<String>, APIError>{
+    let key: Option<(Option<String>,)> = sqlx::query_as("SELECT encrypt_master_key_2 FROM RetentionPolicies WHERE user_id=?")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    Ok(key.and_then(|(key,)| key))
+}
+
+/// Clé confiée illisible : la politique n'est plus appliquée que par les sessions, jusqu'au prochain enregistrement
+pub async fn forget_scheduler_key(db: &MySqlPool, user_id: &str)->Result<(), APIError>{
+    sqlx::query("UPDATE RetentionPolicies SET encrypt_master_key_2=NULL WHERE user_id=?")
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Réserve l'application de la politique si elle est active et n'a pas tourné depuis interval secondes.
+/// La mise à jour est atomique : deux sessions simultanées ne lancent pas deux fois la rétention.
+pub async fn claim_due_policy(db: &MySqlPool, user_id: &str, interval: u64)->Result<Option<RetentionPolicy>, APIError>{
+    let now = get_current_timestamp();
+    let result = sqlx::query("UPDATE RetentionPolicies SET last_applied_at=? \
+    WHERE user_id=? AND enabled=1 AND (last_applied_at IS NULL OR last_applied_at <= ?)")
+    .bind(now)
+    .bind(user_id)
+    .bind(now.saturating_sub(interval))
+    .execute(db).await.map_err(database_error)?;
+    if result.rows_affected() == 0{
+        return Ok(None)
+    }
+    Ok(Some(get_policy(db, user_id).await?))
+}
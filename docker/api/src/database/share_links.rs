@@ -0,0 +1,193 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Fichier envoyé au détenteur du lien
+pub const SERVED: &str = "served";
+/// Lien expiré, révoqué ou à usage unique déjà utilisé
+pub const EXPIRED: &str = "expired";
+pub const REVOKED: &str = "revoked";
+pub const USED: &str = "used";
+/// Fichier du lien introuvable sur le serveur
+pub const FAILED: &str = "failed";
+
+/// Lien de partage vu par les membres du dépôt, sans le jeton (table ShareLinks)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ShareLink{
+    pub id: u64,
+    pub created_by: String,
+    pub archive_name: String,
+    pub file_name: String,
+    pub single_use: bool,
+    /// Nombre de téléchargements servis
+    pub use_count: u32,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub revoked_at: Option<u64>
+}
+
+/// Lien retrouvé à partir du jeton, avec le fichier préparé à sa création
+#[derive(Debug, sqlx::FromRow)]
+pub struct StoredLink{
+    pub id: u64,
+    /// Dépôt du fichier partagé
+    pub user_id: String,
+    pub archive_name: String,
+    pub file_name: String,
+    /// Fichier dans /srv/repos/<user_id>/shared, NULL une fois supprimé
+    pub restore_id: Option<String>,
+    pub expires_at: u64,
+    pub revoked_at: Option<u64>
+}
+
+/// Lien qui ne peut plus servir dont le fichier est encore sur le serveur
+#[derive(Debug, sqlx::FromRow)]
+pub struct StaleLink{
+    pub id: u64,
+    pub user_id: String,
+    pub restore_id: String
+}
+
+/// Utilisation d'un lien, table ShareLinkRedemptions
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Redemption{
+    pub id: u64,
+    pub redeemed_at: u64,
+    pub ip: String,
+    pub user_agent: Option<String>,
+    /// served, expired, revoked, used ou failed
+    pub status: String
+}
+
+/// Lien à enregistrer, avant la création du jeton qui contient son id
+pub struct NewShareLink<'a>{
+    pub user_id: &'a str,
+    pub created_by: &'a str,
+    pub archive_name: &'a str,
+    pub file_name: &'a str,
+    pub restore_id: &'a str,
+    pub single_use: bool,
+    pub expires_at: u64
+}
+
+const LINK_COLUMNS: &str = "id, created_by, archive_name, file_name, single_use, use_count, created_at, expires_at, revoked_at";
+
+pub async fn insert_link(db: &MySqlPool, link: &NewShareLink<'_>)->Result<u64, APIError>{
+    let result = sqlx::query("INSERT INTO ShareLinks \
+    (user_id, created_by, archive_name, file_name, restore_id, single_use, created_at, expires_at) VALUES(?,?,?,?,?,?,?,?)")
+    .bind(link.user_id)
+    .bind(link.created_by)
+    .bind(link.archive_name)
+    .bind(link.file_name)
+    .bind(link.restore_id)
+    .bind(link.single_use)
+    .bind(get_current_timestamp())
+    .bind(link.expires_at)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+pub async fn list_links(db: &MySqlPool, user_id: &str)->Result<Vec<ShareLink>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM ShareLinks WHERE user_id=? ORDER BY created_at DESC, id DESC", LINK_COLUMNS))
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Erreur NoFile si le lien n'appartient pas au dépôt
+pub async fn get_link(db: &MySqlPool, user_id: &str, link_id: u64)->Result<ShareLink, APIError>{
+    let link: Option<ShareLink> = sqlx::query_as(&format!("SELECT {} FROM ShareLinks WHERE user_id=? AND id=?", LINK_COLUMNS))
+    .bind(user_id)
+    .bind(link_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    link.ok_or(APIError::NoFile)
+}
+
+pub async fn find_link(db: &MySqlPool, link_id: u64)->Result<Option<StoredLink>, APIError>{
+    sqlx::query_as("SELECT id, user_id, archive_name, file_name, restore_id, expires_at, revoked_at FROM ShareLinks WHERE id=?")
+    .bind(link_id)
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+/// Compte une utilisation si le lien est encore valable. Un lien à usage unique ne peut être compté qu'une fois,
+/// même par deux requêtes simultanées. Renvoie false si le lien est expiré, révoqué ou déjà utilisé
+pub async fn claim_use(db: &MySqlPool, link_id: u64)->Result<bool, APIError>{
+    let result = sqlx::query("UPDATE ShareLinks SET use_count=use_count+1 \
+    WHERE id=? AND revoked_at IS NULL AND expires_at>? AND (single_use=0 OR use_count=0)")
+    .bind(link_id)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rend l'utilisation comptée par claim_use quand la restauration a échoué
+pub async fn release_use(db: &MySqlPool, link_id: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE ShareLinks SET use_count=use_count-1 WHERE id=? AND use_count>0")
+    .bind(link_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn revoke_link(db: &MySqlPool, user_id: &str, link_id: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE ShareLinks SET revoked_at=? WHERE user_id=? AND id=? AND revoked_at IS NULL")
+    .bind(get_current_timestamp())
+    .bind(user_id)
+    .bind(link_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Liens créés par un membre qui a perdu l'accès au dépôt (retiré de l'organisation ou passé viewer)
+pub async fn revoke_member_links(db: &MySqlPool, user_id: &str, created_by: &str)->Result<u64, APIError>{
+    let result = sqlx::query("UPDATE ShareLinks SET revoked_at=? WHERE user_id=? AND created_by=? AND revoked_at IS NULL")
+    .bind(get_current_timestamp())
+    .bind(user_id)
+    .bind(created_by)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected())
+}
+
+/// Liens créés par les membres d'une organisation supprimée, le fondateur garde les siens
+pub async fn revoke_shared_links(db: &MySqlPool, user_id: &str)->Result<u64, APIError>{
+    let result = sqlx::query("UPDATE ShareLinks SET revoked_at=? WHERE user_id=? AND created_by<>user_id AND revoked_at IS NULL")
+    .bind(get_current_timestamp())
+    .bind(user_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.rows_affected())
+}
+
+/// Liens expirés, révoqués ou à usage unique déjà servis dont le fichier n'est pas encore supprimé
+pub async fn stale_links(db: &MySqlPool)->Result<Vec<StaleLink>, APIError>{
+    // use_count est compté avant l'ouverture du fichier : seul un téléchargement servi consomme un lien à usage unique
+    sqlx::query_as("SELECT id, user_id, restore_id FROM ShareLinks l \
+    WHERE restore_id IS NOT NULL AND (revoked_at IS NOT NULL OR expires_at<=? OR (single_use=1 AND \
+    EXISTS (SELECT 1 FROM ShareLinkRedemptions r WHERE r.link_id=l.id AND r.status=?)))")
+    .bind(get_current_timestamp())
+    .bind(SERVED)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+pub async fn clear_restore_id(db: &MySqlPool, link_id: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE ShareLinks SET restore_id=NULL WHERE id=?")
+    .bind(link_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn record_redemption(db: &MySqlPool, link_id: u64, ip: &str, user_agent: Option<&str>, status: &str)->Result<(), APIError>{
+    sqlx::query("INSERT INTO ShareLinkRedemptions (link_id, redeemed_at, ip, user_agent, status) VALUES(?,?,?,?,?)")
+    .bind(link_id)
+    .bind(get_current_timestamp())
+    .bind(ip)
+    .bind(user_agent)
+    .bind(status)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn list_redemptions(db: &MySqlPool, link_id: u64)->Result<Vec<Redemption>, APIError>{
+    sqlx::query_as("SELECT id, redeemed_at, ip, user_agent, status FROM ShareLinkRedemptions WHERE link_id=? ORDER BY redeemed_at DESC, id DESC")
+    .bind(link_id)
+    .fetch_all(db).await.map_err(database_error)
+}
@@ -0,0 +1,132 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Clé de l'utilisateur propriétaire du dépôt (borg serve)
+pub const BORG: &str = "borg";
+/// Clé partagée par l'utilisateur tunnel pour le reverse tunnel
+pub const TUNNEL: &str = "tunnel";
+
+/// Clé publique installée sur le serveur borg (table SshKeys)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SshKey{
+    pub id: u64,
+    pub device_id: Option<u64>,
+    pub device_name: Option<String>,
+    /// borg ou tunnel
+    pub purpose: String,
+    pub key_type: String,
+    /// Format de ssh-keygen -l : SHA256:<base64>
+    pub fingerprint: String,
+    pub created_at: u64,
+    pub revoked_at: Option<u64>
+}
+
+/// Clé encore active, acceptée par sshd
+#[derive(Debug, sqlx::FromRow)]
+pub struct ActiveKey{
+    pub id: u64,
+    pub device_id: Option<u64>,
+    pub purpose: String,
+    pub fingerprint: String
+}
+
+/// Clé à enregistrer après son installation
+pub struct NewKey<'a>{
+    pub device_id: Option<u64>,
+    pub purpose: &'static str,
+    pub key_type: &'a str,
+    pub fingerprint: &'a str,
+    pub public_key: &'a str
+}
+
+pub async fn list_keys(db: &MySqlPool, user_id: &str)->Result<Vec<SshKey>, APIError>{
+    sqlx::query_as("SELECT k.id, k.device_id, d.name AS device_name, k.purpose, k.key_type, k.fingerprint, k.created_at, k.revoked_at \
+    FROM SshKeys k LEFT JOIN Devices d ON d.id=k.device_id WHERE k.user_id=? ORDER BY k.revoked_at IS NOT NULL, k.created_at DESC, k.id DESC")
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Erreur NoFile si la clé n'existe pas ou est déjà révoquée
+pub async fn get_active_key(db: &MySqlPool, user_id: &str, key_id: u64)->Result<ActiveKey, APIError>{
+    let key: Option<ActiveKey> = sqlx::query_as("SELECT id, device_id, purpose, fingerprint FROM SshKeys WHERE user_id=? AND id=? AND revoked_at IS NULL")
+    .bind(user_id)
+    .bind(key_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    key.ok_or(APIError::NoFile)
+}
+
+/// Une clé déjà installée n'est pas dupliquée, elle est rattachée au nouvel appareil s'il y en a un
+pub async fn record_key(db: &MySqlPool, user_id: &str, key: &NewKey<'_>)->Result<u64, APIError>{
+    let existing: Option<(u64,)> = sqlx::query_as("SELECT id FROM SshKeys WHERE user_id=? AND purpose=? AND fingerprint=? AND revoked_at IS NULL")
+    .bind(user_id)
+    .bind(key.purpose)
+    .bind(key.fingerprint)
+    .fetch_optional(db).await.map_err(database_error)?;
+    if let Some((key_id,)) = existing{
+        sqlx::query("UPDATE SshKeys SET device_id=COALESCE(?, device_id) WHERE id=?")
+        .bind(key.device_id)
+        .bind(key_id)
+        .execute(db).await.map_err(database_error)?;
+        return Ok(key_id)
+    }
+    let result = sqlx::query("INSERT INTO SshKeys (user_id, device_id, purpose, key_type, fingerprint, public_key, created_at) VALUES(?,?,?,?,?,?,?)")
+    .bind(user_id)
+    .bind(key.device_id)
+    .bind(key.purpose)
+    .bind(key.key_type)
+    .bind(key.fingerprint)
+    .bind(key.public_key)
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+pub async fn revoke_key(db: &MySqlPool, user_id: &str, key_id: u64)->Result<(), APIError>{
+    sqlx::query("UPDATE SshKeys SET revoked_at=? WHERE user_id=? AND id=? AND revoked_at IS NULL")
+    .bind(get_current_timestamp())
+    .bind(user_id)
+    .bind(key_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Révoque l'ancienne clé et enregistre la nouvelle dans une seule transaction
+pub async fn replace_key(db: &MySqlPool, user_id: &str, old_key_id: u64, key: &NewKey<'_>)->Result<u64, APIError>{
+    let now = get_current_timestamp();
+    let mut transaction = db.begin().await.map_err(database_error)?;
+    sqlx::query("UPDATE SshKeys SET revoked_at=? WHERE user_id=? AND id=?")
+    .bind(now)
+    .bind(user_id)
+    .bind(old_key_id)
+    .execute(&mut *transaction).await.map_err(database_error)?;
+    let result = sqlx::query("INSERT INTO SshKeys (user_id, device_id, purpose, key_type, fingerprint, public_key, created_at) VALUES(?,?,?,?,?,?,?)")
+    .bind(user_id)
+    .bind(key.device_id)
+    .bind(key.purpose)
+    .bind(key.key_type)
+    .bind(key.fingerprint)
+    .bind(key.public_key)
+    .bind(now)
+    .execute(&mut *transaction).await.map_err(database_error)?;
+    transaction.commit().await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+pub async fn active_device_keys(db: &MySqlPool, user_id: &str, device_id: u64)->Result<Vec<ActiveKey>, APIError>{
+    sqlx::query_as("SELECT id, device_id, purpose, fingerprint FROM SshKeys WHERE user_id=? AND device_id=? AND revoked_at IS NULL")
+    .bind(user_id)
+    .bind(device_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Clés actives d'un usage avec leur propriétaire, pour tous les utilisateurs si user_id est None (tunnel partagé)
+pub async fn authorized_keys(db: &MySqlPool, purpose: &str, user_id: Option<&str>)->Result<Vec<(String, String)>, APIError>{
+    sqlx::query_as("SELECT user_id, public_key FROM SshKeys WHERE purpose=? AND revoked_at IS NULL AND (? IS NULL OR user_id=?) ORDER BY id")
+    .bind(purpose)
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
@@ -0,0 +1,115 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use std::collections::HashSet;
+use crate::database::database_error;
+use crate::error::APIError;
+
+/// Ports du docker borg réservés aux reverse tunnels des clients
+pub const FIRST_PORT: u16 = 22000;
+pub const LAST_PORT: u16 = 22999;
+
+/// Raisons de fin d'un bail
+pub const RELEASED: &str = "released";
+pub const EXPIRED: &str = "expired";
+pub const KILLED: &str = "killed";
+
+/// Port prêté à un client pour son reverse tunnel (table TunnelLeases).
+/// Un port et un client n'ont qu'un bail actif à la fois (index uniques active_port et active_user).
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TunnelLease{
+    pub id: u64,
+    pub user_id: String,
+    pub port: u16,
+    pub created_at: u64,
+    /// Dernier passage où le tunnel était ouvert, None s'il n'a jamais été vu
+    pub last_seen_at: Option<u64>,
+    pub expires_at: u64
+}
+
+const LEASE_COLUMNS: &str = "id, user_id, port, created_at, last_seen_at, expires_at";
+
+/// Baux actifs d'un utilisateur, de tous les utilisateurs si user_id est None
+pub async fn active_leases(db: &MySqlPool, user_id: Option<&str>)->Result<Vec<TunnelLease>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM TunnelLeases WHERE released_at IS NULL AND (? IS NULL OR user_id=?) ORDER BY port", LEASE_COLUMNS))
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+async fn user_lease(db: &MySqlPool, user_id: &str)->Result<Option<TunnelLease>, APIError>{
+    sqlx::query_as(&format!("SELECT {} FROM TunnelLeases WHERE released_at IS NULL AND user_id=?", LEASE_COLUMNS))
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+/// Bail actif du client, prolongé, ou nouveau bail sur un port libre.
+/// Le dernier port du client est repris s'il est libre. busy contient les ports déjà écoutés sur le serveur.
+pub async fn acquire_lease(db: &MySqlPool, user_id: &str, busy: &HashSet<u16>, lease_seconds: u64)->Result<u16, APIError>{
+    let now = get_current_timestamp();
+    if let Some(lease) = user_lease(db, user_id).await?{
+        renew_lease(db, lease.id, now + lease_seconds, None).await?;
+        return Ok(lease.port)
+    }
+    let last_port: Option<(u16,)> = sqlx::query_as("SELECT port FROM TunnelLeases WHERE user_id=? ORDER BY id DESC LIMIT 1")
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    let taken: Vec<(u16,)> = sqlx::query_as("SELECT port FROM TunnelLeases WHERE released_at IS NULL")
+    .fetch_all(db).await.map_err(database_error)?;
+    let taken: HashSet<u16> = taken.into_iter().map(|(port,)| port).collect();
+
+    let candidates = last_port.map(|(port,)| port).into_iter().chain(FIRST_PORT..=LAST_PORT);
+    for port in candidates.filter(|port| !taken.contains(port) && !busy.contains(port)){
+        let inserted = sqlx::query("INSERT INTO TunnelLeases (user_id, port, created_at, expires_at) VALUES(?,?,?,?)")
+        .bind(user_id)
+        .bind(port)
+        .bind(now)
+        .bind(now + lease_seconds)
+        .execute(db).await;
+        match inserted{
+            Ok(_)=>return Ok(port),
+            // Bail pris en même temps : pour ce client on renvoie le sien, sinon on essaie le port suivant
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation()=>{
+                if let Some(lease) = user_lease(db, user_id).await?{
+                    return Ok(lease.port)
+                }
+            },
+            Err(e)=>return Err(database_error(e))
+        }
+    }
+    println!("Aucun port libre entre {} et {} pour le client : {}", FIRST_PORT, LAST_PORT, user_id);
+    Err(APIError::NoFile)
+}
+
+/// Repousse l'expiration, seen_at est la date à laquelle le tunnel a été vu ouvert
+pub async fn renew_lease(db: &MySqlPool, lease_id: u64, expires_at: u64, seen_at: Option<u64>)->Result<(), APIError>{
+    sqlx::query("UPDATE TunnelLeases SET expires_at=GREATEST(expires_at, ?), last_seen_at=COALESCE(?, last_seen_at) WHERE id=? AND released_at IS NULL")
+    .bind(expires_at)
+    .bind(seen_at)
+    .bind(lease_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Libère le bail actif d'un port, renvoie son id s'il y en avait un
+pub async fn release_port(db: &MySqlPool, port: u16, user_id: Option<&str>, reason: &str)->Result<Option<u64>, APIError>{
+    let lease: Option<(u64,)> = sqlx::query_as("SELECT id FROM TunnelLeases WHERE released_at IS NULL AND port=? AND (? IS NULL OR user_id=?)")
+    .bind(port)
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    let Some((lease_id,)) = lease else{
+        return Ok(None)
+    };
+    release_lease(db, lease_id, reason).await?;
+    Ok(Some(lease_id))
+}
+
+pub async fn release_lease(db: &MySqlPool, lease_id: u64, reason: &str)->Result<(), APIError>{
+    sqlx::query("UPDATE TunnelLeases SET released_at=?, release_reason=? WHERE id=? AND released_at IS NULL")
+    .bind(get_current_timestamp())
+    .bind(reason)
+    .bind(lease_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
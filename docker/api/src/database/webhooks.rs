@@ -0,0 +1,166 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::database_error;
+use crate::error::APIError;
+
+pub const PENDING: &str = "pending";
+pub const DELIVERED: &str = "delivered";
+pub const FAILED: &str = "failed";
+
+/// Webhook enregistré par un utilisateur (table Webhooks), sans son secret
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Webhook{
+    pub id: u64,
+    pub url: String,
+    /// Évènements séparés par des virgules, * pour tous
+    #[serde(serialize_with = "serialize_events")]
+    pub events: String,
+    pub enabled: bool,
+    pub created_at: u64
+}
+
+fn serialize_events<S: serde::Serializer>(events: &str, serializer: S)->Result<S::Ok, S::Error>{
+    serializer.collect_seq(events.split(','))
+}
+
+/// Envoi d'un évènement à un webhook (table WebhookDeliveries)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Delivery{
+    pub id: u64,
+    pub webhook_id: u64,
+    pub event: String,
+    pub payload: String,
+    /// pending, delivered ou failed
+    pub status: String,
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    pub error: Option<String>,
+    pub next_attempt_at: Option<u64>,
+    pub created_at: u64,
+    pub delivered_at: Option<u64>
+}
+
+/// Envoi à tenter avec sa destination
+#[derive(Debug, sqlx::FromRow)]
+pub struct DueDelivery{
+    pub id: u64,
+    pub event: String,
+    pub payload: String,
+    pub attempts: u32,
+    pub url: String,
+    pub secret: String
+}
+
+pub async fn insert_webhook(db: &MySqlPool, user_id: &str, url: &str, secret: &str, events: &[String])->Result<u64, APIError>{
+    let result = sqlx::query("INSERT INTO Webhooks (user_id, url, secret, events, enabled, created_at) VALUES(?,?,?,?,1,?)")
+    .bind(user_id)
+    .bind(url)
+    .bind(secret)
+    .bind(events.join(","))
+    .bind(get_current_timestamp())
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+pub async fn list_webhooks(db: &MySqlPool, user_id: &str)->Result<Vec<Webhook>, APIError>{
+    sqlx::query_as("SELECT id, url, events, enabled, created_at FROM Webhooks WHERE user_id=? ORDER BY id")
+    .bind(user_id)
+    .fetch_all(db).await.map_err(database_error)
+}
+
+/// Le webhook de l'utilisateur, NoFile s'il n'existe pas ou appartient à un autre
+pub async fn webhook_exists(db: &MySqlPool, user_id: &str, webhook_id: u64)->Result<(), APIError>{
+    let webhook: Option<(u64,)> = sqlx::query_as("SELECT id FROM Webhooks WHERE user_id=? AND id=?")
+    .bind(user_id)
+    .bind(webhook_id)
+    .fetch_optional(db).await.map_err(database_error)?;
+    webhook.map(|_| ()).ok_or(APIError::NoFile)
+}
+
+/// Les envois du webhook sont supprimés avec lui
+pub async fn delete_webhook(db: &MySqlPool, user_id: &str, webhook_id: u64)->Result<(), APIError>{
+    let result = sqlx::query("DELETE FROM Webhooks WHERE user_id=? AND id=?")
+    .bind(user_id)
+    .bind(webhook_id)
+    .execute(db).await.map_err(database_error)?;
+    if result.rows_affected() == 0{
+        return Err(APIError::NoFile)
+    }
+    Ok(())
+}
+
+/// Webhooks actifs abonnés à l'évènement
+pub async fn subscribed_webhooks(db: &MySqlPool, user_id: &str, event: &str)->Result<Vec<u64>, APIError>{
+    let webhooks: Vec<(u64,)> = sqlx::query_as("SELECT id FROM Webhooks WHERE user_id=? AND enabled=1 AND (events='*' OR FIND_IN_SET(?, events))")
+    .bind(user_id)
+    .bind(event)
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(webhooks.into_iter().map(|(id,)| id).collect())
+}
+
+pub async fn insert_delivery(db: &MySqlPool, webhook_id: u64, user_id: &str, event: &str, payload: &str)->Result<u64, APIError>{
+    let now = get_current_timestamp();
+    let result = sqlx::query("INSERT INTO WebhookDeliveries (webhook_id, user_id, event, payload, status, attempts, next_attempt_at, created_at) VALUES(?,?,?,?,?,0,?,?)")
+    .bind(webhook_id)
+    .bind(user_id)
+    .bind(event)
+    .bind(payload)
+    .bind(PENDING)
+    .bind(now)
+    .bind(now)
+    .execute(db).await.map_err(database_error)?;
+    Ok(result.last_insert_id())
+}
+
+/// Envois en attente dont la prochaine tentative est passée
+pub async fn due_deliveries(db: &MySqlPool, limit: u32)->Result<Vec<u64>, APIError>{
+    let ids: Vec<(u64,)> = sqlx::query_as("SELECT id FROM WebhookDeliveries WHERE status=? AND next_attempt_at<=? ORDER BY next_attempt_at LIMIT ?")
+    .bind(PENDING)
+    .bind(get_current_timestamp())
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)?;
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Réserve l'envoi pendant lease secondes : une seule tâche le tente à la fois
+pub async fn claim_delivery(db: &MySqlPool, delivery_id: u64, lease: u64)->Result<Option<DueDelivery>, APIError>{
+    let now = get_current_timestamp();
+    let result = sqlx::query("UPDATE WebhookDeliveries SET next_attempt_at=? WHERE id=? AND status=? AND next_attempt_at<=?")
+    .bind(now + lease)
+    .bind(delivery_id)
+    .bind(PENDING)
+    .bind(now)
+    .execute(db).await.map_err(database_error)?;
+    if result.rows_affected() == 0{
+        return Ok(None)
+    }
+    sqlx::query_as("SELECT d.id, d.event, d.payload, d.attempts, w.url, w.secret FROM WebhookDeliveries d JOIN Webhooks w ON w.id=d.webhook_id WHERE d.id=?")
+    .bind(delivery_id)
+    .fetch_optional(db).await.map_err(database_error)
+}
+
+/// Résultat d'une tentative, next_attempt_at vide une fois l'envoi délivré ou abandonné
+pub async fn record_attempt(db: &MySqlPool, delivery_id: u64, status: &str, attempts: u32, response_status: Option<u16>, error: Option<&str>, next_attempt_at: Option<u64>)->Result<(), APIError>{
+    let delivered_at = (status == DELIVERED).then(get_current_timestamp);
+    sqlx::query("UPDATE WebhookDeliveries SET status=?, attempts=?, response_status=?, error=?, next_attempt_at=?, delivered_at=? WHERE id=?")
+    .bind(status)
+    .bind(attempts)
+    .bind(response_status)
+    .bind(error.map(|error| error.chars().take(1024).collect::<String>()))
+    .bind(next_attempt_at)
+    .bind(delivered_at)
+    .bind(delivery_id)
+    .execute(db).await.map_err(database_error)?;
+    Ok(())
+}
+
+/// Derniers envois d'un webhook, le plus récent en premier
+pub async fn list_deliveries(db: &MySqlPool, user_id: &str, webhook_id: u64, limit: u32)->Result<Vec<Delivery>, APIError>{
+    sqlx::query_as("SELECT id, webhook_id, event, payload, status, attempts, response_status, error, next_attempt_at, created_at, delivered_at \
+    FROM WebhookDeliveries WHERE user_id=? AND webhook_id=? ORDER BY id DESC LIMIT ?")
+    .bind(user_id)
+    .bind(webhook_id)
+    .bind(limit)
+    .fetch_all(db).await.map_err(database_error)
+}
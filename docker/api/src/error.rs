@@ -13,6 +13,19 @@ pub enum APIError{
     Sftp,
     Write,
     ValidInput,
+    Database,
+    /// Tâche encore en cours ou en échec
+    JobNotReady,
+    /// Archive sous verrou de rétention
+    ArchiveLocked,
+    /// Alerte non envoyée (serveur SMTP ou webhook injoignable)
+    Notification,
+    /// Route réservée aux administrateurs (ADMIN_USERS)
+    NotAdmin,
+    /// Dépôt d'une organisation dont l'utilisateur n'est pas membre, ou rôle insuffisant
+    Forbidden,
+    /// Lien de partage invalide, expiré, révoqué ou déjà utilisé
+    ShareLink,
 
     //Convertion
     UTF8,
@@ -38,6 +51,8 @@ pub enum APIError{
     //Login
     NotSignup,
     KDFError,
+    /// Mot de passe incorrect lors d'une ré-authentification
+    WrongPassword,
 
     //Bearer
     /// Token à expiré
@@ -61,6 +76,13 @@ impl error::ResponseError for APIError{
             APIError::Ssh=>"104",
             APIError::Sftp=>"105",
             APIError::ValidInput=>"106",
+            APIError::Database=>"107",
+            APIError::JobNotReady=>"108",
+            APIError::ArchiveLocked=>"109",
+            APIError::Notification=>"110",
+            APIError::NotAdmin=>"111",
+            APIError::Forbidden=>"112",
+            APIError::ShareLink=>"113",
 
             // File
             APIError::Write=>"200",
@@ -83,6 +105,7 @@ impl error::ResponseError for APIError{
 
             // Login
             APIError::NotSignup=>"0",
+            APIError::WrongPassword=>"10",
 
             //Bearer
             APIError::Expired=>{
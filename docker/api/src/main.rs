@@ -9,7 +9,10 @@ mod error;
 mod route;
 mod borg_script;
 mod stream_http;
-use crate::route::{get_list, get_repot_key, get_ssh_pub_key_server, send_ssh_key, send_ssh_key_tunnel, signin, signup, restore, get_log};
+mod tasks;
+mod database;
+mod notify;
+use crate::route::{get_list, get_repot_key, get_ssh_pub_key_server, send_ssh_key, send_ssh_key_tunnel, signin, signup, restore, get_log, get_diff, restore_download, restore_jobs, delete_archive, compact, retention, storage_usage, repository_stats, check, alerts, backup_reports, backup_schedule, webhooks, devices, ssh_keys, authorized_keys, tunnels, organisations, share_links, restore_purges};
 
 #[post("/imaconnected")]
 async fn imaconnected(req: HttpRequest) -> Result<HttpResponse, APIError>{
@@ -29,6 +32,23 @@ async fn imaconnected(req: HttpRequest) -> Result<HttpResponse, APIError>{
 async fn main() -> std::io::Result<()> {
     let auth = Auth::new().await;
     println!("connection db et ssh réussi");
+    tasks::restore_janitor::spawn(auth.ssh_connexion.clone(), auth.db.clone());
+    match database::restore_jobs::fail_interrupted_jobs(&auth.db).await{
+        Ok(0)=>{},
+        Ok(count)=>println!("{} restaurations interrompues par l'arrêt de l'API", count),
+        Err(e)=>println!("Erreur lors de la reprise des restaurations : {}", e)
+    }
+    match database::check_runs::fail_interrupted_runs(&auth.db).await{
+        Ok(0)=>{},
+        Ok(count)=>println!("{} vérifications interrompues par l'arrêt de l'API", count),
+        Err(e)=>println!("Erreur lors de la reprise des vérifications : {}", e)
+    }
+    tasks::repository_check::spawn_scheduler(auth.clone());
+    tasks::backup_monitor::spawn_scheduler(auth.clone());
+    tasks::retention::spawn_scheduler(auth.clone());
+    tasks::webhook_delivery::spawn_scheduler(auth.db.clone());
+    tasks::tunnel_leases::spawn_reaper(auth.clone());
+    tasks::key_import::spawn(auth.clone());
 
     HttpServer::new(move || {
         App::new()
@@ -46,6 +66,68 @@ async fn main() -> std::io::Result<()> {
             .service(get_ssh_pub_key_server::get_ssh_pub_key_server)
             .service(restore::get_restore)
             .service(get_log::get_log)
+            .service(get_diff::get_diff)
+            .service(restore_download::prepare_restore)
+            .service(restore_download::download_restore)
+            .service(restore_jobs::create_restore_job)
+            .service(restore_jobs::restore_job_status)
+            .service(restore_jobs::download_restore_job)
+            .service(delete_archive::delete_archive_route)
+            .service(compact::compact_route)
+            .service(retention::get_retention_policy)
+            .service(retention::set_retention_policy)
+            .service(retention::retention_preview)
+            .service(storage_usage::storage_usage)
+            .service(repository_stats::repository_stats)
+            .service(check::check_repository_route)
+            .service(check::check_runs)
+            .service(check::check_run)
+            .service(check::events)
+            .service(alerts::alerts)
+            .service(backup_reports::backup_report)
+            .service(backup_reports::backup_reports)
+            .service(backup_reports::backup_report_detail)
+            .service(backup_schedule::get_backup_schedule)
+            .service(backup_schedule::set_backup_schedule)
+            .service(backup_schedule::test_backup_schedule)
+            .service(webhooks::webhooks)
+            .service(webhooks::create_webhook)
+            .service(webhooks::remove_webhook)
+            .service(webhooks::webhook_deliveries)
+            .service(webhooks::test_webhook)
+            .service(devices::devices)
+            .service(devices::create_device)
+            .service(devices::remove_device)
+            .service(ssh_keys::ssh_keys)
+            .service(ssh_keys::revoke_ssh_key)
+            .service(ssh_keys::rotate_ssh_key)
+            .service(tunnels::tunnels)
+            .service(tunnels::admin_tunnels)
+            .service(tunnels::kill_tunnel_route)
+            .service(restore_purges::restore_purges)
+            .service(organisations::organisations)
+            .service(organisations::create_organisation_route)
+            .service(organisations::join_organisation)
+            .service(organisations::delete_organisation_route)
+            .service(organisations::members)
+            .service(organisations::set_member_role_route)
+            .service(organisations::remove_member_route)
+            .service(organisations::invitations)
+            .service(organisations::create_invitation_route)
+            .service(organisations::delete_invitation_route)
+            .service(share_links::share_links)
+            .service(share_links::create_share_link)
+            .service(share_links::revoke_share_link)
+            .service(share_links::share_link_redemptions)
+            .service(share_links::share_landing)
+            .service(share_links::redeem_share_link)
+        )
+        // Routes appelées par le docker borg uniquement, nginx ne transmet que /api
+        .service(
+            web::scope("/internal")
+            .service(authorized_keys::authorized_keys_route)
+            .service(tunnels::acquire_tunnel_lease)
+            .service(tunnels::release_tunnel_lease)
         )
     })
     .bind(("0.0.0.0", 8080)).expect("exit notime to play")
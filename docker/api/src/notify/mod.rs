@@ -0,0 +1,98 @@
+use jsonwebtoken::get_current_timestamp;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use crate::database::events::record_event;
+use crate::error::APIError;
+pub mod smtp;
+pub mod webhook;
+
+/// Alerte destinée à un utilisateur, envoyée par chaque notificateur configuré
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert{
+    /// missed_backup, backup_failed ou test
+    pub kind: &'static str,
+    /// warning ou critical, comme les évènements
+    pub severity: &'static str,
+    pub subject: String,
+    pub message: String,
+    pub created_at: u64
+}
+
+impl Alert {
+    pub fn new(kind: &'static str, severity: &'static str, subject: String, message: String)->Self{
+        Alert{kind, severity, subject, message, created_at: get_current_timestamp()}
+    }
+}
+
+/// Où envoyer les alertes d'un utilisateur. Les webhooks passent par /api/webhooks, signés
+pub struct Recipients<'a>{
+    pub email: Option<&'a str>
+}
+
+/// Canaux d'envoi des alertes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notifier{
+    /// Fil d'évènements de l'application (table Events, /api/events)
+    InApp,
+    /// Courriel, si SMTP_HOST est configuré
+    Email
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delivery{
+    pub notifier: &'static str,
+    /// false si l'utilisateur n'a pas de destinataire pour ce canal
+    pub attempted: bool,
+    pub delivered: bool
+}
+
+impl Notifier {
+    pub fn as_str(&self)->&'static str{
+        match self{
+            Notifier::InApp=>"in_app",
+            Notifier::Email=>"email"
+        }
+    }
+
+    /// Notificateurs disponibles sur ce serveur
+    pub fn configured()->Vec<Notifier>{
+        let mut notifiers = vec![Notifier::InApp];
+        if smtp::config().is_some(){
+            notifiers.push(Notifier::Email);
+        }
+        notifiers
+    }
+
+    /// Ok(false) si l'utilisateur n'a pas de destinataire pour ce canal
+    async fn notify(&self, db: &MySqlPool, user_id: &str, recipients: &Recipients<'_>, alert: &Alert)->Result<bool, APIError>{
+        match self{
+            Notifier::InApp=>{
+                record_event(db, user_id, alert.severity, alert.kind, &alert.message).await?;
+                Ok(true)
+            },
+            Notifier::Email=>{
+                let Some(email) = recipients.email else{
+                    return Ok(false)
+                };
+                smtp::send(email, alert).await?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Envoie l'alerte par tous les canaux, l'échec de l'un n'empêche pas les autres
+pub async fn dispatch(db: &MySqlPool, user_id: &str, recipients: &Recipients<'_>, alert: &Alert)->Vec<Delivery>{
+    let mut deliveries = Vec::new();
+    for notifier in Notifier::configured(){
+        let delivery = match notifier.notify(db, user_id, recipients, alert).await{
+            Ok(attempted)=>Delivery{notifier: notifier.as_str(), attempted, delivered: attempted},
+            Err(e)=>{
+                println!("Alerte {} non envoyée par {} pour {} : {}", alert.kind, notifier.as_str(), user_id, e);
+                Delivery{notifier: notifier.as_str(), attempted: true, delivered: false}
+            }
+        };
+        deliveries.push(delivery);
+    }
+    deliveries
+}
@@ -0,0 +1,316 @@
+use openssl::ssl::{SslConnector, SslMethod, SslStream};
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::LazyLock;
+use std::time::Duration;
+use crate::error::APIError;
+use super::Alert;
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Chiffrement de la connexion au serveur SMTP
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Security{
+    /// Connexion en clair, pour un relais local ou un serveur de test
+    None,
+    StartTls,
+    /// TLS dès la connexion (port 465)
+    Tls
+}
+
+#[derive(Debug)]
+pub struct SmtpConfig{
+    host: String,
+    port: u16,
+    security: Security,
+    username: Option<String>,
+    password: Option<String>,
+    from: String
+}
+
+/// SMTP_HOST active l'envoi de courriels, les autres variables ont des valeurs par défaut
+static CONFIG: LazyLock<Option<SmtpConfig>> = LazyLock::new(|| {
+    let host = env::var("SMTP_HOST").ok().filter(|host| !host.is_empty())?;
+    let security = match env::var("SMTP_SECURITY").unwrap_or_default().as_str(){
+        "none"=>Security::None,
+        "tls"=>Security::Tls,
+        _=>Security::StartTls
+    };
+    let default_port = match security{
+        Security::None=>25,
+        Security::StartTls=>587,
+        Security::Tls=>465
+    };
+    let port = env::var("SMTP_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(default_port);
+    let from = env::var("SMTP_FROM").unwrap_or_else(|_| String::from("noreply@strongholder.fr"));
+    println!("Alertes par courriel via {}:{} ({:?})", host, port, security);
+    Some(SmtpConfig{
+        host,
+        port,
+        security,
+        username: env::var("SMTP_USERNAME").ok(),
+        password: env::var("SMTP_PASSWORD").ok(),
+        from
+    })
+});
+
+pub fn config()->Option<&'static SmtpConfig>{
+    CONFIG.as_ref()
+}
+
+/// Refuse les retours à la ligne qui permettraient d'injecter des en-têtes
+pub fn valid_address(address: &str)->bool{
+    address.len() <= 254
+    && address.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+    && !address.chars().any(|c| c.is_whitespace() || c.is_control() || c == '<' || c == '>')
+}
+
+pub async fn send(to: &str, alert: &Alert)->Result<(), APIError>{
+    let Some(config) = config() else{
+        return Err(APIError::Notification)
+    };
+    if !valid_address(to){
+        return Err(APIError::ValidInput)
+    }
+    let to = to.to_string();
+    let alert = alert.clone();
+    match tokio::task::spawn_blocking(move || send_blocking(config, &to, &alert)).await{
+        Ok(Ok(()))=>Ok(()),
+        Ok(Err(e))=>{
+            println!("Erreur SMTP : {}", e);
+            Err(APIError::Notification)
+        },
+        Err(_)=>Err(APIError::Notification)
+    }
+}
+
+enum Connection{
+    Plain(TcpStream),
+    Tls(Box<SslStream<TcpStream>>)
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8])->io::Result<usize>{
+        match self{
+            Connection::Plain(stream)=>stream.read(buf),
+            Connection::Tls(stream)=>stream.read(buf)
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8])->io::Result<usize>{
+        match self{
+            Connection::Plain(stream)=>stream.write(buf),
+            Connection::Tls(stream)=>stream.write(buf)
+        }
+    }
+    fn flush(&mut self)->io::Result<()>{
+        match self{
+            Connection::Plain(stream)=>stream.flush(),
+            Connection::Tls(stream)=>stream.flush()
+        }
+    }
+}
+
+fn tls(host: &str, stream: TcpStream)->io::Result<Connection>{
+    let connector = SslConnector::builder(SslMethod::tls()).map_err(io::Error::other)?.build();
+    let stream = connector.connect(host, stream).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(Connection::Tls(Box::new(stream)))
+}
+
+/// Lit une réponse, éventuellement sur plusieurs lignes (250-...), et vérifie son code
+fn expect(connection: &mut Connection, code: u16)->io::Result<String>{
+    let mut reply = String::new();
+    loop {
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        while !line.ends_with(b"\r\n"){
+            if connection.read(&mut byte)? == 0{
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connexion SMTP fermée"))
+            }
+            line.push(byte[0]);
+        }
+        let line = String::from_utf8_lossy(&line).to_string();
+        reply.push_str(&line);
+        if line.len() < 4 || line.as_bytes()[3] != b'-'{
+            break;
+        }
+    }
+    if !reply.starts_with(&code.to_string()){
+        return Err(io::Error::other(format!("réponse inattendue, {} attendu : {}", code, reply.trim_end())))
+    }
+    Ok(reply)
+}
+
+fn command(connection: &mut Connection, line: &str, code: u16)->io::Result<String>{
+    connection.write_all(format!("{}\r\n", line).as_bytes())?;
+    expect(connection, code)
+}
+
+/// Les en-têtes sont en ASCII, un sujet accentué est encodé (RFC 2047)
+fn encode_header(value: &str)->String{
+    let value = value.replace(['\r', '\n'], " ");
+    if value.is_ascii(){
+        return value
+    }
+    format!("=?UTF-8?B?{}?=", openssl::base64::encode_block(value.as_bytes()))
+}
+
+fn send_blocking(config: &SmtpConfig, to: &str, alert: &Alert)->io::Result<()>{
+    let stream = TcpStream::connect((config.host.as_str(), config.port))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut connection = match config.security{
+        Security::Tls=>tls(&config.host, stream)?,
+        _=>Connection::Plain(stream)
+    };
+    expect(&mut connection, 220)?;
+    command(&mut connection, "EHLO strongholder", 250)?;
+    if config.security == Security::StartTls{
+        command(&mut connection, "STARTTLS", 220)?;
+        let Connection::Plain(stream) = connection else{
+            return Err(io::Error::other("STARTTLS sur une connexion déjà chiffrée"))
+        };
+        connection = tls(&config.host, stream)?;
+        command(&mut connection, "EHLO strongholder", 250)?;
+    }
+    if let (Some(username), Some(password)) = (&config.username, &config.password){
+        let credentials = openssl::base64::encode_block(format!("\0{}\0{}", username, password).as_bytes());
+        command(&mut connection, &format!("AUTH PLAIN {}", credentials), 235)?;
+    }
+    command(&mut connection, &format!("MAIL FROM:<{}>", config.from), 250)?;
+    command(&mut connection, &format!("RCPT TO:<{}>", to), 250)?;
+    command(&mut connection, "DATA", 354)?;
+
+    let mut message = format!(
+        "From: Strongholder <{}>\r\nTo: <{}>\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        config.from, to, encode_header(&alert.subject), chrono::Utc::now().to_rfc2822()
+    );
+    for line in alert.message.lines(){
+        // Une ligne commençant par un point serait prise pour la fin du message
+        if line.starts_with('.'){
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    connection.write_all(message.as_bytes())?;
+    expect(&mut connection, 250)?;
+    let _ = command(&mut connection, "QUIT", 221);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use super::*;
+
+    /// Serveur SMTP minimal sur 127.0.0.1 : renvoie tout ce que le client a écrit.
+    /// rcpt_reply permet de refuser le destinataire
+    fn fake_server(rcpt_reply: &'static str)->(u16, JoinHandle<String>){
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut transcript = String::new();
+            let mut in_data = false;
+            writer.write_all(b"220 test ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0{
+                    break;
+                }
+                transcript.push_str(&line);
+                let reply = if in_data{
+                    if line != ".\r\n"{
+                        continue
+                    }
+                    in_data = false;
+                    "250 queued\r\n"
+                }else if line.starts_with("EHLO"){
+                    "250-test\r\n250 AUTH PLAIN\r\n"
+                }else if line.starts_with("AUTH"){
+                    "235 ok\r\n"
+                }else if line.starts_with("RCPT"){
+                    rcpt_reply
+                }else if line.starts_with("DATA"){
+                    in_data = true;
+                    "354 go\r\n"
+                }else if line.starts_with("QUIT"){
+                    "221 bye\r\n"
+                }else{
+                    "250 ok\r\n"
+                };
+                writer.write_all(reply.as_bytes()).unwrap();
+                if line.starts_with("QUIT"){
+                    break;
+                }
+            }
+            transcript
+        });
+        (port, server)
+    }
+
+    fn config(port: u16)->SmtpConfig{
+        SmtpConfig{
+            host: String::from("127.0.0.1"),
+            port,
+            security: Security::None,
+            username: Some(String::from("user")),
+            password: Some(String::from("secret")),
+            from: String::from("noreply@example.com")
+        }
+    }
+
+    #[test]
+    fn sends_the_whole_dialogue(){
+        let (port, server) = fake_server("250 ok\r\n");
+        let alert = Alert::new("backup_failed", "critical", String::from("Échec de sauvegarde"),
+            String::from("Première ligne\n.point en début de ligne"));
+        send_blocking(&config(port), "alice@example.com", &alert).unwrap();
+        let transcript = server.join().unwrap();
+
+        assert!(transcript.starts_with("EHLO strongholder\r\n\
+            AUTH PLAIN AHVzZXIAc2VjcmV0\r\n\
+            MAIL FROM:<noreply@example.com>\r\n\
+            RCPT TO:<alice@example.com>\r\n\
+            DATA\r\n\
+            From: Strongholder <noreply@example.com>\r\n\
+            To: <alice@example.com>\r\n\
+            Subject: =?UTF-8?B?w4ljaGVjIGRlIHNhdXZlZ2FyZGU=?=\r\n\
+            Date: "), "{}", transcript);
+        // Le point en début de ligne est doublé pour ne pas terminer le message
+        assert!(transcript.ends_with("Content-Transfer-Encoding: 8bit\r\n\r\n\
+            Première ligne\r\n\
+            ..point en début de ligne\r\n\
+            .\r\n\
+            QUIT\r\n"), "{}", transcript);
+    }
+
+    #[test]
+    fn refused_recipient_is_an_error(){
+        let (port, server) = fake_server("550 no such user\r\n");
+        let alert = Alert::new("test", "warning", String::from("Test"), String::from("Test"));
+        let error = send_blocking(&config(port), "bob@example.com", &alert).unwrap_err();
+        assert!(error.to_string().contains("550"), "{}", error);
+        // Le client abandonne sans envoyer le message
+        let transcript = server.join().unwrap();
+        assert!(transcript.ends_with("RCPT TO:<bob@example.com>\r\n"), "{}", transcript);
+    }
+
+    #[test]
+    fn rejects_header_injection(){
+        assert!(valid_address("alice@example.com"));
+        assert!(!valid_address("alice@example.com\r\nBcc: eve@example.com"));
+        assert!(!valid_address("<alice@example.com>"));
+        assert!(!valid_address("alice@localhost"));
+        assert_eq!(encode_header("Sujet\r\nBcc: eve@example.com"), "Sujet  Bcc: eve@example.com");
+    }
+}
@@ -0,0 +1,17 @@
+use actix_web::{get, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::database::archive_analyses::suspicious_archives;
+use crate::error::APIError;
+
+const LIST_LIMIT: u32 = 50;
+
+
+#[get("/alerts")]
+async fn alerts(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(suspicious_archives(&auth.db, &credentials.id, LIST_LIMIT).await?))
+}
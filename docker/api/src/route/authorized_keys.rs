@@ -0,0 +1,53 @@
+use actix_web::{get, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::authentification::internal::{valid_internal_token, valid_user_id};
+use crate::database::quotas::get_usage;
+use crate::database::ssh_keys::{authorized_keys, BORG, TUNNEL};
+use crate::error::APIError;
+
+/// Utilisateur partagé du docker borg qui reçoit les reverse tunnels
+const TUNNEL_USER: &str = "tunnel";
+const REPOS_DIR: &str = "/srv/repos";
+/// borg serve refuse un --storage-quota inférieur à 10M
+const MIN_STORAGE_QUOTA: u64 = 10_000_000;
+
+/// Type et base64 de la clé, le commentaire envoyé par le client n'est pas repris
+fn key_part(public_key: &str)->Option<String>{
+    let mut fields = public_key.split_whitespace();
+    Some(format!("{} {}", fields.next()?, fields.next()?))
+}
+
+/// Appelée par l'AuthorizedKeysCommand de sshd sur le docker borg, hors du scope /api donc jamais exposée par nginx.
+/// Renvoie les lignes au format authorized_keys des clés actives de l'utilisateur ssh : une clé révoquée est refusée dès la connexion suivante.
+#[get("/authorized_keys/{user}")]
+async fn authorized_keys_route(req: HttpRequest, auth: web::Data<Auth>, user: web::Path<String>)->Result<HttpResponse, APIError>{
+    if !valid_internal_token(&req){
+        println!("Demande de clés autorisées refusée pour l'utilisateur ssh : {}", user);
+        return Err(APIError::ErrorBearer)
+    }
+    let lines: Vec<String> = if user.as_str() == TUNNEL_USER{
+        // La dernière colonne identifie le client propriétaire de la clé
+        authorized_keys(&auth.db, TUNNEL, None).await?.iter()
+        .filter_map(|(user_id, public_key)| Some(format!("no-pty,no-agent-forwarding,no-X11-forwarding {} {}", key_part(public_key)?, user_id)))
+        .collect()
+    }else if valid_user_id(&user){
+        // append-only : le client ajoute des archives mais ne peut pas en supprimer
+        let repo = format!("{}/{}/repo", REPOS_DIR, user);
+        // Le quota est appliqué par borg serve lui-même : une sauvegarde lancée par cron, sans l'application, est refusée aussi
+        let quota = match get_usage(&auth.db, &user).await?.quota_bytes{
+            Some(quota)=>format!(" --storage-quota {}", quota.max(MIN_STORAGE_QUOTA)),
+            None=>String::new()
+        };
+        authorized_keys(&auth.db, BORG, Some(&user)).await?.iter()
+        .filter_map(|(_, public_key)| Some(format!("command=\"borg serve --append-only --restrict-to-path {}{}\",no-pty,no-agent-forwarding,no-port-forwarding,no-X11-forwarding {}", repo, quota, key_part(public_key)?)))
+        .collect()
+    }else{
+        // api et les comptes système gardent leur fichier authorized_keys
+        Vec::new()
+    };
+    let mut body = lines.join("\n");
+    if !body.is_empty(){
+        body.push('\n');
+    }
+    Ok(HttpResponse::Ok().content_type("text/plain; charset=utf-8").body(body))
+}
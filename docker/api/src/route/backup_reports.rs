@@ -0,0 +1,75 @@
+use actix_web::{get, post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::Auth;
+use crate::database::backup_reports::{get_report, list_reports, save_report, BackupReport};
+use crate::error::APIError;
+use crate::tasks::webhook_delivery::{emit, BACKUP_FAILED, BACKUP_FINISHED};
+
+/// La liste des fichiers d'une grosse sauvegarde dépasse la limite par défaut d'actix
+const MAX_REPORT_SIZE: usize = 64 * 1024 * 1024;
+const MAX_WARNINGS: usize = 1000;
+const LIST_LIMIT: u32 = 50;
+const MAX_LIST_LIMIT: u32 = 500;
+
+#[derive(Deserialize)]
+struct ReportsQuery{
+    limit: Option<u32>,
+    device: Option<String>
+}
+
+#[post("/backup_report")]
+async fn backup_report(req: HttpRequest, auth: web::Data<Auth>, payload: web::Payload)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+
+    let Ok(Ok(body)) = payload.to_bytes_limited(MAX_REPORT_SIZE).await else{
+        println!("Rapport de sauvegarde trop volumineux ou illisible pour {}", credentials.id);
+        return Err(APIError::ValidInput)
+    };
+    let mut report: BackupReport = match serde_json::from_slice(&body){
+        Ok(report)=>report,
+        Err(_)=>return Err(APIError::ValidInput)
+    };
+    if report.archive_name.is_empty() || report.archive_name.len() > 255
+    || report.archive_id.as_ref().is_some_and(|id| id.len() > 64 || !id.chars().all(|c| c.is_ascii_hexdigit())){
+        return Err(APIError::ValidInput)
+    }
+    report.warnings.truncate(MAX_WARNINGS);
+    println!("Rapport de sauvegarde {} (code {}) pour l'utilisateur : {}", report.archive_name, report.exit_code, credentials.id);
+    save_report(&auth.db, &credentials.id, &report).await?;
+    let event = if report.exit_code >= 2 { BACKUP_FAILED } else { BACKUP_FINISHED };
+    emit(&auth.db, &credentials.id, event, json!({
+        "archive_name": report.archive_name,
+        "archive_id": report.archive_id,
+        "exit_code": report.exit_code,
+        "duration": report.duration,
+        "nfiles": report.stats.nfiles,
+        "warnings": report.warnings.len()
+    }));
+    Ok(HttpResponse::Ok().body(""))
+}
+
+#[get("/backup_reports")]
+async fn backup_reports(req: HttpRequest, auth: web::Data<Auth>, query: web::Query<ReportsQuery>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let limit = query.limit.unwrap_or(LIST_LIMIT).min(MAX_LIST_LIMIT);
+    Ok(HttpResponse::Ok().json(list_reports(&auth.db, &credentials.id, query.device.as_deref(), limit).await?))
+}
+
+#[get("/backup_reports/{archive_name}")]
+async fn backup_report_detail(req: HttpRequest, auth: web::Data<Auth>, archive_name: web::Path<String>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(get_report(&auth.db, &credentials.id, &archive_name).await?))
+}
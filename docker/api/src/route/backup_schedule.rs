@@ -0,0 +1,54 @@
+use actix_web::{get, post, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::database::backup_schedules::{get_schedule, save_schedule, BackupSchedule};
+use crate::database::events::WARNING;
+use crate::error::APIError;
+use crate::notify::{dispatch, smtp, Alert, Recipients};
+
+/// Au-delà de 90 jours le suivi n'a plus d'intérêt
+const MAX_INTERVAL_HOURS: u32 = 90*24;
+
+#[get("/backup_schedule")]
+async fn get_backup_schedule(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(get_schedule(&auth.db, &credentials.id).await?))
+}
+
+#[post("/backup_schedule")]
+async fn set_backup_schedule(req: HttpRequest, auth: web::Data<Auth>, schedule: web::Json<BackupSchedule>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    println!("backup_schedule pour l'utilisateur : {}", credentials.id);
+    let mut schedule = schedule.into_inner();
+    // Une chaîne vide retire le destinataire
+    schedule.email = schedule.email.filter(|email| !email.is_empty());
+    if schedule.interval_hours == 0 || schedule.interval_hours > MAX_INTERVAL_HOURS
+    || schedule.email.as_deref().is_some_and(|email| !smtp::valid_address(email)){
+        return Err(APIError::ValidInput)
+    }
+    save_schedule(&auth.db, &credentials.id, &schedule).await?;
+    Ok(HttpResponse::Ok().json(get_schedule(&auth.db, &credentials.id).await?))
+}
+
+/// Envoie une alerte de test par chaque canal et indique lesquels l'ont délivrée
+#[post("/backup_schedule/test")]
+async fn test_backup_schedule(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let schedule = get_schedule(&auth.db, &credentials.id).await?;
+    let recipients = Recipients{email: schedule.email.as_deref()};
+    let alert = Alert::new("test", WARNING,
+        String::from("Strongholder : alerte de test"),
+        String::from("Les alertes de sauvegarde arriveront par ce canal."));
+    Ok(HttpResponse::Ok().json(dispatch(&auth.db, &credentials.id, &recipients, &alert).await))
+}
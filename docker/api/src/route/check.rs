@@ -0,0 +1,72 @@
+use actix_web::{get, post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::Auth;
+use crate::borg_script::check::CheckMode;
+use crate::database::check_runs::{get_run, insert_run, list_runs, MANUAL};
+use crate::database::events::list_events;
+use crate::error::APIError;
+use crate::tasks;
+
+const LIST_LIMIT: u32 = 50;
+
+#[derive(Deserialize, Default)]
+struct CheckRequest{
+    #[serde(default)]
+    mode: CheckMode
+}
+
+
+#[post("/check_repository")]
+async fn check_repository_route(req: HttpRequest, auth: web::Data<Auth>, body: String)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let check_request: CheckRequest = if body.trim().is_empty(){
+        CheckRequest::default()
+    }else{
+        match serde_json::from_str(&body){
+            Ok(check_request)=>check_request,
+            Err(_)=>return Err(APIError::ValidInput)
+        }
+    };
+    println!("check_repository {} pour l'utilisateur : {}", check_request.mode.as_str(), credentials.id);
+    let run_id = insert_run(&auth.db, &credentials.id, check_request.mode.as_str(), MANUAL).await?;
+    let user_id = credentials.id.clone();
+    // La clé n'est restaurée que pour verify_data
+    let credentials = (check_request.mode == CheckMode::VerifyData).then_some(credentials);
+    tasks::repository_check::start(auth.get_ref().clone(), user_id, credentials, check_request.mode, run_id);
+    Ok(HttpResponse::Accepted().json(json!({"run_id": run_id})))
+}
+
+#[get("/check_runs")]
+async fn check_runs(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(list_runs(&auth.db, &credentials.id, LIST_LIMIT).await?))
+}
+
+#[get("/check_runs/{run_id}")]
+async fn check_run(req: HttpRequest, auth: web::Data<Auth>, run_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(get_run(&auth.db, &credentials.id, *run_id).await?))
+}
+
+#[get("/events")]
+async fn events(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(list_events(&auth.db, &credentials.id, LIST_LIMIT).await?))
+}
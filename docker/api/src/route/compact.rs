@@ -0,0 +1,46 @@
+use actix_web::{post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::borg_script::compact::{compact_repository, Compaction};
+use crate::database::organisations::Role;
+use crate::error::APIError;
+use crate::tasks::retention::report_compaction;
+
+#[derive(Deserialize)]
+struct CompactRequest{
+    password: String,
+    /// Confirme que les archives supprimées par le client (append-only) peuvent être perdues
+    #[serde(default)]
+    accept_client_deletes: bool
+}
+
+/// Compactage relancé par un owner, seul moyen de libérer l'espace après un compactage refusé
+#[post("/compact")]
+async fn compact_route(req: HttpRequest, auth: web::Data<Auth>, compact_request: web::Json<CompactRequest>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    println!("compact pour le dépôt : {} (suppressions du client acceptées : {})", repository.id, compact_request.accept_client_deletes);
+
+    /* Ré-authentification : les suppressions du client deviennent définitives */
+    auth.verify_password(&credentials, &compact_request.password).await?;
+
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let compaction = compact_repository(&repository.id, compact_request.accept_client_deletes, auth.ssh_connexion.clone()).await;
+    key.release().await?;
+    let compaction = compaction?;
+    report_compaction(&auth, &repository.id, &compaction).await?;
+    let client_deleted = match &compaction{
+        Compaction::Refused(archives)=>archives.clone(),
+        _=>Vec::new()
+    };
+    Ok(HttpResponse::Ok().json(json!({
+        "compacted": compaction == Compaction::Done,
+        "client_deleted": client_deleted
+    })))
+}
@@ -0,0 +1,63 @@
+use actix_web::{post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::borg_script::compact::Compaction;
+use crate::borg_script::delete_archive::delete_archive;
+use crate::borg_script::info::invalidate_statistics;
+use crate::database::archive_deletions::{record_deletion, DELETED, FAILED, LOCKED, WRONG_PASSWORD};
+use crate::database::archive_locks::is_locked;
+use crate::error::APIError;
+use crate::tasks::retention::report_compaction;
+
+#[derive(Deserialize)]
+struct DeleteRequest{
+    archive_name: String,
+    password: String
+}
+
+
+#[post("/delete_archive")]
+async fn delete_archive_route(req: HttpRequest, auth: web::Data<Auth>, delete_request: web::Json<DeleteRequest>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    let archive_name = delete_request.archive_name.trim();
+    println!("delete_archive {} pour le dépôt : {}", archive_name, repository.id);
+    if archive_name.is_empty(){
+        return Err(APIError::ValidInput)
+    }
+
+    /* Ré-authentification : un token volé ne suffit pas pour supprimer une archive */
+    if let Err(e) = auth.verify_password(&credentials, &delete_request.password).await{
+        if e == APIError::WrongPassword{
+            record_deletion(&auth.db, &repository.id, archive_name, WRONG_PASSWORD, None).await?;
+        }
+        return Err(e)
+    }
+    if is_locked(&auth.db, &repository.id, archive_name).await?{
+        println!("Archive {} sous verrou de rétention", archive_name);
+        record_deletion(&auth.db, &repository.id, archive_name, LOCKED, None).await?;
+        return Err(APIError::ArchiveLocked)
+    }
+
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let deleted = delete_archive(&repository.id, archive_name, auth.ssh_connexion.clone()).await;
+    key.release().await?;
+    let (deleted, compaction) = match deleted{
+        Ok(deleted)=>deleted,
+        Err(e)=>{
+            record_deletion(&auth.db, &repository.id, archive_name, FAILED, None).await?;
+            return Err(e)
+        }
+    };
+    invalidate_statistics(&repository.id);
+    record_deletion(&auth.db, &repository.id, archive_name, DELETED, Some(&deleted)).await?;
+    report_compaction(&auth, &repository.id, &compaction).await?;
+    Ok(HttpResponse::Ok().json(json!({"deleted": deleted, "compacted": compaction == Compaction::Done})))
+}
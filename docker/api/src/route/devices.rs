@@ -0,0 +1,65 @@
+use actix_web::{delete, get, post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::database::devices::{delete_device, get_device, list_devices, register_device, valid_device_name};
+use crate::database::ssh_keys::active_device_keys;
+use crate::error::APIError;
+use crate::route::ssh_keys::uninstall_key;
+
+const MAX_DEVICES: usize = 20;
+
+#[derive(Deserialize)]
+struct NewDevice{
+    name: String
+}
+
+#[get("/devices")]
+async fn devices(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    Ok(HttpResponse::Ok().json(list_devices(&auth.db, &repository.id).await?))
+}
+
+/// Un nom déjà enregistré renvoie l'appareil existant
+#[post("/devices")]
+async fn create_device(req: HttpRequest, auth: web::Data<Auth>, device: web::Json<NewDevice>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    if !valid_device_name(&device.name){
+        return Err(APIError::ValidInput)
+    }
+    let known_devices = list_devices(&auth.db, &repository.id).await?;
+    if known_devices.len() >= MAX_DEVICES && !known_devices.iter().any(|known| known.name == device.name){
+        return Err(APIError::ValidInput)
+    }
+    let device = register_device(&auth.db, &repository.id, &device.name).await?;
+    println!("Appareil {} enregistré pour le dépôt : {}", device.name, repository.id);
+    Ok(HttpResponse::Ok().json(device))
+}
+
+/// Les clés SSH de l'appareil sont révoquées, ses archives ne sont pas supprimées
+#[delete("/devices/{device_id}")]
+async fn remove_device(req: HttpRequest, auth: web::Data<Auth>, device_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    get_device(&auth.db, &repository.id, *device_id).await?;
+    for key in active_device_keys(&auth.db, &repository.id, *device_id).await?{
+        uninstall_key(&auth, &repository.id, &key).await?;
+    }
+    delete_device(&auth.db, &repository.id, *device_id).await?;
+    Ok(HttpResponse::Ok().body(""))
+}
@@ -0,0 +1,37 @@
+use actix_web::{post, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::error::APIError;
+use crate::borg_script::diff::diff_archive;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct DiffRequest{
+    archive_name_1: String,
+    archive_name_2: String,
+    path_prefix: Option<String>
+}
+
+
+#[post("/get_diff")]
+async fn get_diff(req: HttpRequest, auth: web::Data<Auth>, diff_request: web::Json<DiffRequest>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    println!("get_diff pour le dépôt : {}", repository.id);
+
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let diff = diff_archive(
+        &repository.id,
+        auth.ssh_connexion.clone(),
+        &diff_request.archive_name_1,
+        &diff_request.archive_name_2,
+        diff_request.path_prefix.as_deref()
+    ).await;
+    key.release().await?;
+    Ok(HttpResponse::Ok().json(diff?))
+}
@@ -1,13 +1,17 @@
 use actix_web::{post, HttpResponse, HttpRequest, web, Result};
 use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
 use crate::error::APIError;
 use crate::borg_script::list_archive::{list_archive, list_archive_content};
 use serde::Deserialize;
 use serde_json;
 
+/// Corps optionnel : archive_name pour le contenu d'une archive, device pour les archives d'un appareil
 #[derive(Deserialize)]
-struct Archive{
-    archive_name: String
+struct ListQuery{
+    archive_name: Option<String>,
+    device: Option<String>
 }
 
 
@@ -18,23 +22,32 @@ async fn get_list(req: HttpRequest, auth: web::Data<Auth>, body: String)->Result
         return Err(APIError::NoCookieBearer)
     };
     let credentials= Auth::decode_token(cookie.value())?;
-    println!("get list pour l'utilisateur : {}", credentials.id);
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    println!("get list pour le dépôt : {}", repository.id);
 
-    let _ = auth.restore_master_key_file(&credentials).await?;
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
     if body.len() == 0{
-        let archives = list_archive(&credentials.id, auth.ssh_connexion.clone()).await?;
-        auth.delete_master_key_file(&credentials.id).await?;
+        let archives = list_archive(&repository.id, auth.ssh_connexion.clone()).await?;
+        key.release().await?;
         return Ok(HttpResponse::Ok().json(archives))
     }else{
-        let archive: Archive = match serde_json::from_str(body.as_str()){
+        let query: ListQuery = match serde_json::from_str(body.as_str()){
             Ok(o)=>o,
             Err(_)=>{
                 println!("Erreur lors de la conversion en json dans get_list");
                 return Err(APIError::Json)
             }
         };
-        let archive_files = list_archive_content(&credentials.id, auth.ssh_connexion.clone(), &archive.archive_name).await?;
-        auth.delete_master_key_file(&credentials.id).await?;
+        let Some(archive_name) = query.archive_name else{
+            let mut archives = list_archive(&repository.id, auth.ssh_connexion.clone()).await?;
+            key.release().await?;
+            if let Some(device) = &query.device{
+                archives.retain_device(device);
+            }
+            return Ok(HttpResponse::Ok().json(archives))
+        };
+        let archive_files = list_archive_content(&repository.id, auth.ssh_connexion.clone(), &archive_name).await?;
+        key.release().await?;
         return Ok(HttpResponse::Ok().json(archive_files))
     };
             
@@ -1,19 +1,97 @@
 use actix_web::{post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
 use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
 use crate::error::APIError;
-use crate::borg_script::log::list_log_content;
+use crate::borg_script::log::{list_log_archives, read_log_archives, Logs};
+use crate::database::log_cache::{cached_archive_ids, forget_log, get_cached_log, save_log};
+
+/// Corps optionnel : sans corps, tous les logs sont renvoyés
+#[derive(Deserialize, Default)]
+pub struct LogQuery{
+    /// Seules les archives de logs postérieures à cette date (format time de /get_list) sont renvoyées
+    since: Option<String>,
+    /// Nombre maximum d'archives, les plus récentes sont gardées
+    limit: Option<usize>,
+    /// Seuls les logs de cet appareil sont renvoyés
+    device: Option<String>
+}
 
 #[post("/get_log")]
-async fn get_log(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+async fn get_log(req: HttpRequest, query: Option<web::Json<LogQuery>>, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
     /* Extraction du cookie JWT */
     let Some(cookie) = req.cookie("Bearer") else{
         return Err(APIError::NoCookieBearer)
     };
     let credentials= Auth::decode_token(cookie.value())?;
-    println!("get_log pour {}", credentials.id);
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    println!("get_log pour {}", repository.id);
+    let query = query.map(|query| query.into_inner()).unwrap_or_default();
+
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let logs = collect_logs(&auth, &repository.id, &query).await;
+    key.release().await?;
+    Ok(HttpResponse::Ok().json(logs?))
+}
+
+async fn collect_logs(auth: &Auth, uuid: &String, query: &LogQuery)->Result<Logs, APIError>{
+    let mut archives = list_log_archives(uuid, auth.ssh_connexion.clone()).await?;
+
+    // Les logs des archives supprimées ne sont plus utiles
+    for archive_id in cached_archive_ids(&auth.db, uuid).await?{
+        if !archives.iter().any(|archive| archive.id == archive_id){
+            forget_log(&auth.db, uuid, &archive_id).await?;
+        }
+    }
+
+    if let Some(device) = &query.device{
+        archives.retain(|archive| archive.device.as_ref() == Some(device));
+    }
+    if let Some(since) = &query.since{
+        archives.retain(|archive| &archive.time > since);
+    }
+    if let Some(limit) = query.limit{
+        let skipped = archives.len().saturating_sub(limit);
+        archives.drain(..skipped);
+    }
+
+    // Seules les archives absentes du cache sont lues sur le serveur borg
+    let mut contents = Vec::<Option<String>>::new();
+    let mut missing = Vec::<String>::new();
+    for archive in &archives{
+        let cached = get_cached_log(&auth.db, uuid, &archive.id).await?;
+        if cached.is_none(){
+            missing.push(archive.archive.clone());
+        }
+        contents.push(cached);
+    }
+    let read = read_log_archives(uuid, auth.ssh_connexion.clone(), &missing).await;
 
-    let _ = auth.restore_master_key_file(&credentials).await?;
-    let logs = list_log_content(&credentials.id, auth.ssh_connexion.clone()).await?;
-    auth.delete_master_key_file(&credentials.id).await?;
-    return Ok(HttpResponse::Ok().json(logs))
-}
\ No newline at end of file
+    let mut logs = Logs::default();
+    for (archive, content) in archives.iter().zip(contents){
+        let content = match content{
+            Some(content)=>Some(content),
+            None=>{
+                let content = read.iter()
+                .find(|(name, _)| name == &archive.archive)
+                .and_then(|(_, content)| content.clone());
+                if let Some(content) = &content && !archive.id.is_empty(){
+                    save_log(&auth.db, uuid, &archive.id, &archive.archive, content).await?;
+                }
+                content
+            }
+        };
+        match content{
+            Some(content)=>{
+                logs.logs.push(content);
+                logs.latest = Some(archive.time.clone());
+            },
+            None=>logs.failed.push(archive.archive.clone())
+        }
+    }
+    if !logs.failed.is_empty(){
+        println!("get_log pour {} : {} archive(s) de logs illisible(s)", uuid, logs.failed.len());
+    }
+    Ok(logs)
+}
@@ -1,7 +1,10 @@
-use actix_web::{HttpRequest, Result, post,http::header::{ContentDisposition, DispositionType, DispositionParam}, web,HttpResponse};
+use actix_web::{HttpRequest, Result, post, web,HttpResponse};
 use crate::error::APIError;
 use crate::authentification::auth::Auth;
-use crate::stream_http::stream_http::StreamBuffer2;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::tasks::webhook_delivery::{emit, REPO_KEY_DOWNLOADED};
+use crate::stream_http::{content_disposition::attachment, range::RangeRequest, stream_http::StreamBuffer2};
 
 
 #[post("/get_repot_key")]
@@ -12,14 +15,23 @@ async fn get_repot_key(req: HttpRequest, auth: web::Data<Auth>) -> Result<HttpRe
     };
 
     let credentials = Auth::decode_token(cookie.value())?;
-    let repot_key = auth.decrypt_master_1_key(&credentials).await?;
-    let stream = StreamBuffer2::new(repot_key.clone());
-    let content_disposition = ContentDisposition {
-        disposition: DispositionType::Attachment,
-        parameters: vec![
-            DispositionParam::Filename(String::from(format!("{}.gpg", credentials.id)))
-        ],
-    };
-    return Ok(HttpResponse::Ok().insert_header(content_disposition).streaming(stream))
-}
+    // La clé reste valable après le départ d'un membre : seuls les owners d'un dépôt partagé la téléchargent
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    let repot_key = auth.decrypt_repository_key_1(&credentials, &repository).await?;
+    // Le contenu est toujours le même pour un utilisateur, son empreinte sert d'ETag
+    let etag = format!("\"{}\"", hex::encode(openssl::sha::sha256(&repot_key)));
+    let size = repot_key.len() as u64;
 
+    let range = RangeRequest::from_request(&req, size, &etag);
+    let mut response = range.response(size, &etag);
+    if range == RangeRequest::Unsatisfiable{
+        return Ok(response.finish())
+    }
+    let (start, length) = range.bounds(size);
+    // Une reprise de téléchargement n'est pas un nouveau téléchargement
+    if start == 0{
+        emit(&auth.db, &repository.id, REPO_KEY_DOWNLOADED, serde_json::json!({}));
+    }
+    let stream = StreamBuffer2::new(repot_key[start as usize..(start + length) as usize].to_vec());
+    Ok(response.insert_header(attachment(&format!("{}.gpg", repository.id))).no_chunking(length).streaming(stream))
+}
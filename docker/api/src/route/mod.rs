@@ -7,3 +7,23 @@ pub mod get_ssh_pub_key_server;
 pub mod restore;
 pub mod send_ssh_key_tunnel;
 pub mod get_log;
+pub mod get_diff;
+pub mod restore_download;
+pub mod restore_jobs;
+pub mod delete_archive;
+pub mod compact;
+pub mod retention;
+pub mod storage_usage;
+pub mod repository_stats;
+pub mod check;
+pub mod alerts;
+pub mod backup_reports;
+pub mod backup_schedule;
+pub mod webhooks;
+pub mod devices;
+pub mod ssh_keys;
+pub mod authorized_keys;
+pub mod tunnels;
+pub mod organisations;
+pub mod share_links;
+pub mod restore_purges;
@@ -0,0 +1,214 @@
+use actix_web::{delete, get, post, HttpResponse, HttpRequest, web, Result};
+use jsonwebtoken::get_current_timestamp;
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::{Auth, WrappedKeys};
+use crate::authentification::repository::resolve_organisation;
+use crate::database::organisations::{accept_invitation, create_invitation, create_organisation, delete_invitation, delete_organisation,
+    find_invitation, list_invitations, list_members, list_organisations, membership_by_organisation, remove_member, set_member_role,
+    valid_organisation_name, Role};
+use crate::database::share_links::{revoke_member_links, revoke_shared_links};
+use crate::error::APIError;
+
+/// Durée de validité d'une invitation (7 jours)
+const INVITATION_SECONDS: u64 = 7*24*60*60;
+
+#[derive(Deserialize)]
+struct NewOrganisation{
+    name: String
+}
+
+#[derive(Deserialize)]
+struct MemberRole{
+    role: Role
+}
+
+#[derive(Deserialize)]
+struct JoinOrganisation{
+    token: String
+}
+
+/// Empreinte stockée en base, le jeton lui-même chiffre les clés de l'invitation
+fn token_hash(token: &[u8])->String{
+    hex::encode(openssl::sha::sha256(token))
+}
+
+#[get("/organisations")]
+async fn organisations(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(list_organisations(&auth.db, &credentials.id).await?))
+}
+
+/// Partage le dépôt de l'utilisateur, qui devient fondateur et owner de l'organisation
+#[post("/organisations")]
+async fn create_organisation_route(req: HttpRequest, auth: web::Data<Auth>, organisation: web::Json<NewOrganisation>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let name = organisation.name.trim();
+    if !valid_organisation_name(name){
+        return Err(APIError::ValidInput)
+    }
+    let organisation_id = create_organisation(&auth.db, name, &credentials.id).await?;
+    println!("Organisation {} créée par l'utilisateur : {}", organisation_id, credentials.id);
+    Ok(HttpResponse::Ok().json(json!({"id": organisation_id, "name": name, "repository_id": credentials.id, "role": Role::Owner})))
+}
+
+/// Le jeton ne sert qu'une fois : les clés du dépôt sont chiffrées à nouveau avec le kdf de l'utilisateur
+#[post("/organisations/join")]
+async fn join_organisation(req: HttpRequest, auth: web::Data<Auth>, join: web::Json<JoinOrganisation>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let Ok(secret) = hex::decode(join.token.trim()) else{
+        return Err(APIError::ValidInput)
+    };
+    let Some(invitation) = find_invitation(&auth.db, &token_hash(&secret)).await? else{
+        return Err(APIError::NoFile)
+    };
+    if membership_by_organisation(&auth.db, invitation.organisation_id, &credentials.id).await?.is_some(){
+        return Err(APIError::AlreadyExist)
+    }
+    let Ok(kdf_client) = hex::decode(&credentials.kdf) else{
+        return Err(APIError::KDFError)
+    };
+    let invitation_keys = WrappedKeys{
+        encrypt_master_key_1: invitation.encrypt_master_key_1.clone(),
+        encrypt_master_key_2: invitation.encrypt_master_key_2.clone()
+    };
+    let member_keys = auth.rewrap_keys(&invitation_keys, &hex::encode(&secret), &kdf_client)?;
+    accept_invitation(&auth.db, &invitation, &credentials.id, &member_keys).await?;
+    println!("L'utilisateur {} a rejoint l'organisation {} ({})", credentials.id, invitation.organisation_id, invitation.role);
+    Ok(HttpResponse::Ok().json(json!({"id": invitation.organisation_id, "role": invitation.role})))
+}
+
+/// Réservée au fondateur, le dépôt et ses archives sont conservés
+#[delete("/organisations/{organisation_id}")]
+async fn delete_organisation_route(req: HttpRequest, auth: web::Data<Auth>, organisation_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_organisation(&auth, &credentials, *organisation_id, Role::Owner).await?;
+    if repository.id != credentials.id{
+        return Err(APIError::Forbidden)
+    }
+    // Les anciens membres ne doivent plus pouvoir partager de fichiers du dépôt
+    revoke_shared_links(&auth.db, &repository.id).await?;
+    delete_organisation(&auth.db, *organisation_id).await?;
+    println!("Organisation {} supprimée par l'utilisateur : {}", organisation_id, credentials.id);
+    Ok(HttpResponse::Ok().body(""))
+}
+
+#[get("/organisations/{organisation_id}/members")]
+async fn members(req: HttpRequest, auth: web::Data<Auth>, organisation_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    resolve_organisation(&auth, &credentials, *organisation_id, Role::Viewer).await?;
+    Ok(HttpResponse::Ok().json(list_members(&auth.db, *organisation_id).await?))
+}
+
+/// Le rôle du fondateur ne peut pas changer
+#[post("/organisations/{organisation_id}/members/{user_id}")]
+async fn set_member_role_route(req: HttpRequest, auth: web::Data<Auth>, path: web::Path<(u64, String)>, member: web::Json<MemberRole>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let (organisation_id, user_id) = path.into_inner();
+    let repository = resolve_organisation(&auth, &credentials, organisation_id, Role::Owner).await?;
+    if user_id == repository.id{
+        return Err(APIError::Forbidden)
+    }
+    if !set_member_role(&auth.db, organisation_id, &user_id, member.role).await?{
+        return Err(APIError::NoFile)
+    }
+    // Un viewer ne peut pas restaurer : ses liens de partage ne doivent plus servir
+    if member.role < Role::Restorer{
+        revoke_member_links(&auth.db, &repository.id, &user_id).await?;
+    }
+    println!("Rôle {} donné à {} dans l'organisation {} par : {}", member.role.as_str(), user_id, organisation_id, credentials.id);
+    Ok(HttpResponse::Ok().json(list_members(&auth.db, organisation_id).await?))
+}
+
+/// Un membre peut quitter l'organisation, un owner peut retirer les autres membres sauf le fondateur
+#[delete("/organisations/{organisation_id}/members/{user_id}")]
+async fn remove_member_route(req: HttpRequest, auth: web::Data<Auth>, path: web::Path<(u64, String)>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let (organisation_id, user_id) = path.into_inner();
+    let required = if user_id == credentials.id { Role::Viewer } else { Role::Owner };
+    let repository = resolve_organisation(&auth, &credentials, organisation_id, required).await?;
+    if user_id == repository.id{
+        return Err(APIError::Forbidden)
+    }
+    if !remove_member(&auth.db, organisation_id, &user_id).await?{
+        return Err(APIError::NoFile)
+    }
+    revoke_member_links(&auth.db, &repository.id, &user_id).await?;
+    println!("Membre {} retiré de l'organisation {} par : {}", user_id, organisation_id, credentials.id);
+    Ok(HttpResponse::Ok().body(""))
+}
+
+#[get("/organisations/{organisation_id}/invitations")]
+async fn invitations(req: HttpRequest, auth: web::Data<Auth>, organisation_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    resolve_organisation(&auth, &credentials, *organisation_id, Role::Owner).await?;
+    Ok(HttpResponse::Ok().json(list_invitations(&auth.db, *organisation_id).await?))
+}
+
+/// Le jeton n'est renvoyé qu'à la création. Les clés du dépôt sont chiffrées avec le jeton, l'API n'en garde que l'empreinte
+#[post("/organisations/{organisation_id}/invitations")]
+async fn create_invitation_route(req: HttpRequest, auth: web::Data<Auth>, organisation_id: web::Path<u64>, invitation: web::Json<MemberRole>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_organisation(&auth, &credentials, *organisation_id, Role::Owner).await?;
+    let mut secret = [0u8; 32];
+    if openssl::rand::rand_bytes(&mut secret).is_err(){
+        return Err(APIError::KDFError)
+    }
+    let keys = auth.repository_keys(&repository).await?;
+    let invitation_keys = auth.rewrap_keys(&keys, &credentials.kdf, &secret)?;
+    let expires_at = get_current_timestamp() + INVITATION_SECONDS;
+    let invitation_id = create_invitation(&auth.db, *organisation_id, invitation.role, &token_hash(&secret), &invitation_keys, &credentials.id, expires_at).await?;
+    println!("Invitation {} ({}) créée dans l'organisation {} par : {}", invitation_id, invitation.role.as_str(), organisation_id, credentials.id);
+    Ok(HttpResponse::Ok().json(json!({"id": invitation_id, "role": invitation.role, "token": hex::encode(secret), "expires_at": expires_at})))
+}
+
+#[delete("/organisations/{organisation_id}/invitations/{invitation_id}")]
+async fn delete_invitation_route(req: HttpRequest, auth: web::Data<Auth>, path: web::Path<(u64, u64)>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let (organisation_id, invitation_id) = path.into_inner();
+    resolve_organisation(&auth, &credentials, organisation_id, Role::Owner).await?;
+    if !delete_invitation(&auth.db, organisation_id, invitation_id).await?{
+        return Err(APIError::NoFile)
+    }
+    Ok(HttpResponse::Ok().body(""))
+}
@@ -0,0 +1,31 @@
+use actix_web::{post, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::borg_script::info::{cache_statistics, cached_statistics, repository_statistics};
+use crate::database::quotas::save_usage;
+use crate::error::APIError;
+
+
+#[post("/repository_stats")]
+async fn repository_stats(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    println!("repository_stats pour le dépôt : {}", repository.id);
+
+    if let Some(statistics) = cached_statistics(&repository.id){
+        return Ok(HttpResponse::Ok().json(statistics))
+    }
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let statistics = repository_statistics(&repository.id, auth.ssh_connexion.clone()).await;
+    key.release().await?;
+    let statistics = statistics?;
+    cache_statistics(&repository.id, &statistics);
+    // La mesure sert aussi au suivi du quota
+    save_usage(&auth.db, &repository.id, &statistics.repository).await?;
+    Ok(HttpResponse::Ok().json(statistics))
+}
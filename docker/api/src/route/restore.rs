@@ -1,9 +1,12 @@
-use actix_web::{post, HttpResponse, HttpRequest,http::header::{ContentDisposition, DispositionType, DispositionParam}, web};
+use actix_web::{post, HttpResponse, HttpRequest, web};
 use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
 use crate::borg_script::restore::dertermining_restore_mode;
 use crate::error::APIError;
 use crate::stream_http::stream_http::StreamBuffer;
-use openssh_sftp_client::file::TokioCompatFile;
+use crate::stream_http::content_disposition::attachment;
+use crate::tasks::webhook_delivery;
 
 
 #[post("/get_restore")]
@@ -14,24 +17,20 @@ async fn get_restore(req: HttpRequest, auth: web::Data<Auth>, body: String)-> Re
     };
 
     let credentials=Auth::decode_token(cookie.value())?;
-    println!("get_restore pour {}", credentials.id);
-    let _ = auth.restore_master_key_file(&credentials).await?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    println!("get_restore pour {}", repository.id);
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
 
     if body.len() == 0{
         return Err(APIError::ValidInput)
     }
     println!("{}" , &body);
-    let (file, file_name) = dertermining_restore_mode(&credentials.id, &body, auth.ssh_connexion.clone(), auth.sftp_connexion.clone()).await?;
+    let (reader, file_name) = dertermining_restore_mode(&repository.id, &body, auth.ssh_connexion.clone(), auth.sftp_connexion.clone(), None).await?;
     println!("{}", &file_name);
-    let reader = TokioCompatFile::from(file);
     let stream = StreamBuffer::new(reader);
-    auth.delete_master_key_file(&credentials.id).await?;
-    let content_disposition = ContentDisposition {
-        disposition: DispositionType::Attachment,
-        parameters: vec![
-            DispositionParam::Filename(String::from(file_name))
-        ],
-    };
-    return Ok(HttpResponse::Ok().insert_header(content_disposition).streaming(stream))
+    // borg a déjà ouvert le dépôt lorsque les premiers octets sont reçus, la clé peut être supprimée pendant le flux
+    key.release().await?;
+    webhook_delivery::emit_restore(&auth.db, &repository.id, &body, "stream");
+    Ok(HttpResponse::Ok().insert_header(attachment(&file_name)).streaming(stream))
 }
 
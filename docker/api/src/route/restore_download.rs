@@ -0,0 +1,67 @@
+use actix_web::{get, post, HttpResponse, HttpRequest, web};
+use openssh_sftp_client::file::TokioCompatFile;
+use std::io::SeekFrom;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::borg_script::store_restore::{open_restore, restore_etag, stage_restore};
+use crate::error::APIError;
+use crate::stream_http::{content_disposition::attachment, range::RangeRequest, stream_http::StreamBuffer};
+use crate::tasks::webhook_delivery;
+
+
+#[post("/prepare_restore")]
+async fn prepare_restore(req: HttpRequest, auth: web::Data<Auth>, body: String)-> Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+
+    let credentials=Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    println!("prepare_restore pour {}", repository.id);
+    if body.is_empty(){
+        return Err(APIError::ValidInput)
+    }
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let artifact = stage_restore(&repository.id, &body, auth.ssh_connexion.clone(), auth.sftp_connexion.clone()).await;
+    key.release().await?;
+    let artifact = artifact?;
+    webhook_delivery::emit_restore(&auth.db, &repository.id, &body, "download");
+    Ok(HttpResponse::Ok().json(artifact))
+}
+
+#[get("/download_restore/{restore_id}")]
+async fn download_restore(req: HttpRequest, auth: web::Data<Auth>, restore_id: web::Path<String>)-> Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+
+    let credentials=Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    println!("download_restore {} pour {}", restore_id, repository.id);
+    serve_restore(&req, &auth, &repository.id, &restore_id).await
+}
+
+/// Envoie une restauration préparée en tenant compte des en-têtes Range et If-Range
+pub async fn serve_restore(req: &HttpRequest, auth: &Auth, uuid: &String, restore_id: &str)-> Result<HttpResponse, APIError>{
+    // Le fichier est déjà déchiffré sur le serveur, la clé n'est pas nécessaire
+    let (file, size, file_name) = open_restore(uuid, restore_id, auth.sftp_connexion.clone()).await?;
+    let etag = restore_etag(restore_id);
+
+    let range = RangeRequest::from_request(req, size, &etag);
+    let mut response = range.response(size, &etag);
+    if range == RangeRequest::Unsatisfiable{
+        return Ok(response.finish())
+    }
+    let (start, length) = range.bounds(size);
+    let mut file = Box::pin(TokioCompatFile::from(file));
+    if start > 0 && file.seek(SeekFrom::Start(start)).await.is_err(){
+        println!("Erreur seek dans la restauration {}", restore_id);
+        return Err(APIError::Sftp)
+    }
+    let stream = StreamBuffer::new(file.take(length));
+    Ok(response.insert_header(attachment(&file_name)).no_chunking(length).streaming(stream))
+}
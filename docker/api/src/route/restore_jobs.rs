@@ -0,0 +1,60 @@
+use actix_web::{get, post, HttpResponse, HttpRequest, web};
+use serde_json::json;
+use uuid::Uuid;
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::borg_script::restore::restore_archive_name;
+use crate::database::restore_jobs::{get_job, insert_job, READY};
+use crate::error::APIError;
+use crate::route::restore_download::serve_restore;
+use crate::tasks;
+
+
+#[post("/restore_jobs")]
+async fn create_restore_job(req: HttpRequest, auth: web::Data<Auth>, body: String)-> Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+
+    let credentials=Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    // Le corps est vérifié tout de suite pour ne pas créer de tâche vouée à l'échec
+    let archive_name = restore_archive_name(&body)?;
+    let job_id = Uuid::new_v4().simple().to_string();
+    println!("restore_jobs {} pour {}", job_id, repository.id);
+    insert_job(&auth.db, &job_id, &repository.id, &archive_name).await?;
+    tasks::restore_jobs::start(auth.get_ref().clone(), credentials, repository, body, job_id.clone());
+    Ok(HttpResponse::Accepted().json(json!({"job_id": job_id})))
+}
+
+#[get("/restore_jobs/{job_id}")]
+async fn restore_job_status(req: HttpRequest, auth: web::Data<Auth>, job_id: web::Path<String>)-> Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+
+    let credentials=Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    let job = get_job(&auth.db, &repository.id, &job_id).await?;
+    Ok(HttpResponse::Ok().json(job))
+}
+
+#[get("/restore_jobs/{job_id}/download")]
+async fn download_restore_job(req: HttpRequest, auth: web::Data<Auth>, job_id: web::Path<String>)-> Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+
+    let credentials=Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    let job = get_job(&auth.db, &repository.id, &job_id).await?;
+    let (READY, Some(restore_id)) = (job.status.as_str(), job.restore_id) else{
+        return Err(APIError::JobNotReady)
+    };
+    println!("download_restore_job {} pour {}", job_id, repository.id);
+    serve_restore(&req, &auth, &repository.id, &restore_id).await
+}
@@ -0,0 +1,19 @@
+use actix_web::{get, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::database::admins::require_admin;
+use crate::database::restore_purges::list_purges;
+use crate::error::APIError;
+
+const LIST_LIMIT: u32 = 50;
+
+/// Derniers passages du nettoyage des restaurations, réservé aux administrateurs
+#[get("/admin/restore_purges")]
+async fn restore_purges(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    require_admin(&auth.db, &credentials.id).await?;
+    Ok(HttpResponse::Ok().json(list_purges(&auth.db, LIST_LIMIT).await?))
+}
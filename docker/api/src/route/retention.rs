@@ -0,0 +1,65 @@
+use actix_web::{get, post, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::database::retention_policies::{get_policy, save_policy, RetentionPolicy};
+use crate::error::APIError;
+use crate::tasks::retention::preview;
+
+
+#[get("/retention_policy")]
+async fn get_retention_policy(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    Ok(HttpResponse::Ok().json(get_policy(&auth.db, &repository.id).await?))
+}
+
+#[post("/retention_policy")]
+async fn set_retention_policy(req: HttpRequest, auth: web::Data<Auth>, policy: web::Json<RetentionPolicy>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    println!("retention_policy pour le dépôt : {}", repository.id);
+    if ! policy.is_valid(){
+        return Err(APIError::ValidInput)
+    }
+    // Une politique active confie la clé du dépôt au planificateur, qui l'applique sans session
+    let scheduler_key = match policy.enabled{
+        true=>Some(auth.scheduler_key(&credentials, &repository).await?),
+        false=>None
+    };
+    save_policy(&auth.db, &repository.id, &policy, scheduler_key.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(get_policy(&auth.db, &repository.id).await?))
+}
+
+/// Sans corps, la politique enregistrée est utilisée
+#[post("/retention_preview")]
+async fn retention_preview(req: HttpRequest, auth: web::Data<Auth>, body: String)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    println!("retention_preview pour le dépôt : {}", repository.id);
+    let policy = if body.trim().is_empty(){
+        get_policy(&auth.db, &repository.id).await?
+    }else{
+        match serde_json::from_str::<RetentionPolicy>(&body){
+            Ok(policy)=>policy,
+            Err(_)=>return Err(APIError::ValidInput)
+        }
+    };
+    if ! policy.is_valid(){
+        return Err(APIError::ValidInput)
+    }
+    let plan = preview(&auth, &credentials, &repository, &policy).await?;
+    Ok(HttpResponse::Ok().json(plan))
+}
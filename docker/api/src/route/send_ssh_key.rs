@@ -1,12 +1,19 @@
 use actix_web::{post, HttpResponse, HttpRequest, web};
 use crate::authentification::auth::Auth;
-use crate::borg_script::install_client_key::install_client_key;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::database::ssh_keys::BORG;
 use crate::error::APIError;
+use crate::route::ssh_keys::install_key;
 use serde::Deserialize;
+use serde_json::json;
 
 #[derive(Deserialize)]
 struct SshKey{
-    ssh: String
+    ssh: String,
+    /// Appareil auquel la clé appartient, voir /devices
+    #[serde(default)]
+    device_id: Option<u64>
 }
 
 
@@ -18,8 +25,7 @@ async fn send_ssh_key(req: HttpRequest, ssh_key: web::Json<SshKey>, auth: web::D
     };
 
     let credentials = Auth::decode_token(cookie.value())?;
-    /* Upload du fichier */
-    let filepath= format!("/srv/repos/api/{}.pub", credentials.id,);
-    install_client_key(credentials.id, &ssh_key.ssh, filepath, auth.ssh_connexion.clone(), auth.sftp_connexion.clone()).await;
-    return Ok(HttpResponse::Ok().finish())
-}
\ No newline at end of file
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    let key_id = install_key(&auth, &repository.id, BORG, &ssh_key.ssh, ssh_key.device_id).await?;
+    Ok(HttpResponse::Ok().json(json!({"id": key_id})))
+}
@@ -0,0 +1,302 @@
+use actix_web::{delete, get, post, HttpResponse, HttpRequest, web, Result};
+use openssh_sftp_client::file::TokioCompatFile;
+use tokio::io::AsyncReadExt;
+use jsonwebtoken::{encode, decode, Header, Algorithm, Validation, EncodingKey, DecodingKey, get_current_timestamp};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::env;
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::{resolve_repository, Repository};
+use crate::borg_script::restore::{restore_file_stream, RestoreFormat};
+use crate::borg_script::share_file::{keep_shared, open_shared, remove_shared};
+use crate::borg_script::store_restore::store_restore;
+use crate::database::organisations::Role;
+use crate::database::share_links::{claim_use, find_link, get_link, insert_link, list_links, list_redemptions, record_redemption, release_use, revoke_link,
+    NewShareLink, EXPIRED, FAILED, REVOKED, SERVED, USED};
+use crate::error::APIError;
+use crate::route::signin::client_ip;
+use crate::stream_http::{content_disposition::attachment, stream_http::StreamBuffer};
+use crate::tasks::webhook_delivery::{emit, SHARE_LINK_REDEEMED};
+
+/// Validité par défaut d'un lien (24h) et validité maximale (30 jours)
+const DEFAULT_HOURS: u64 = 24;
+const MAX_HOURS: u64 = 30*24;
+
+#[derive(Deserialize)]
+struct NewLink{
+    archive_name: String,
+    /// Chemin du fichier dans l'archive, comme pour /api/get_restore
+    file_name: String,
+    hours: Option<u64>,
+    #[serde(default)]
+    single_use: bool
+}
+
+/// Formulaire de la page /share, le jeton vient du fragment de l'URL
+#[derive(Deserialize)]
+struct Redeem{
+    token: String
+}
+
+/// Contenu du jeton d'un lien, signé comme les cookies Bearer (HS384 avec JWT_SECRET)
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ShareClaims{
+    exp: u64,
+    /// Id dans ShareLinks
+    link: u64
+}
+
+/// Le fragment (#jeton) n'est envoyé ni au serveur ni dans le Referer : le jeton n'apparaît pas dans les logs de nginx.
+/// Le téléchargement demande un clic, un aperçu de lien (messagerie, antivirus) ne consomme pas un lien à usage unique
+const LANDING_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="fr">
+<head><meta charset="utf-8"><meta name="referrer" content="no-referrer"><title>Strongholder : fichier partagé</title></head>
+<body>
+<form method="post" action="/api/share">
+<input type="hidden" name="token" id="token">
+<button type="submit">Télécharger le fichier</button>
+</form>
+<script>
+document.getElementById("token").value = location.hash.slice(1);
+history.replaceState(null, "", location.pathname);
+</script>
+</body>
+</html>
+"#;
+
+fn jwt_secret()->String{
+    env::var("JWT_SECRET").expect("JWT_SECRET inexistant")
+}
+
+fn sign_share_token(claims: &ShareClaims, secret: &[u8])->Result<String, APIError>{
+    match encode(&Header::new(Algorithm::HS384), claims, &EncodingKey::from_secret(secret)){
+        Ok(token)=>Ok(token),
+        Err(e)=>{
+            println!("Erreur lors de l'encodage du lien de partage : {}", e);
+            Err(APIError::EncodeToken)
+        }
+    }
+}
+
+/// Seule la signature est vérifiée ici : l'expiration est contrôlée en base pour que la tentative soit tracée
+fn verify_share_token(token: &str, secret: &[u8])->Result<ShareClaims, APIError>{
+    let mut validation = Validation::new(Algorithm::HS384);
+    validation.validate_exp = false;
+    match decode::<ShareClaims>(token, &DecodingKey::from_secret(secret), &validation){
+        Ok(token)=>Ok(token.claims),
+        Err(_)=>{
+            println!("Lien de partage à la signature invalide");
+            Err(APIError::ShareLink)
+        }
+    }
+}
+
+/// Statut enregistré pour un lien que claim_use a refusé
+fn refusal_status(revoked_at: Option<u64>, expires_at: u64, now: u64)->&'static str{
+    if revoked_at.is_some(){
+        REVOKED
+    }else if expires_at <= now{
+        EXPIRED
+    }else{
+        USED
+    }
+}
+
+/// Les colonnes ip et user_agent sont limitées, un en-tête plus long est tronqué
+fn truncated(value: &str, max: usize)->String{
+    value.chars().take(max).collect()
+}
+
+#[get("/share_links")]
+async fn share_links(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    Ok(HttpResponse::Ok().json(list_links(&auth.db, &repository.id).await?))
+}
+
+/// Le lien n'est renvoyé qu'à la création. Le fichier est restauré tout de suite avec la clé de la session :
+/// l'utilisation du lien n'a pas besoin de la clé du dépôt
+#[post("/share_links")]
+async fn create_share_link(req: HttpRequest, auth: web::Data<Auth>, link: web::Json<NewLink>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    let archive_name = link.archive_name.trim();
+    let file_name = link.file_name.trim();
+    let hours = link.hours.unwrap_or(DEFAULT_HOURS);
+    if archive_name.is_empty() || archive_name.len() > 255 || file_name.trim_matches('/').is_empty() || file_name.len() > 1024 || hours == 0 || hours > MAX_HOURS{
+        return Err(APIError::ValidInput)
+    }
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let restore_id = stage_shared_file(&auth, &repository, archive_name, file_name).await;
+    key.release().await?;
+    let restore_id = restore_id?;
+    let expires_at = get_current_timestamp() + hours*60*60;
+    let link_id = match insert_link(&auth.db, &NewShareLink{
+        user_id: &repository.id,
+        created_by: &credentials.id,
+        archive_name,
+        file_name,
+        restore_id: &restore_id,
+        single_use: link.single_use,
+        expires_at
+    }).await{
+        Ok(link_id)=>link_id,
+        Err(e)=>{
+            // Sans lien en base, le nettoyage ne retrouverait pas le fichier
+            let _ = remove_shared(&repository.id, &restore_id, auth.ssh_connexion.clone()).await;
+            return Err(e)
+        }
+    };
+    let token = sign_share_token(&ShareClaims{exp: expires_at, link: link_id}, jwt_secret().as_bytes())?;
+    println!("Lien de partage {} créé pour le dépôt {} par : {}", link_id, repository.id, credentials.id);
+    Ok(HttpResponse::Ok().json(json!({
+        "id": link_id,
+        "url": format!("/api/share#{}", token),
+        "expires_at": expires_at,
+        "single_use": link.single_use
+    })))
+}
+
+/// Le créateur du lien ou un owner du dépôt peut le révoquer
+#[delete("/share_links/{link_id}")]
+async fn revoke_share_link(req: HttpRequest, auth: web::Data<Auth>, link_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    let link = get_link(&auth.db, &repository.id, *link_id).await?;
+    if link.created_by != credentials.id{
+        resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    }
+    revoke_link(&auth.db, &repository.id, link.id).await?;
+    println!("Lien de partage {} révoqué par : {}", link.id, credentials.id);
+    Ok(HttpResponse::Ok().body(""))
+}
+
+#[get("/share_links/{link_id}/redemptions")]
+async fn share_link_redemptions(req: HttpRequest, auth: web::Data<Auth>, link_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Restorer).await?;
+    let link = get_link(&auth.db, &repository.id, *link_id).await?;
+    Ok(HttpResponse::Ok().json(list_redemptions(&auth.db, link.id).await?))
+}
+
+/// Fichier restauré dans le dossier shared du dépôt, supprimé par tasks::restore_janitor quand le lien ne peut plus servir
+async fn stage_shared_file(auth: &Auth, repository: &Repository, archive_name: &str, file_name: &str)->Result<String, APIError>{
+    let (reader, download_name) = restore_file_stream(&repository.id, archive_name, file_name, RestoreFormat::default(), auth.ssh_connexion.clone(), auth.sftp_connexion.clone()).await?;
+    let artifact = store_restore(&repository.id, reader, download_name, auth.ssh_connexion.clone()).await?;
+    keep_shared(&repository.id, &artifact.restore_id, auth.ssh_connexion.clone()).await?;
+    Ok(artifact.restore_id)
+}
+
+/// Page ouverte par le lien, sans cookie Bearer (exemptée dans middleware_auth.rs). Elle ne lit pas le lien et ne le consomme pas
+#[get("/share")]
+async fn share_landing()->HttpResponse{
+    HttpResponse::Ok()
+    .content_type("text/html; charset=utf-8")
+    .insert_header(("Cache-Control", "no-store"))
+    .insert_header(("Referrer-Policy", "no-referrer"))
+    .body(LANDING_PAGE)
+}
+
+/// Sans cookie Bearer (exemptée dans middleware_auth.rs) : le jeton signé, posté par la page /share, suffit.
+/// Chaque tentative sur un lien existant est tracée
+#[post("/share")]
+async fn redeem_share_link(req: HttpRequest, auth: web::Data<Auth>, form: web::Form<Redeem>)->Result<HttpResponse, APIError>{
+    let claims = verify_share_token(&form.token, jwt_secret().as_bytes())?;
+    let Some(link) = find_link(&auth.db, claims.link).await? else{
+        return Err(APIError::ShareLink)
+    };
+    let ip = truncated(&client_ip(&req).await, 45);
+    let user_agent = req.headers().get("User-Agent").and_then(|value| value.to_str().ok()).map(|value| truncated(value, 255));
+
+    if !claim_use(&auth.db, link.id).await?{
+        let status = refusal_status(link.revoked_at, link.expires_at, get_current_timestamp());
+        println!("Lien de partage {} refusé ({}) pour : {}", link.id, status, ip);
+        record_redemption(&auth.db, link.id, &ip, user_agent.as_deref(), status).await?;
+        return Err(APIError::ShareLink)
+    }
+    let file = match &link.restore_id{
+        Some(restore_id)=>open_shared(&link.user_id, restore_id, auth.sftp_connexion.clone()).await,
+        None=>Err(APIError::NoFile)
+    };
+    let (file, size, download_name) = match file{
+        Ok(file)=>file,
+        Err(e)=>{
+            // Un lien à usage unique reste utilisable si le fichier n'a pas été envoyé
+            release_use(&auth.db, link.id).await?;
+            record_redemption(&auth.db, link.id, &ip, user_agent.as_deref(), FAILED).await?;
+            return Err(e)
+        }
+    };
+    record_redemption(&auth.db, link.id, &ip, user_agent.as_deref(), SERVED).await?;
+    println!("Lien de partage {} utilisé par : {}", link.id, ip);
+    emit(&auth.db, &link.user_id, SHARE_LINK_REDEEMED, json!({
+        "link_id": link.id,
+        "archive_name": link.archive_name,
+        "file_name": link.file_name,
+        "ip": ip
+    }));
+    let stream = StreamBuffer::new(Box::pin(TokioCompatFile::from(file)).take(size));
+    Ok(HttpResponse::Ok()
+    .insert_header(("Cache-Control", "no-store"))
+    .insert_header(attachment(&download_name))
+    .no_chunking(size)
+    .streaming(stream))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const SECRET: &[u8] = b"secret de test";
+
+    #[test]
+    fn signed_token_round_trips(){
+        let claims = ShareClaims{exp: 1771677830, link: 7};
+        let token = sign_share_token(&claims, SECRET).unwrap();
+        assert_eq!(verify_share_token(&token, SECRET).unwrap(), claims);
+    }
+
+    #[test]
+    fn rejects_another_secret_and_tampering(){
+        let token = sign_share_token(&ShareClaims{exp: 1771677830, link: 7}, SECRET).unwrap();
+        assert!(matches!(verify_share_token(&token, b"autre secret"), Err(APIError::ShareLink)));
+        // Même signature sur un autre lien
+        let other = sign_share_token(&ShareClaims{exp: 1771677830, link: 8}, SECRET).unwrap();
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let (payload, _) = other.rsplit_once('.').unwrap();
+        assert!(matches!(verify_share_token(&format!("{}.{}", payload, signature), SECRET), Err(APIError::ShareLink)));
+        assert!(matches!(verify_share_token("pas.un.jeton", SECRET), Err(APIError::ShareLink)));
+    }
+
+    #[test]
+    fn expired_token_is_refused_in_base_not_at_decoding(){
+        // L'expiration est contrôlée par claim_use pour que la tentative soit tracée
+        let claims = ShareClaims{exp: 1, link: 7};
+        let token = sign_share_token(&claims, SECRET).unwrap();
+        assert_eq!(verify_share_token(&token, SECRET).unwrap(), claims);
+    }
+
+    #[test]
+    fn refusal_statuses(){
+        assert_eq!(refusal_status(Some(100), 50, 200), REVOKED);
+        assert_eq!(refusal_status(None, 200, 200), EXPIRED);
+        assert_eq!(refusal_status(None, 100, 200), EXPIRED);
+        assert_eq!(refusal_status(None, 300, 200), USED);
+    }
+}
@@ -1,8 +1,11 @@
-use actix_web::{post,web, cookie::Cookie, HttpResponse};
-use crate::{authentification::auth::{Auth, Login}, error::APIError};
+use actix_web::{post,web, cookie::Cookie, HttpRequest, HttpResponse};
+use crate::{authentification::auth::{Auth, Login}, database::known_addresses::remember_address, error::APIError, tasks};
+use std::env;
+use std::net::{IpAddr, ToSocketAddrs};
+use crate::tasks::webhook_delivery::{emit, SIGNIN_NEW_IP};
 
 #[post("/signin")]
-async fn signin(id: web::Json<Login>, auth: web::Data<Auth>) -> Result<HttpResponse,APIError>{
+async fn signin(req: HttpRequest, id: web::Json<Login>, auth: web::Data<Auth>) -> Result<HttpResponse,APIError>{
     let login= Login{
         username: id.username.clone(), 
         password: id.password.clone()
@@ -11,13 +14,49 @@ async fn signin(id: web::Json<Login>, auth: web::Data<Auth>) -> Result<HttpRespo
         Ok(token)=>token,
         Err(e)=>return Err(e)
     };
-    let cookie = Cookie::build("Bearer", token)
+    let cookie = Cookie::build("Bearer", token.clone())
     .path("/")
     .secure(true)
     .http_only(true)
     .finish();
     println!("User: {} signin", id.username);
+    // La session donne accès à la clé du dépôt : analyse des nouvelles archives et rétention
+    let credentials = Auth::decode_token(&token)?;
+    let ip = client_ip(&req).await;
+    match remember_address(&auth.db, &credentials.id, &ip).await{
+        Ok(true)=>emit(&auth.db, &credentials.id, SIGNIN_NEW_IP, serde_json::json!({"ip": ip})),
+        Ok(false)=>(),
+        Err(e)=>println!("Impossible d'enregistrer l'adresse de connexion de {} : {}", id.username, e)
+    }
+    tasks::session::spawn(auth.get_ref().clone(), credentials);
     Ok(HttpResponse::Ok()
     .append_header(("Set-Cookie", cookie.to_string()))
     .body(""))
-}
\ No newline at end of file
+}
+
+/// Adresse du client. Seul nginx (service TRUSTED_PROXY, web par défaut) peut la transmettre dans X-Real-IP :
+/// un client qui joint l'API directement ne choisit pas l'adresse enregistrée
+pub async fn client_ip(req: &HttpRequest)->String{
+    let Some(peer) = req.peer_addr().map(|addr| addr.ip()) else{
+        return String::new()
+    };
+    if let Some(ip) = req.headers().get("X-Real-IP").and_then(|value| value.to_str().ok()).and_then(|value| value.trim().parse::<IpAddr>().ok())
+    && is_trusted_proxy(peer).await{
+        return ip.to_string()
+    }
+    peer.to_string()
+}
+
+/// Résolu à chaque appel : l'adresse du conteneur nginx change quand il redémarre
+async fn is_trusted_proxy(peer: IpAddr)->bool{
+    let proxy = env::var("TRUSTED_PROXY").unwrap_or_else(|_| String::from("web"));
+    let addresses = web::block(move || (proxy.as_str(), 0).to_socket_addrs().map(|addresses| addresses.map(|address| address.ip()).collect::<Vec<IpAddr>>())).await;
+    match addresses{
+        Ok(Ok(addresses))=>addresses.contains(&peer),
+        Ok(Err(e))=>{
+            println!("Adresse du proxy introuvable, X-Real-IP ignoré : {}", e);
+            false
+        },
+        Err(_)=>false
+    }
+}
@@ -0,0 +1,137 @@
+use actix_web::{delete, get, post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::database::devices::get_device;
+use crate::database::ssh_keys::{get_active_key, list_keys, record_key, replace_key, revoke_key, ActiveKey, NewKey, BORG, TUNNEL};
+use crate::error::APIError;
+use crate::tasks::webhook_delivery::{emit, SSH_KEY_INSTALLED, SSH_KEY_REVOKED};
+
+#[derive(Deserialize)]
+struct RotateKey{
+    ssh: String
+}
+
+/// Empreinte d'une clé publique SSH au format de ssh-keygen -l : SHA256:<base64 sans padding>
+fn key_fingerprint(ssh_key: &str)->Option<String>{
+    let blob = ssh_key.split_whitespace().nth(1)?;
+    let decoded = openssl::base64::decode_block(blob).ok()?;
+    let digest = openssl::sha::sha256(&decoded);
+    Some(format!("SHA256:{}", openssl::base64::encode_block(&digest).trim_end_matches('=')))
+}
+
+/// Clé publique sur une seule ligne : une ligne de plus dans la sortie de /internal/authorized_keys serait une clé sans restriction
+fn parse_public_key(ssh_key: &str)->Result<(&str, String), APIError>{
+    let ssh_key = ssh_key.trim();
+    if ssh_key.contains(['\n', '\r']){
+        return Err(APIError::ValidInput)
+    }
+    let (Some(key_type), Some(fingerprint)) = (ssh_key.split_whitespace().next(), key_fingerprint(ssh_key)) else{
+        return Err(APIError::ValidInput)
+    };
+    Ok((key_type, fingerprint))
+}
+
+/// Enregistre la clé, sshd l'accepte dès la connexion suivante (voir authorized_keys.rs). Renvoie son id dans SshKeys
+pub async fn install_key(auth: &Auth, user_id: &str, purpose: &'static str, ssh_key: &str, device_id: Option<u64>)->Result<u64, APIError>{
+    let (key_type, fingerprint) = parse_public_key(ssh_key)?;
+    let device = match device_id{
+        Some(device_id)=>Some(get_device(&auth.db, user_id, device_id).await?),
+        None=>None
+    };
+    let key_id = record_key(&auth.db, user_id, &NewKey{
+        device_id,
+        purpose,
+        key_type,
+        fingerprint: &fingerprint,
+        public_key: ssh_key.trim()
+    }).await?;
+    emit(&auth.db, user_id, SSH_KEY_INSTALLED, json!({
+        "key_id": key_id,
+        "purpose": purpose,
+        "key_type": key_type,
+        "fingerprint": fingerprint,
+        "device": device.as_ref().map(|device| &device.name)
+    }));
+    Ok(key_id)
+}
+
+/// Marque la clé révoquée, sshd la refuse dès la connexion suivante
+pub async fn uninstall_key(auth: &Auth, user_id: &str, key: &ActiveKey)->Result<(), APIError>{
+    revoke_key(&auth.db, user_id, key.id).await?;
+    emit(&auth.db, user_id, SSH_KEY_REVOKED, json!({
+        "key_id": key.id,
+        "purpose": key.purpose,
+        "fingerprint": key.fingerprint,
+        "device_id": key.device_id
+    }));
+    Ok(())
+}
+
+#[get("/ssh_keys")]
+async fn ssh_keys(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    Ok(HttpResponse::Ok().json(list_keys(&auth.db, &repository.id).await?))
+}
+
+#[delete("/ssh_keys/{key_id}")]
+async fn revoke_ssh_key(req: HttpRequest, auth: web::Data<Auth>, key_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    let key = get_active_key(&auth.db, &repository.id, *key_id).await?;
+    uninstall_key(&auth, &repository.id, &key).await?;
+    println!("Clé {} révoquée pour le dépôt : {}", key.fingerprint, repository.id);
+    Ok(HttpResponse::Ok().body(""))
+}
+
+/// L'ancienne clé est révoquée et la nouvelle enregistrée dans la même transaction : sshd voit l'une ou l'autre, jamais les deux
+#[post("/ssh_keys/{key_id}/rotate")]
+async fn rotate_ssh_key(req: HttpRequest, auth: web::Data<Auth>, key_id: web::Path<u64>, body: web::Json<RotateKey>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Owner).await?;
+    let old_key = get_active_key(&auth.db, &repository.id, *key_id).await?;
+    let (key_type, fingerprint) = parse_public_key(&body.ssh)?;
+    if fingerprint == old_key.fingerprint{
+        return Err(APIError::ValidInput)
+    }
+    let purpose = if old_key.purpose == TUNNEL { TUNNEL } else { BORG };
+    let new_key_id = replace_key(&auth.db, &repository.id, old_key.id, &NewKey{
+        device_id: old_key.device_id,
+        purpose,
+        key_type,
+        fingerprint: &fingerprint,
+        public_key: body.ssh.trim()
+    }).await?;
+    println!("Clé {} remplacée par {} pour le dépôt : {}", old_key.fingerprint, fingerprint, repository.id);
+    emit(&auth.db, &repository.id, SSH_KEY_REVOKED, json!({
+        "key_id": old_key.id,
+        "purpose": purpose,
+        "fingerprint": old_key.fingerprint,
+        "device_id": old_key.device_id,
+        "replaced_by": new_key_id
+    }));
+    emit(&auth.db, &repository.id, SSH_KEY_INSTALLED, json!({
+        "key_id": new_key_id,
+        "purpose": purpose,
+        "key_type": key_type,
+        "fingerprint": fingerprint,
+        "device_id": old_key.device_id,
+        "replaces": old_key.id
+    }));
+    Ok(HttpResponse::Ok().json(json!({"id": new_key_id, "fingerprint": fingerprint})))
+}
@@ -0,0 +1,32 @@
+use actix_web::{post, HttpResponse, HttpRequest, web, Result};
+use crate::authentification::auth::Auth;
+use crate::authentification::repository::resolve_repository;
+use crate::database::organisations::Role;
+use crate::borg_script::info::repository_info;
+use crate::database::quotas::{get_usage, save_usage};
+use crate::error::APIError;
+
+
+#[post("/storage_usage")]
+async fn storage_usage(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let repository = resolve_repository(&auth, &req, &credentials, Role::Viewer).await?;
+    println!("storage_usage pour le dépôt : {}", repository.id);
+
+    let key = auth.lease_repository_key(&credentials, &repository).await?;
+    let stats = repository_info(&repository.id, auth.ssh_connexion.clone()).await;
+    key.release().await?;
+    let usage = match stats{
+        Ok(stats)=>save_usage(&auth.db, &repository.id, &stats).await?,
+        Err(e)=>{
+            // Le dépôt est peut-être occupé par une sauvegarde, la dernière mesure reste valable
+            println!("Mesure du dépôt impossible, dernière mesure renvoyée : {}", e);
+            get_usage(&auth.db, &repository.id).await?
+        }
+    };
+    Ok(HttpResponse::Ok().json(usage))
+}
@@ -0,0 +1,121 @@
+use actix_web::{delete, get, post, HttpResponse, HttpRequest, web, Result};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use crate::authentification::auth::Auth;
+use crate::authentification::internal::{valid_internal_token, valid_user_id};
+use crate::borg_script::tunnels::{kill_tunnel, tunnel_sessions};
+use crate::database::admins::require_admin;
+use crate::database::tunnel_leases::{acquire_lease, active_leases, release_port, TunnelLease, KILLED, RELEASED};
+use crate::error::APIError;
+use crate::tasks::tunnel_leases::lease_seconds;
+
+#[derive(Serialize)]
+struct Tunnel{
+    #[serde(flatten)]
+    lease: TunnelLease,
+    /// Le reverse tunnel est ouvert en ce moment
+    connected: bool
+}
+
+/// Port de la plage vu par l'administrateur, avec ou sans bail
+#[derive(Serialize)]
+struct AdminTunnel{
+    port: u16,
+    /// Sessions sshd qui écoutent sur le port, vide si le tunnel est fermé
+    pids: Vec<u32>,
+    /// None : tunnel ouvert sans bail (session bloquée ou antérieure aux baux)
+    lease: Option<TunnelLease>
+}
+
+/// Appelée par alloc_reverse_port.sh (strongholder-tunnel-lease) au début d'une sauvegarde, renvoie le port en texte
+#[post("/tunnel_leases/{user}")]
+async fn acquire_tunnel_lease(req: HttpRequest, auth: web::Data<Auth>, user: web::Path<String>)->Result<HttpResponse, APIError>{
+    if !valid_internal_token(&req){
+        return Err(APIError::ErrorBearer)
+    }
+    if !valid_user_id(&user){
+        return Err(APIError::ValidInput)
+    }
+    // Un port déjà écouté (session sans bail) n'est pas prêté. Sans la liste des tunnels ouverts,
+    // un port occupé pourrait être prêté : la sauvegarde échoue plutôt que d'écouter sur le tunnel d'un autre client
+    let busy: HashSet<u16> = match tunnel_sessions(auth.ssh_connexion.clone()).await{
+        Ok(sessions)=>sessions.iter().map(|session| session.port).collect(),
+        Err(e)=>{
+            println!("Tunnels ouverts inconnus, pas de bail pour le client {} : {}", user, e);
+            return Err(APIError::Script)
+        }
+    };
+    let port = acquire_lease(&auth.db, &user, &busy, lease_seconds()).await?;
+    println!("Port {} prêté au client : {}", port, user);
+    Ok(HttpResponse::Ok().content_type("text/plain; charset=utf-8").body(port.to_string()))
+}
+
+/// Appelée par server_cleanup_key.sh à la fin d'une sauvegarde
+#[delete("/tunnel_leases/{user}/{port}")]
+async fn release_tunnel_lease(req: HttpRequest, auth: web::Data<Auth>, path: web::Path<(String, u16)>)->Result<HttpResponse, APIError>{
+    if !valid_internal_token(&req){
+        return Err(APIError::ErrorBearer)
+    }
+    let (user, port) = path.into_inner();
+    if release_port(&auth.db, port, Some(&user), RELEASED).await?.is_some(){
+        println!("Port {} rendu par le client : {}", port, user);
+    }
+    Ok(HttpResponse::Ok().body(""))
+}
+
+#[get("/tunnels")]
+async fn tunnels(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let leases = active_leases(&auth.db, Some(&credentials.id)).await?;
+    let open: HashSet<u16> = if leases.is_empty(){
+        HashSet::new()
+    }else{
+        tunnel_sessions(auth.ssh_connexion.clone()).await?.iter().map(|session| session.port).collect()
+    };
+    let user_tunnels: Vec<Tunnel> = leases.into_iter().map(|lease| Tunnel{connected: open.contains(&lease.port), lease}).collect();
+    Ok(HttpResponse::Ok().json(user_tunnels))
+}
+
+/// Tous les baux actifs et tous les tunnels ouverts, réservé aux administrateurs
+#[get("/admin/tunnels")]
+async fn admin_tunnels(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    require_admin(&auth.db, &credentials.id).await?;
+    let mut by_port: BTreeMap<u16, AdminTunnel> = BTreeMap::new();
+    for lease in active_leases(&auth.db, None).await?{
+        by_port.insert(lease.port, AdminTunnel{port: lease.port, pids: Vec::new(), lease: Some(lease)});
+    }
+    for session in tunnel_sessions(auth.ssh_connexion.clone()).await?{
+        by_port.entry(session.port)
+        .or_insert_with(|| AdminTunnel{port: session.port, pids: Vec::new(), lease: None})
+        .pids.push(session.pid);
+    }
+    Ok(HttpResponse::Ok().json(by_port.into_values().collect::<Vec<AdminTunnel>>()))
+}
+
+/// Ferme le tunnel ouvert sur le port et libère son bail, réservé aux administrateurs
+#[delete("/admin/tunnels/{port}")]
+async fn kill_tunnel_route(req: HttpRequest, auth: web::Data<Auth>, port: web::Path<u16>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    require_admin(&auth.db, &credentials.id).await?;
+    let killed = kill_tunnel(*port, auth.ssh_connexion.clone()).await?;
+    let lease_id = release_port(&auth.db, *port, None, KILLED).await?;
+    if !killed && lease_id.is_none(){
+        return Err(APIError::NoFile)
+    }
+    println!("Tunnel du port {} fermé par l'administrateur : {}", port, credentials.id);
+    Ok(HttpResponse::Ok().json(json!({"killed": killed, "lease_id": lease_id})))
+}
@@ -0,0 +1,95 @@
+use actix_web::{delete, get, post, HttpResponse, HttpRequest, web, Result};
+use serde::Deserialize;
+use serde_json::json;
+use crate::authentification::auth::Auth;
+use crate::database::webhooks::{delete_webhook, insert_webhook, list_deliveries, list_webhooks, webhook_exists};
+use crate::error::APIError;
+use crate::notify::webhook::parse_url;
+use crate::tasks::webhook_delivery::{enqueue, EVENTS, PING};
+
+const MAX_WEBHOOKS: usize = 10;
+const LIST_LIMIT: u32 = 50;
+
+#[derive(Deserialize)]
+struct NewWebhook{
+    url: String,
+    /// Vide ou ["*"] pour tous les évènements
+    #[serde(default)]
+    events: Vec<String>
+}
+
+#[get("/webhooks")]
+async fn webhooks(req: HttpRequest, auth: web::Data<Auth>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(list_webhooks(&auth.db, &credentials.id).await?))
+}
+
+/// Le secret de signature n'est renvoyé qu'à la création
+#[post("/webhooks")]
+async fn create_webhook(req: HttpRequest, auth: web::Data<Auth>, webhook: web::Json<NewWebhook>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    let mut events = webhook.events.clone();
+    events.sort();
+    events.dedup();
+    if events.is_empty() || events.iter().any(|event| event == "*"){
+        events = vec![String::from("*")];
+    }
+    if parse_url(&webhook.url).is_none() || events.iter().any(|event| event != "*" && !EVENTS.contains(&event.as_str())){
+        return Err(APIError::ValidInput)
+    }
+    if list_webhooks(&auth.db, &credentials.id).await?.len() >= MAX_WEBHOOKS{
+        return Err(APIError::ValidInput)
+    }
+    let mut secret = [0u8; 32];
+    if openssl::rand::rand_bytes(&mut secret).is_err(){
+        return Err(APIError::Notification)
+    }
+    let secret = hex::encode(secret);
+    let webhook_id = insert_webhook(&auth.db, &credentials.id, &webhook.url, &secret, &events).await?;
+    println!("Webhook {} créé pour l'utilisateur : {}", webhook_id, credentials.id);
+    Ok(HttpResponse::Ok().json(json!({"id": webhook_id, "url": webhook.url, "events": events, "secret": secret})))
+}
+
+#[delete("/webhooks/{webhook_id}")]
+async fn remove_webhook(req: HttpRequest, auth: web::Data<Auth>, webhook_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    delete_webhook(&auth.db, &credentials.id, *webhook_id).await?;
+    Ok(HttpResponse::Ok().body(""))
+}
+
+#[get("/webhooks/{webhook_id}/deliveries")]
+async fn webhook_deliveries(req: HttpRequest, auth: web::Data<Auth>, webhook_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    Ok(HttpResponse::Ok().json(list_deliveries(&auth.db, &credentials.id, *webhook_id, LIST_LIMIT).await?))
+}
+
+/// Envoie un évènement ping, son résultat apparaît dans les envois du webhook
+#[post("/webhooks/{webhook_id}/test")]
+async fn test_webhook(req: HttpRequest, auth: web::Data<Auth>, webhook_id: web::Path<u64>)->Result<HttpResponse, APIError>{
+    /* Extraction du cookie JWT */
+    let Some(cookie) = req.cookie("Bearer") else{
+        return Err(APIError::NoCookieBearer)
+    };
+    let credentials= Auth::decode_token(cookie.value())?;
+    webhook_exists(&auth.db, &credentials.id, *webhook_id).await?;
+    let Some(delivery_id) = enqueue(&auth.db, &credentials.id, *webhook_id, PING, &json!({})).await else{
+        return Err(APIError::Database)
+    };
+    Ok(HttpResponse::Accepted().json(json!({"delivery_id": delivery_id})))
+}
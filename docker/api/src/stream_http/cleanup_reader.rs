@@ -0,0 +1,41 @@
+use openssh::Session;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+use crate::stream_http::stream_http::BoxReader;
+
+/// Lecteur d'un fichier préparé sur le serveur, supprimé dès que le lecteur est libéré :
+/// à la fin du flux comme lorsque le client se déconnecte en cours de route.
+pub struct CleanupReader{
+    reader: BoxReader,
+    ssh_connexion: Arc<Session>,
+    cleanup_args: Vec<String>
+}
+
+impl CleanupReader {
+    /// cleanup_args est la commande lancée avec sudo pour supprimer le fichier
+    pub fn new(reader: BoxReader, ssh_connexion: Arc<Session>, cleanup_args: Vec<String>)->Self{
+        Self{reader, ssh_connexion, cleanup_args}
+    }
+}
+
+impl AsyncRead for CleanupReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>)->Poll<io::Result<()>>{
+        self.reader.as_mut().poll_read(cx, buf)
+    }
+}
+
+impl Drop for CleanupReader {
+    fn drop(&mut self){
+        let ssh_connexion = self.ssh_connexion.clone();
+        let cleanup_args = std::mem::take(&mut self.cleanup_args);
+        actix_web::rt::spawn(async move {
+            match ssh_connexion.command("sudo").args(&cleanup_args).status().await{
+                Ok(status) if status.success()=>{},
+                _=>println!("Erreur lors de la suppression du fichier restauré : {:?}", cleanup_args)
+            }
+        });
+    }
+}
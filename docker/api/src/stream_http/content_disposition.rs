@@ -0,0 +1,22 @@
+use actix_web::http::header::{Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue};
+
+/// Construit un Content-Disposition "attachment" pour le nom de fichier donné.
+/// Le paramètre filename ne contient que de l'ASCII (les autres caractères sont remplacés par _),
+/// le vrai nom est transmis encodé en UTF-8 dans filename* (RFC 6266).
+pub fn attachment(file_name: &str)->ContentDisposition{
+    let ascii_name: String = file_name.chars()
+        .map(|c| if c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {c} else {'_'})
+        .collect();
+    let mut parameters = vec![DispositionParam::Filename(ascii_name.clone())];
+    if ascii_name != file_name{
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue{
+            charset: Charset::Ext(String::from("UTF-8")),
+            language_tag: None,
+            value: file_name.as_bytes().to_vec()
+        }));
+    }
+    ContentDisposition{
+        disposition: DispositionType::Attachment,
+        parameters
+    }
+}
@@ -1 +1,6 @@
-pub mod stream_http;
\ No newline at end of file
+pub mod stream_http;
+pub mod tar_to_zip;
+pub mod content_disposition;
+pub mod remote_stream;
+pub mod range;
+pub mod cleanup_reader;
@@ -0,0 +1,149 @@
+use actix_web::{HttpRequest, HttpResponse, HttpResponseBuilder, http::{StatusCode, header}};
+
+/// Portion du fichier demandée par le client (en-têtes Range et If-Range)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest{
+    /// Tout le fichier
+    Full,
+    /// De start à end inclus
+    Partial{start: u64, end: u64},
+    /// La plage demandée est hors du fichier
+    Unsatisfiable
+}
+
+impl RangeRequest {
+    /// Lit les en-têtes de la requête pour un fichier de taille size et d'ETag etag.
+    /// Une plage mal formée ou multiple est ignorée, le fichier complet est alors renvoyé (RFC 9110).
+    pub fn from_request(req: &HttpRequest, size: u64, etag: &str)->Self{
+        let Some(range) = req.headers().get(header::RANGE).and_then(|value| value.to_str().ok()) else{
+            return RangeRequest::Full
+        };
+        // Si le fichier a changé depuis le début du téléchargement, la reprise n'a plus de sens
+        if let Some(if_range) = req.headers().get(header::IF_RANGE) && if_range.to_str().ok() != Some(etag){
+            return RangeRequest::Full
+        }
+        let Some(range) = range.trim().strip_prefix("bytes=") else{
+            return RangeRequest::Full
+        };
+        if range.contains(','){
+            return RangeRequest::Full
+        }
+        let Some((start, end)) = range.split_once('-') else{
+            return RangeRequest::Full
+        };
+        let (start, end) = (start.trim(), end.trim());
+
+        // bytes=-500 : les 500 derniers octets
+        if start.is_empty(){
+            return match end.parse::<u64>() {
+                Ok(0)=>RangeRequest::Unsatisfiable,
+                Ok(_) if size == 0=>RangeRequest::Unsatisfiable,
+                Ok(suffix)=>RangeRequest::Partial{start: size.saturating_sub(suffix), end: size - 1},
+                Err(_)=>RangeRequest::Full
+            }
+        }
+        let Ok(start) = start.parse::<u64>() else{
+            return RangeRequest::Full
+        };
+        let end = if end.is_empty(){
+            size.saturating_sub(1)
+        }else{
+            match end.parse::<u64>() {
+                Ok(end) if end >= start=>end.min(size.saturating_sub(1)),
+                _=>return RangeRequest::Full
+            }
+        };
+        if start >= size{
+            return RangeRequest::Unsatisfiable
+        }
+        RangeRequest::Partial{start, end}
+    }
+
+    /// Position de départ et nombre d'octets à envoyer
+    pub fn bounds(&self, size: u64)->(u64, u64){
+        match *self {
+            RangeRequest::Partial{start, end}=>(start, end - start + 1),
+            _=>(0, size)
+        }
+    }
+
+    /// Réponse avec le statut et les en-têtes correspondant à la plage, le corps reste à ajouter.
+    /// Pour Unsatisfiable, la réponse 416 peut être terminée directement.
+    pub fn response(&self, size: u64, etag: &str)->HttpResponseBuilder{
+        let mut response = match *self {
+            RangeRequest::Full=>HttpResponse::Ok(),
+            RangeRequest::Partial{start, end}=>{
+                let mut response = HttpResponse::build(StatusCode::PARTIAL_CONTENT);
+                response.insert_header((header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size)));
+                response
+            },
+            RangeRequest::Unsatisfiable=>{
+                let mut response = HttpResponse::build(StatusCode::RANGE_NOT_SATISFIABLE);
+                response.insert_header((header::CONTENT_RANGE, format!("bytes */{}", size)));
+                response
+            }
+        };
+        response.insert_header((header::ACCEPT_RANGES, "bytes"));
+        response.insert_header((header::ETAG, etag.to_string()));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const ETAG: &str = "\"abc\"";
+
+    fn request(headers: &[(header::HeaderName, &str)], size: u64)->RangeRequest{
+        let mut req = TestRequest::default();
+        for (name, value) in headers{
+            req = req.insert_header((name.clone(), *value));
+        }
+        RangeRequest::from_request(&req.to_http_request(), size, ETAG)
+    }
+
+    #[test]
+    fn suffix_range_is_the_end_of_the_file(){
+        assert_eq!(request(&[(header::RANGE, "bytes=-500")], 1000), RangeRequest::Partial{start: 500, end: 999});
+        assert_eq!(request(&[(header::RANGE, "bytes=-5000")], 1000), RangeRequest::Partial{start: 0, end: 999});
+        assert_eq!(request(&[(header::RANGE, "bytes=-0")], 1000), RangeRequest::Unsatisfiable);
+    }
+
+    #[test]
+    fn open_ended_range_goes_to_the_end(){
+        let range = request(&[(header::RANGE, "bytes=200-")], 1000);
+        assert_eq!(range, RangeRequest::Partial{start: 200, end: 999});
+        assert_eq!(range.bounds(1000), (200, 800));
+        let response = range.response(1000, ETAG).finish();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers().get(header::CONTENT_RANGE).unwrap(), "bytes 200-999/1000");
+    }
+
+    #[test]
+    fn start_beyond_the_file_is_unsatisfiable(){
+        for range in ["bytes=1000-", "bytes=1500-2000"]{
+            let range = request(&[(header::RANGE, range)], 1000);
+            assert_eq!(range, RangeRequest::Unsatisfiable);
+            let response = range.response(1000, ETAG).finish();
+            assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+            assert_eq!(response.headers().get(header::CONTENT_RANGE).unwrap(), "bytes */1000");
+        }
+    }
+
+    #[test]
+    fn if_range_mismatch_sends_the_whole_file(){
+        let range = request(&[(header::RANGE, "bytes=200-"), (header::IF_RANGE, "\"old\"")], 1000);
+        assert_eq!(range, RangeRequest::Full);
+        assert_eq!(range.response(1000, ETAG).finish().status(), StatusCode::OK);
+        assert_eq!(request(&[(header::RANGE, "bytes=200-"), (header::IF_RANGE, ETAG)], 1000), RangeRequest::Partial{start: 200, end: 999});
+    }
+
+    #[test]
+    fn multiple_ranges_are_ignored(){
+        let range = request(&[(header::RANGE, "bytes=0-99,200-299")], 1000);
+        assert_eq!(range, RangeRequest::Full);
+        assert_eq!(range.bounds(1000), (0, 1000));
+    }
+}
@@ -0,0 +1,160 @@
+use openssh::{ChildStdout, Session, Stdio};
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader, ReadBuf};
+use tokio::sync::{oneshot, watch};
+use serde::Deserialize;
+use crate::error::APIError;
+
+const FIRST_CHUNK_SIZE: usize = 32*1024;
+
+/// Ligne de progression de borg avec --progress --log-json
+#[derive(Deserialize)]
+struct BorgProgress{
+    r#type: String,
+    current: Option<f64>,
+    total: Option<f64>
+}
+
+/// Pourcentage d'avancement si la ligne est une progression borg
+fn progress_percent(line: &str)->Option<f64>{
+    let progress: BorgProgress = serde_json::from_str(line).ok()?;
+    if progress.r#type != "progress_percent"{
+        return None
+    }
+    match (progress.current, progress.total) {
+        (Some(current), Some(total)) if total > 0.0=>Some((current*100.0/total).min(100.0)),
+        _=>Some(0.0)
+    }
+}
+
+/// Sortie standard d'un script lancé en ssh, lue au fur et à mesure que le client HTTP la consomme.
+/// Rien n'est écrit sur le disque du serveur : si le client lit lentement, le pipe ssh se remplit et le script attend.
+/// Si le flux est abandonné avant la fin (client déconnecté), la commande d'annulation est lancée sur le serveur.
+pub struct RemoteStream{
+    first_chunk: Vec<u8>,
+    offset: usize,
+    stdout: ChildStdout,
+    /// Vrai si le script s'est terminé avec succès
+    status: oneshot::Receiver<bool>,
+    finished: bool,
+    ssh_connexion: Arc<Session>,
+    cancel_args: Vec<String>
+}
+
+impl RemoteStream {
+    /// Lance `sudo args...` et attend les premiers octets.
+    /// Une erreur du script avant toute donnée (archive inconnue, clé absente...) est donc renvoyée ici,
+    /// avant que la réponse HTTP ne soit commencée.
+    /// Les lignes de progression de borg (--progress --log-json) sont envoyées dans progress en pourcentage.
+    pub async fn spawn(ssh_connexion: Arc<Session>, args: Vec<String>, cancel_args: Vec<String>, progress: Option<watch::Sender<f64>>)->Result<Self, APIError>{
+        let mut child = match ssh_connexion.clone().arc_command("sudo").args(args).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().await{
+            Ok(child)=>child,
+            Err(_)=>{println!("connexion ssh erreur");return Err(APIError::Ssh)}
+        };
+        let (Some(mut stdout), Some(stderr)) = (child.stdout().take(), child.stderr().take()) else{
+            println!("Sortie du script distant indisponible");
+            return Err(APIError::Ssh)
+        };
+
+        let (sender, mut status) = oneshot::channel();
+        actix_web::rt::spawn(async move {
+            // stderr est lu en parallèle pour que le script ne bloque pas sur un pipe plein
+            let mut error = String::new();
+            // découpage en octets : un nom de fichier non UTF-8 ne doit pas interrompre la lecture
+            let mut lines = BufReader::new(stderr).split(b'\n');
+            while let Ok(Some(line)) = lines.next_segment().await{
+                let line = String::from_utf8_lossy(&line);
+                match progress_percent(&line) {
+                    Some(percent)=>if let Some(progress) = &progress{
+                        let _ = progress.send(percent);
+                    },
+                    None=>{
+                        error.push_str(&line);
+                        error.push('\n');
+                    }
+                }
+            }
+            let success = match child.wait().await{
+                Ok(exit_status)=>exit_status.success(),
+                Err(e)=>{println!("Erreur attente script distant : {}", e);false}
+            };
+            if ! success{
+                println!("Erreur du script distant\nstderr: {}", error);
+            }
+            let _ = sender.send(success);
+        });
+
+        let mut first_chunk = vec![0u8; FIRST_CHUNK_SIZE];
+        let size = match stdout.read(&mut first_chunk).await{
+            Ok(size)=>size,
+            Err(e)=>{println!("Erreur lecture sortie du script distant : {}", e);return Err(APIError::Ssh)}
+        };
+        first_chunk.truncate(size);
+        if size == 0{
+            // Même terminé avec succès, un flux vide n'est pas une archive
+            let _ = (&mut status).await;
+            return Err(APIError::Script)
+        }
+
+        Ok(Self{
+            first_chunk,
+            offset: 0,
+            stdout,
+            status,
+            finished: false,
+            ssh_connexion,
+            cancel_args
+        })
+    }
+}
+
+impl AsyncRead for RemoteStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>)->Poll<io::Result<()>>{
+        let this = self.as_mut().get_mut();
+        if this.offset < this.first_chunk.len(){
+            let end = (this.offset + buf.remaining()).min(this.first_chunk.len());
+            buf.put_slice(&this.first_chunk[this.offset..end]);
+            this.offset = end;
+            return Poll::Ready(Ok(()))
+        }
+
+        let filled = buf.filled().len();
+        match Pin::new(&mut this.stdout).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) if buf.filled().len() == filled => {},
+            other => return other
+        }
+
+        // Fin de la sortie : le flux n'est valide que si le script a réussi,
+        // sinon on renvoie une erreur pour que le client ne prenne pas une archive tronquée pour complète
+        match Pin::new(&mut this.status).poll(cx) {
+            Poll::Ready(Ok(true))=>{
+                this.finished = true;
+                Poll::Ready(Ok(()))
+            },
+            Poll::Ready(_)=>{
+                this.finished = true;
+                Poll::Ready(Err(io::Error::other("le script distant a échoué en cours de flux")))
+            },
+            Poll::Pending=>Poll::Pending
+        }
+    }
+}
+
+impl Drop for RemoteStream {
+    fn drop(&mut self){
+        if self.finished{
+            return
+        }
+        println!("Flux abandonné, annulation du script distant");
+        let ssh_connexion = self.ssh_connexion.clone();
+        let cancel_args = std::mem::take(&mut self.cancel_args);
+        actix_web::rt::spawn(async move {
+            if ssh_connexion.command("sudo").args(cancel_args).status().await.is_err(){
+                println!("Erreur ssh lors de l'annulation du script distant");
+            }
+        });
+    }
+}
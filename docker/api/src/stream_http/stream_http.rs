@@ -2,16 +2,17 @@ use futures_core::{stream::Stream, task::{Poll, Context}};
 use tokio::io::{AsyncRead,ReadBuf};
 use std::pin::Pin;
 use bytes::Bytes;
-use openssh_sftp_client::file::TokioCompatFile;
 
+/// Flux de lecture quelconque (fichier sftp, conversion zip...)
+pub type BoxReader = Pin<Box<dyn AsyncRead>>;
 
 pub struct StreamBuffer{
-    reader: Pin<Box<TokioCompatFile>>,
+    reader: BoxReader,
     buf: [u8; 32*1024]
 }
 
 impl StreamBuffer {
-    pub fn new(reader: TokioCompatFile )->Self{
+    pub fn new(reader: impl AsyncRead + 'static)->Self{
         Self{reader:Box::pin(reader), buf: [0u8;32*1024]}
     }
 }
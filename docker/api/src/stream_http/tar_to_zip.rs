@@ -0,0 +1,62 @@
+use async_zip::{Compression, ZipDateTime, ZipEntryBuilder, tokio::write::ZipFileWriter};
+use chrono::DateTime;
+use std::future::poll_fn;
+use std::pin::Pin;
+use futures_core::Stream;
+use tokio::io::{AsyncRead, DuplexStream, duplex};
+use tokio_tar::Archive;
+use tokio_util::compat::FuturesAsyncWriteCompatExt;
+
+const PIPE_SIZE: usize = 64*1024;
+
+/// Convertit à la volée une archive tar en archive zip.
+/// Le zip est écrit dans un pipe en mémoire au fur et à mesure que le client le lit,
+/// l'archive n'est donc jamais entièrement chargée en mémoire ni écrite sur disque.
+pub fn tar_to_zip(tar: impl AsyncRead + Unpin + 'static)->DuplexStream{
+    let (writer, reader) = duplex(PIPE_SIZE);
+    actix_web::rt::spawn(async move {
+        if let Err(e) = write_zip(tar, writer).await{
+            // Arrive aussi lorsque le client ferme la connexion en cours de route
+            println!("Erreur lors de la conversion tar vers zip : {}", e);
+        }
+    });
+    reader
+}
+
+async fn write_zip(tar: impl AsyncRead + Unpin, writer: DuplexStream)->Result<(), String>{
+    let mut archive = Archive::new(tar);
+    let mut entries = archive.entries().map_err(|e| e.to_string())?;
+    let mut zip = ZipFileWriter::with_tokio(writer);
+
+    while let Some(entry) = poll_fn(|cx| Pin::new(&mut entries).poll_next(cx)).await{
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let mode = header.mode().unwrap_or(0o644);
+        let mtime = header.mtime().unwrap_or(0);
+        let mut path = entry.path().map_err(|e| e.to_string())?.to_string_lossy().into_owned();
+
+        let date = DateTime::from_timestamp(mtime as i64, 0).unwrap_or_default();
+        if entry_type.is_dir(){
+            if ! path.ends_with('/'){
+                path.push('/');
+            }
+            let builder = ZipEntryBuilder::new(path.into(), Compression::Stored)
+                .last_modification_date(ZipDateTime::from_chrono(&date))
+                .unix_permissions(mode as u16);
+            zip.write_entry_whole(builder, &[]).await.map_err(|e| e.to_string())?;
+        }else if entry_type.is_file(){
+            let builder = ZipEntryBuilder::new(path.into(), Compression::Deflate)
+                .last_modification_date(ZipDateTime::from_chrono(&date))
+                .unix_permissions(mode as u16);
+            let mut entry_writer = zip.write_entry_stream(builder).await.map_err(|e| e.to_string())?.compat_write();
+            tokio::io::copy(&mut entry, &mut entry_writer).await.map_err(|e| e.to_string())?;
+            entry_writer.into_inner().close().await.map_err(|e| e.to_string())?;
+        }else{
+            // Liens et fichiers spéciaux n'ont pas d'équivalent fiable dans un zip
+            println!("Entrée ignorée lors de la conversion zip : {}", path);
+        }
+    }
+    zip.close().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
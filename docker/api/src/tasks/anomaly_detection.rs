@@ -0,0 +1,329 @@
+use jsonwebtoken::get_current_timestamp;
+use std::collections::{HashMap, HashSet};
+use crate::authentification::auth::{Auth, Credentials};
+use crate::authentification::repository::Repository;
+use crate::borg_script::diff::diff_archive;
+use crate::borg_script::info::{archive_info, ArchiveStats};
+use crate::borg_script::list_archive::{archive_device, list_archive};
+use crate::database::archive_analyses::{analyzed_archives, healthy_history, save_analysis, ArchiveAnalysis};
+use crate::database::archive_locks::lock_archives;
+use crate::database::events::{record_event, CRITICAL};
+use crate::error::APIError;
+
+/// Nombre maximal d'archives analysées par session (borg diff peut être long)
+const MAX_ARCHIVES_PER_SESSION: usize = 5;
+/// Nombre d'archives saines servant de référence
+const HISTORY_LENGTH: usize = 10;
+/// Archives saines lues pour retrouver l'historique de chaque appareil
+const HISTORY_WINDOW: u32 = 50;
+/// Archives précédant une archive suspecte protégées de la rétention
+const PROTECTED_ARCHIVES: usize = 5;
+const LOCK_DAYS: u64 = 30;
+
+/// En dessous, les proportions de fichiers n'ont pas de sens
+const MIN_FILES: u64 = 50;
+const MODIFIED_RATIO: f64 = 0.8;
+const REMOVED_RATIO: f64 = 0.5;
+/// Baisse du nombre de fichiers par rapport à la moyenne
+const FILE_COUNT_DROP: f64 = 0.5;
+/// Des fichiers chiffrés par un ransomware ne se dédupliquent plus :
+/// la part de données nouvelles passe d'habituellement faible à presque tout
+const DEDUP_MIN_SIZE: u64 = 10*1024*1024;
+const DEDUP_USUAL_RATIO: f64 = 0.3;
+const DEDUP_COLLAPSE_RATIO: f64 = 0.7;
+
+/// Compare les nouvelles archives à l'historique de leur appareil.
+/// Les chiffres viennent de borg sur le serveur et non du client, qui peut être compromis.
+pub async fn analyze_new_archives(auth: &Auth, credentials: &Credentials, repository: &Repository)->bool{
+    let result = match auth.lease_repository_key(credentials, repository).await{
+        Ok(key)=>{
+            let result = analyze(auth, &repository.id).await;
+            key.release().await.and(result)
+        },
+        Err(e)=>Err(e)
+    };
+    caught_up(&repository.id, result)
+}
+
+/// Même analyse pour le planificateur de la rétention, qui détient déjà un bail sur la clé du dépôt
+pub async fn analyze_leased(auth: &Auth, repository_id: &String)->bool{
+    caught_up(repository_id, analyze(auth, repository_id).await)
+}
+
+/// Vrai si toutes les archives du dépôt sont analysées : la rétention ne doit pas supprimer
+/// une archive qu'une archive suspecte encore en attente aurait verrouillée
+fn caught_up(repository_id: &str, result: Result<(usize, bool), APIError>)->bool{
+    match result{
+        Ok((0, caught_up))=>caught_up,
+        Ok((count, caught_up))=>{
+            println!("{} nouvelles archives analysées pour {}", count, repository_id);
+            caught_up
+        },
+        Err(e)=>{
+            println!("Erreur lors de l'analyse des archives de {} : {}", repository_id, e);
+            false
+        }
+    }
+}
+
+/// Archive à analyser avec ce qui a changé depuis la précédente
+struct NewArchive{
+    stats: ArchiveStats,
+    /// (ajoutés, modifiés, supprimés), None si borg diff a échoué
+    changes: Option<(u64, u64, u64)>,
+    /// Archives de données plus anciennes du même appareil, de la plus récente à la plus ancienne
+    earlier_archives: Vec<String>
+}
+
+/// Nombre d'archives analysées et vrai s'il n'en reste plus, l'appelant détient un bail sur la clé du dépôt
+async fn analyze(auth: &Auth, repository_id: &String)->Result<(usize, bool), APIError>{
+    let analyzed: HashSet<String> = analyzed_archives(&auth.db, repository_id).await?.into_iter().collect();
+    let (new_archives, caught_up) = collect_new_archives(auth, repository_id, &analyzed).await?;
+
+    let mut history = healthy_history(&auth.db, repository_id, HISTORY_WINDOW).await?;
+    let mut previous_nfiles: HashMap<Option<String>, u64> = HashMap::new();
+    for new_archive in &new_archives{
+        let device = archive_device(&new_archive.stats.name).map(str::to_string);
+        let device_history: Vec<&ArchiveAnalysis> = history.iter()
+        .filter(|analysis| archive_device(&analysis.archive_name) == device.as_deref())
+        .take(HISTORY_LENGTH)
+        .collect();
+        let previous = previous_nfiles.get(&device).copied().or(device_history.first().map(|analysis| analysis.nfiles));
+        let reasons = evaluate(new_archive, previous, &device_history);
+        let (added, modified, removed) = new_archive.changes.unwrap_or_default();
+        let analysis = ArchiveAnalysis{
+            archive_name: new_archive.stats.name.clone(),
+            nfiles: new_archive.stats.nfiles,
+            original_size: new_archive.stats.original_size,
+            deduplicated_size: new_archive.stats.deduplicated_size,
+            added,
+            modified,
+            removed,
+            suspicious: !reasons.is_empty(),
+            reasons: (!reasons.is_empty()).then(|| reasons.join("; ")),
+            analyzed_at: get_current_timestamp()
+        };
+        save_analysis(&auth.db, repository_id, &analysis).await?;
+        previous_nfiles.insert(device, analysis.nfiles);
+        if analysis.suspicious{
+            flag_suspicious(auth, repository_id, new_archive, &reasons).await?;
+        }else{
+            history.insert(0, analysis);
+            history.truncate(HISTORY_WINDOW as usize);
+        }
+    }
+    Ok((new_archives.len(), caught_up))
+}
+
+/// Archives plus récentes que la dernière archive analysée (les plus récentes seulement au premier passage),
+/// au plus MAX_ARCHIVES_PER_SESSION. Vrai si aucune archive plus récente n'attend le passage suivant
+async fn collect_new_archives(auth: &Auth, repository_id: &String, analyzed: &HashSet<String>)->Result<(Vec<NewArchive>, bool), APIError>{
+    let archives = list_archive(repository_id, auth.ssh_connexion.clone()).await?;
+    let mut data_archives: Vec<(String, String)> = archives.archives.into_iter()
+    .filter(|archive| !archive.archive.ends_with("_logs"))
+    .map(|archive| (archive.time, archive.archive))
+    .collect();
+    // Les dates ISO se trient comme des chaînes
+    data_archives.sort();
+    let names: Vec<String> = data_archives.into_iter().map(|(_, name)| name).collect();
+
+    let first_new = match names.iter().rposition(|name| analyzed.contains(name)){
+        Some(last_analyzed)=>last_analyzed + 1,
+        None=>names.len().saturating_sub(MAX_ARCHIVES_PER_SESSION)
+    };
+    let last = names.len().min(first_new + MAX_ARCHIVES_PER_SESSION);
+
+    let mut new_archives = Vec::new();
+    for index in first_new..last{
+        let name = &names[index];
+        let device = archive_device(name);
+        // Les archives d'un autre appareil ne servent pas de comparaison
+        let earlier_archives: Vec<String> = names[..index].iter().rev()
+        .filter(|earlier| archive_device(earlier) == device)
+        .cloned()
+        .collect();
+        let stats = archive_info(repository_id, name, auth.ssh_connexion.clone()).await?;
+        let changes = match earlier_archives.first(){
+            Some(previous)=>match diff_archive(repository_id, auth.ssh_connexion.clone(), previous, name, None).await{
+                Ok(diff)=>Some((diff.added as u64, diff.modified as u64, diff.removed as u64)),
+                Err(e)=>{println!("Diff impossible pour l'analyse de {} : {}", name, e);None}
+            },
+            None=>None
+        };
+        new_archives.push(NewArchive{
+            stats,
+            changes,
+            earlier_archives
+        });
+    }
+    Ok((new_archives, last == names.len()))
+}
+
+/// Raisons pour lesquelles l'archive est suspecte, vide si elle ressemble à l'historique
+fn evaluate(new_archive: &NewArchive, previous_nfiles: Option<u64>, history: &[&ArchiveAnalysis])->Vec<String>{
+    let mut reasons = Vec::new();
+    let stats = &new_archive.stats;
+
+    if let (Some((_, modified, removed)), Some(previous_nfiles)) = (new_archive.changes, previous_nfiles) && previous_nfiles >= MIN_FILES{
+        let modified_ratio = modified as f64 / previous_nfiles as f64;
+        if modified_ratio >= MODIFIED_RATIO{
+            reasons.push(format!("{:.0}% des fichiers modifiés", modified_ratio*100.0));
+        }
+        let removed_ratio = removed as f64 / previous_nfiles as f64;
+        if removed_ratio >= REMOVED_RATIO{
+            reasons.push(format!("{:.0}% des fichiers supprimés", removed_ratio*100.0));
+        }
+    }
+
+    if history.is_empty(){
+        return reasons
+    }
+    let average_nfiles = history.iter().map(|analysis| analysis.nfiles).sum::<u64>() / history.len() as u64;
+    if average_nfiles >= MIN_FILES && (stats.nfiles as f64) < average_nfiles as f64 * (1.0 - FILE_COUNT_DROP){
+        reasons.push(format!("nombre de fichiers passé de {} en moyenne à {}", average_nfiles, stats.nfiles));
+    }
+
+    let ratios: Vec<f64> = history.iter()
+    .filter(|analysis| analysis.original_size >= DEDUP_MIN_SIZE)
+    .map(|analysis| analysis.deduplicated_size as f64 / analysis.original_size as f64)
+    .collect();
+    if ratios.len() >= 3 && stats.original_size >= DEDUP_MIN_SIZE{
+        let usual_ratio = ratios.iter().sum::<f64>() / ratios.len() as f64;
+        let ratio = stats.deduplicated_size as f64 / stats.original_size as f64;
+        if usual_ratio < DEDUP_USUAL_RATIO && ratio > DEDUP_COLLAPSE_RATIO{
+            reasons.push(format!("déduplication effondrée : {:.0}% de données nouvelles contre {:.0}% habituellement", ratio*100.0, usual_ratio*100.0));
+        }
+    }
+    reasons
+}
+
+/// Alerte critique et verrouillage des archives précédentes pour qu'elles restent restaurables
+async fn flag_suspicious(auth: &Auth, user_id: &str, new_archive: &NewArchive, reasons: &[String])->Result<(), APIError>{
+    let protected: Vec<String> = new_archive.earlier_archives.iter().take(PROTECTED_ARCHIVES).cloned().collect();
+    let locked_until = get_current_timestamp() + LOCK_DAYS*24*60*60;
+    let reason = format!("archive suspecte {}", new_archive.stats.name);
+    lock_archives(&auth.db, user_id, &protected, locked_until, &reason).await?;
+    record_event(&auth.db, user_id, CRITICAL, "suspicious_archive",
+        &format!("Archive {} suspecte : {}. {} archives précédentes protégées pendant {} jours",
+        new_archive.stats.name, reasons.join("; "), protected.len(), LOCK_DAYS)).await
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const MB: u64 = 1024*1024;
+
+    fn new_archive(nfiles: u64, original_size: u64, deduplicated_size: u64, changes: Option<(u64, u64, u64)>)->NewArchive{
+        NewArchive{
+            stats: ArchiveStats{
+                name: String::from("laptop@new"),
+                start: String::new(),
+                end: String::new(),
+                original_size,
+                compressed_size: original_size,
+                deduplicated_size,
+                nfiles
+            },
+            changes,
+            earlier_archives: Vec::new()
+        }
+    }
+
+    fn analysis(nfiles: u64, original_size: u64, deduplicated_size: u64)->ArchiveAnalysis{
+        ArchiveAnalysis{
+            archive_name: String::from("laptop@old"),
+            nfiles,
+            original_size,
+            deduplicated_size,
+            added: 0,
+            modified: 0,
+            removed: 0,
+            suspicious: false,
+            reasons: None,
+            analyzed_at: 0
+        }
+    }
+
+    /// Trois archives de 100 fichiers et 100 Mo dont 10% de données nouvelles
+    fn history()->Vec<ArchiveAnalysis>{
+        (0..3).map(|_| analysis(100, 100*MB, 10*MB)).collect()
+    }
+
+    #[test]
+    fn an_ordinary_archive_is_not_suspicious(){
+        let history = history();
+        let history: Vec<&ArchiveAnalysis> = history.iter().collect();
+        let archive = new_archive(105, 100*MB, 12*MB, Some((5, 10, 0)));
+        assert!(evaluate(&archive, Some(100), &history).is_empty());
+    }
+
+    #[test]
+    fn most_files_modified_is_suspicious(){
+        let archive = new_archive(100, 100*MB, 10*MB, Some((0, 80, 0)));
+        let reasons = evaluate(&archive, Some(100), &[]);
+        assert_eq!(reasons, ["80% des fichiers modifiés"]);
+    }
+
+    #[test]
+    fn half_of_the_files_removed_is_suspicious(){
+        let archive = new_archive(50, 100*MB, 10*MB, Some((0, 0, 50)));
+        let reasons = evaluate(&archive, Some(100), &[]);
+        assert_eq!(reasons, ["50% des fichiers supprimés"]);
+    }
+
+    #[test]
+    fn ratios_are_ignored_below_min_files(){
+        // Tous les fichiers modifiés ou supprimés, mais l'archive précédente en avait moins de MIN_FILES
+        let archive = new_archive(0, 100*MB, 10*MB, Some((0, 49, 49)));
+        assert!(evaluate(&archive, Some(MIN_FILES - 1), &[]).is_empty());
+        // Sans diff ou sans archive précédente, aucune proportion n'est calculée
+        assert!(evaluate(&new_archive(0, 100*MB, 10*MB, None), Some(100), &[]).is_empty());
+        assert!(evaluate(&new_archive(0, 100*MB, 10*MB, Some((0, 100, 100))), None, &[]).is_empty());
+    }
+
+    #[test]
+    fn a_file_count_drop_is_suspicious(){
+        let history = history();
+        let history: Vec<&ArchiveAnalysis> = history.iter().collect();
+        let reasons = evaluate(&new_archive(49, 100*MB, 10*MB, None), None, &history);
+        assert_eq!(reasons, ["nombre de fichiers passé de 100 en moyenne à 49"]);
+        // Une baisse de moitié exactement reste tolérée
+        assert!(evaluate(&new_archive(50, 100*MB, 10*MB, None), None, &history).is_empty());
+    }
+
+    #[test]
+    fn a_file_count_drop_is_ignored_below_min_files(){
+        let history: Vec<ArchiveAnalysis> = (0..3).map(|_| analysis(MIN_FILES - 1, 100*MB, 10*MB)).collect();
+        let history: Vec<&ArchiveAnalysis> = history.iter().collect();
+        assert!(evaluate(&new_archive(0, 100*MB, 10*MB, None), None, &history).is_empty());
+    }
+
+    #[test]
+    fn a_dedup_collapse_is_suspicious(){
+        let history = history();
+        let history: Vec<&ArchiveAnalysis> = history.iter().collect();
+        let reasons = evaluate(&new_archive(100, 100*MB, 90*MB, None), None, &history);
+        assert_eq!(reasons, ["déduplication effondrée : 90% de données nouvelles contre 10% habituellement"]);
+    }
+
+    #[test]
+    fn a_dedup_collapse_needs_large_enough_archives(){
+        // Nouvelle archive trop petite
+        let history = history();
+        let history: Vec<&ArchiveAnalysis> = history.iter().collect();
+        assert!(evaluate(&new_archive(100, DEDUP_MIN_SIZE - 1, DEDUP_MIN_SIZE - 1, None), None, &history).is_empty());
+        // Moins de trois archives de référence assez grosses
+        let small: Vec<ArchiveAnalysis> = vec![analysis(100, 100*MB, 10*MB), analysis(100, 100*MB, 10*MB), analysis(100, DEDUP_MIN_SIZE - 1, 0)];
+        let small: Vec<&ArchiveAnalysis> = small.iter().collect();
+        assert!(evaluate(&new_archive(100, 100*MB, 90*MB, None), None, &small).is_empty());
+    }
+
+    #[test]
+    fn all_reasons_are_reported_together(){
+        let history = history();
+        let history: Vec<&ArchiveAnalysis> = history.iter().collect();
+        let reasons = evaluate(&new_archive(40, 100*MB, 95*MB, Some((0, 90, 60))), Some(100), &history);
+        assert_eq!(reasons.len(), 4);
+    }
+}
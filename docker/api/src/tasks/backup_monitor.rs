@@ -0,0 +1,173 @@
+use chrono::NaiveDateTime;
+use jsonwebtoken::get_current_timestamp;
+use std::time::Duration;
+use crate::authentification::auth::{Auth, Credentials};
+use crate::authentification::repository::Repository;
+use crate::borg_script::last_transaction::last_transaction_at;
+use crate::borg_script::list_archive::list_archive;
+use crate::database::backup_reports::{last_report, last_successful_report_at, ERROR};
+use crate::database::backup_schedules::{self, get_schedule, list_enabled, set_failed_alert_for, set_missed_alert_for, MonitoredSchedule};
+use crate::database::events::{CRITICAL, WARNING};
+use crate::error::APIError;
+use crate::notify::{dispatch, Alert, Recipients};
+use super::env_minutes;
+use super::webhook_delivery::{self, MISSED_BACKUP};
+
+const DEFAULT_INTERVAL_MINUTES: u64 = 60;
+
+/// Vérifie régulièrement que chaque compte avec un planning actif a sauvegardé à temps.
+/// Sans la clé du dépôt, le serveur se fie à la dernière transaction validée par un client dans le dépôt,
+/// ce qui couvre les sauvegardes lancées par cron sans l'application, ainsi qu'aux rapports de sauvegarde
+/// et à la dernière archive vue par list_archive lors d'une session.
+pub fn spawn_scheduler(auth: Auth){
+    let interval = env_minutes("BACKUP_MONITOR_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    println!("Détection des sauvegardes manquées toutes les {} minutes", interval);
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval*60));
+        loop {
+            ticker.tick().await;
+            let schedules = match list_enabled(&auth.db).await{
+                Ok(schedules)=>schedules,
+                Err(e)=>{println!("Erreur lors de la détection des sauvegardes manquées : {}", e);continue}
+            };
+            for schedule in schedules{
+                if let Err(e) = check_schedule(&auth, &schedule).await{
+                    println!("Erreur lors du suivi des sauvegardes de {} : {}", schedule.user_id, e);
+                }
+            }
+        }
+    });
+}
+
+async fn check_schedule(auth: &Auth, schedule: &MonitoredSchedule)->Result<(), APIError>{
+    let recipients = Recipients{email: schedule.email.as_deref()};
+
+    // Une alerte par rapport en échec
+    if let Some(report) = last_report(&auth.db, &schedule.user_id).await?
+    && report.status == ERROR && schedule.failed_alert_for != Some(report.id){
+        let alert = Alert::new("backup_failed", CRITICAL,
+            String::from("Strongholder : la dernière sauvegarde a échoué"),
+            format!("La sauvegarde {} s'est terminée en erreur (code {}).", report.archive_name, report.exit_code));
+        dispatch(&auth.db, &schedule.user_id, &recipients, &alert).await;
+        set_failed_alert_for(&auth.db, &schedule.user_id, report.id).await?;
+    }
+
+    // Une alerte par retard : elle ne repart qu'après une nouvelle sauvegarde
+    let last_transaction_at = match last_transaction_at(&schedule.user_id, auth.ssh_connexion.clone()).await{
+        Ok(last_transaction_at)=>last_transaction_at,
+        Err(e)=>{println!("Transactions du dépôt de {} illisibles : {}", schedule.user_id, e);None}
+    };
+    let last_report_at = last_successful_report_at(&auth.db, &schedule.user_id).await?;
+    let last_backup_at = last_backup_at(schedule, last_report_at, last_transaction_at);
+    if let Some((reference, elapsed_hours)) = overdue(schedule, last_backup_at, get_current_timestamp()){
+        let message = match last_backup_at{
+            Some(_)=>format!("Aucune sauvegarde depuis {} heures, une sauvegarde est attendue toutes les {} heures.", elapsed_hours, schedule.interval_hours),
+            None=>format!("Aucune sauvegarde depuis l'activation du suivi il y a {} heures.", elapsed_hours)
+        };
+        let alert = Alert::new("missed_backup", WARNING,
+            format!("Strongholder : aucune sauvegarde depuis {} heures", elapsed_hours), message);
+        dispatch(&auth.db, &schedule.user_id, &recipients, &alert).await;
+        webhook_delivery::emit(&auth.db, &schedule.user_id, MISSED_BACKUP, serde_json::json!({
+            "last_backup_at": last_backup_at,
+            "elapsed_hours": elapsed_hours,
+            "interval_hours": schedule.interval_hours
+        }));
+        set_missed_alert_for(&auth.db, &schedule.user_id, reference).await?;
+    }
+    Ok(())
+}
+
+/// Sauvegarde la plus récente parmi la dernière archive vue en session, le dernier rapport réussi
+/// et la dernière transaction d'un client dans le dépôt
+fn last_backup_at(schedule: &MonitoredSchedule, last_report_at: Option<u64>, last_transaction_at: Option<u64>)->Option<u64>{
+    schedule.last_archive_at.max(last_report_at).max(last_transaction_at)
+}
+
+/// Référence du retard (dernière sauvegarde, ou activation du suivi) et heures écoulées,
+/// None si une sauvegarde a eu lieu dans l'intervalle ou si l'alerte est déjà partie pour cette référence
+fn overdue(schedule: &MonitoredSchedule, last_backup_at: Option<u64>, now: u64)->Option<(u64, u64)>{
+    let reference = last_backup_at.unwrap_or(schedule.created_at);
+    let elapsed_hours = now.saturating_sub(reference)/3600;
+    (elapsed_hours >= u64::from(schedule.interval_hours) && schedule.missed_alert_for != Some(reference))
+    .then_some((reference, elapsed_hours))
+}
+
+/// Enregistre la date de la dernière archive, la session donne accès à la clé du dépôt
+pub async fn record_last_archive(auth: &Auth, credentials: &Credentials, repository: &Repository){
+    match get_schedule(&auth.db, &repository.id).await{
+        Ok(schedule) if schedule.enabled=>{},
+        Ok(_)=>return,
+        Err(e)=>{println!("Erreur lors de la lecture du planning de {} : {}", repository.id, e);return}
+    }
+    if let Err(e) = record(auth, credentials, repository).await{
+        println!("Erreur lors du relevé de la dernière archive de {} : {}", repository.id, e);
+    }
+}
+
+async fn record(auth: &Auth, credentials: &Credentials, repository: &Repository)->Result<(), APIError>{
+    let key = auth.lease_repository_key(credentials, repository).await?;
+    let archives = list_archive(&repository.id, auth.ssh_connexion.clone()).await;
+    key.release().await?;
+    let last_archive_at = archives?.archives.iter()
+    .filter_map(|archive| NaiveDateTime::parse_from_str(&archive.time, "%Y-%m-%dT%H:%M:%S%.f").ok())
+    .map(|time| time.and_utc().timestamp() as u64)
+    .max();
+    if let Some(last_archive_at) = last_archive_at{
+        backup_schedules::record_last_archive(&auth.db, &repository.id, last_archive_at).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const HOUR: u64 = 3600;
+    const NOW: u64 = 1_800_000_000;
+
+    /// Compte dont les sauvegardes ne passent que par cron : aucune session, aucun rapport
+    fn cron_only_schedule()->MonitoredSchedule{
+        MonitoredSchedule{
+            user_id: String::from("71aea833849e4c258f17c381669b1c7c"),
+            interval_hours: 24,
+            email: None,
+            last_archive_at: None,
+            missed_alert_for: None,
+            failed_alert_for: None,
+            created_at: NOW - 30*24*HOUR
+        }
+    }
+
+    #[test]
+    fn a_recent_cron_backup_is_not_missed(){
+        let schedule = cron_only_schedule();
+        let last_backup_at = last_backup_at(&schedule, None, Some(NOW - HOUR));
+        assert_eq!(last_backup_at, Some(NOW - HOUR));
+        assert_eq!(overdue(&schedule, last_backup_at, NOW), None);
+    }
+
+    #[test]
+    fn a_stopped_cron_is_missed_once(){
+        let mut schedule = cron_only_schedule();
+        let last_backup_at = last_backup_at(&schedule, None, Some(NOW - 30*HOUR));
+        assert_eq!(overdue(&schedule, last_backup_at, NOW), Some((NOW - 30*HOUR, 30)));
+        schedule.missed_alert_for = Some(NOW - 30*HOUR);
+        assert_eq!(overdue(&schedule, last_backup_at, NOW + 24*HOUR), None);
+    }
+
+    #[test]
+    fn without_any_backup_the_activation_is_the_reference(){
+        let schedule = cron_only_schedule();
+        let last_backup_at = last_backup_at(&schedule, None, None);
+        assert_eq!(overdue(&schedule, last_backup_at, NOW), Some((schedule.created_at, 30*24)));
+    }
+
+    #[test]
+    fn the_most_recent_evidence_wins(){
+        let mut schedule = cron_only_schedule();
+        schedule.last_archive_at = Some(NOW - 50*HOUR);
+        assert_eq!(last_backup_at(&schedule, Some(NOW - 40*HOUR), Some(NOW - 2*HOUR)), Some(NOW - 2*HOUR));
+        assert_eq!(last_backup_at(&schedule, Some(NOW - 40*HOUR), None), Some(NOW - 40*HOUR));
+    }
+}
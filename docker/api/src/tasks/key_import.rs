@@ -0,0 +1,34 @@
+use crate::authentification::auth::Auth;
+use crate::borg_script::import_authorized_keys::{finish_import, list_legacy_keys};
+use crate::error::APIError;
+use crate::route::ssh_keys::install_key;
+
+/// Au démarrage, reprend dans SshKeys les clés des fichiers authorized_keys d'avant l'AuthorizedKeysCommand.
+/// sshd lit encore ces fichiers tant que l'import n'est pas terminé (voir borg/entrypoint.sh)
+pub fn spawn(auth: Auth){
+    actix_web::rt::spawn(async move {
+        if let Err(e) = import(&auth).await{
+            println!("Erreur lors de l'import des clés authorized_keys : {}", e);
+        }
+    });
+}
+
+async fn import(auth: &Auth)->Result<(), APIError>{
+    let keys = list_legacy_keys(auth.ssh_connexion.clone()).await?;
+    let mut failed = 0;
+    for key in &keys{
+        // Une clé déjà importée n'est pas dupliquée (record_key)
+        if let Err(e) = install_key(auth, &key.user_id, key.purpose, &key.public_key, None).await{
+            println!("Clé {} du client {} non importée : {}", key.purpose, key.user_id, e);
+            failed += 1;
+        }
+    }
+    if failed > 0{
+        println!("{} clés non importées, les fichiers authorized_keys restent lus par sshd", failed);
+        return Ok(())
+    }
+    if !keys.is_empty(){
+        println!("{} clés authorized_keys importées dans SshKeys", keys.len());
+    }
+    finish_import(auth.ssh_connexion.clone()).await
+}
@@ -0,0 +1,25 @@
+use std::env;
+pub mod restore_janitor;
+pub mod restore_jobs;
+pub mod retention;
+pub mod repository_check;
+pub mod anomaly_detection;
+pub mod session;
+pub mod backup_monitor;
+pub mod webhook_delivery;
+pub mod tunnel_leases;
+pub mod key_import;
+
+/// Durée en minutes lue dans une variable d'environnement, default si absente ou invalide
+fn env_minutes(name: &str, default: u64)->u64{
+    match env::var(name){
+        Ok(value)=>match value.parse::<u64>(){
+            Ok(minutes) if minutes > 0=>minutes,
+            _=>{
+                println!("{} invalide, valeur par défaut utilisée : {}", name, default);
+                default
+            }
+        },
+        Err(_)=>default
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use crate::authentification::auth::{Auth, Credentials};
+use crate::borg_script::check::{check_repository, CheckMode, CheckOutcome};
+use crate::database::check_runs::{self, CORRUPTED, FAILED, HEALTHY, SCHEDULED};
+use crate::database::events::{record_event, CRITICAL, WARNING};
+use crate::error::APIError;
+use super::env_minutes;
+
+/// Intervalle entre deux vérifications planifiées (une semaine par défaut)
+const DEFAULT_INTERVAL_MINUTES: u64 = 7*24*60;
+
+/// borg check relit tout le dépôt, une seule vérification à la fois
+static RUNNING_CHECKS: Semaphore = Semaphore::const_new(1);
+
+/// Lance la vérification run_id en tâche de fond.
+/// credentials n'est nécessaire que pour verify_data, qui déchiffre les données.
+pub fn start(auth: Auth, user_id: String, credentials: Option<Credentials>, mode: CheckMode, run_id: u64){
+    actix_web::rt::spawn(async move {
+        run(&auth, &user_id, credentials.as_ref(), mode, run_id).await;
+    });
+}
+
+async fn run(auth: &Auth, user_id: &String, credentials: Option<&Credentials>, mode: CheckMode, run_id: u64){
+    let Ok(_permit) = RUNNING_CHECKS.acquire().await else{
+        return
+    };
+    if let Err(e) = check_runs::set_running(&auth.db, run_id).await{
+        println!("Impossible de passer la vérification {} en cours : {}", run_id, e);
+    }
+    let result = match credentials{
+        Some(credentials)=>{
+            // La clé doit rester présente pendant toute la vérification
+            match auth.lease_master_key(credentials).await{
+                Ok(key)=>{
+                    let result = check_repository(user_id, mode, auth.ssh_connexion.clone()).await;
+                    key.release().await.and(result)
+                },
+                Err(e)=>Err(e)
+            }
+        },
+        None=>check_repository(user_id, mode, auth.ssh_connexion.clone()).await
+    };
+    if let Err(e) = record_result(auth, user_id, mode, run_id, result).await{
+        println!("Impossible d'enregistrer le résultat de la vérification {} : {}", run_id, e);
+    }
+}
+
+async fn record_result(auth: &Auth, user_id: &str, mode: CheckMode, run_id: u64, result: Result<(CheckOutcome, String), APIError>)->Result<(), APIError>{
+    match result{
+        Ok((CheckOutcome::Healthy, output))=>{
+            println!("Vérification {} : dépôt sain", run_id);
+            check_runs::finish_run(&auth.db, run_id, HEALTHY, &output).await
+        },
+        Ok((CheckOutcome::Corrupted, output))=>{
+            check_runs::finish_run(&auth.db, run_id, CORRUPTED, &output).await?;
+            record_event(&auth.db, user_id, CRITICAL, "check_corrupted",
+                &format!("La vérification {} ({}) a trouvé des erreurs dans le dépôt", run_id, mode.as_str())).await
+        },
+        Ok((CheckOutcome::NotRun, output))=>{
+            check_runs::finish_run(&auth.db, run_id, FAILED, &output).await?;
+            record_event(&auth.db, user_id, WARNING, "check_not_run",
+                &format!("La vérification {} n'a pas pu être lancée (dépôt occupé ?)", run_id)).await
+        },
+        // Erreur de borg sans verdict sur l'intégrité : tracée sans alerte, le rapport est gardé
+        Ok((CheckOutcome::Failed, output))=>{
+            println!("Vérification {} : échec de borg check sans verdict", run_id);
+            check_runs::finish_run(&auth.db, run_id, FAILED, &output).await
+        },
+        Err(e)=>check_runs::finish_run(&auth.db, run_id, FAILED, &e.to_string()).await
+    }
+}
+
+/// Vérifie régulièrement le dépôt de chaque utilisateur (CHECK_INTERVAL_MINUTES).
+/// Seul le mode repository est planifié : il ne nécessite pas la clé, absente hors session.
+pub fn spawn_scheduler(auth: Auth){
+    let interval = env_minutes("CHECK_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    println!("Vérification des dépôts toutes les {} minutes", interval);
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval*60));
+        // Le premier tick est immédiat, on ne vérifie pas tous les dépôts à chaque redémarrage
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let user_ids = match check_runs::list_user_ids(&auth.db).await{
+                Ok(user_ids)=>user_ids,
+                Err(e)=>{println!("Erreur lors de la planification des vérifications : {}", e);continue}
+            };
+            for user_id in user_ids{
+                match check_runs::insert_run(&auth.db, &user_id, CheckMode::Repository.as_str(), SCHEDULED).await{
+                    Ok(run_id)=>run(&auth, &user_id, None, CheckMode::Repository, run_id).await,
+                    Err(e)=>println!("Impossible de planifier la vérification de {} : {}", user_id, e)
+                }
+            }
+        }
+    });
+}
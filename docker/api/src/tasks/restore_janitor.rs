@@ -0,0 +1,76 @@
+use openssh::Session;
+use sqlx::MySqlPool;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::borg_script::purge_restore::purge_restore;
+use crate::borg_script::share_file::remove_shared;
+use crate::database::restore_purges::record_purge;
+use crate::database::share_links::{clear_restore_id, stale_links};
+use super::env_minutes;
+
+/// Âge maximal d'une restauration préparée avant sa suppression (24h par défaut)
+const DEFAULT_MAX_AGE_MINUTES: u64 = 24*60;
+/// Intervalle entre deux passages (1h par défaut)
+const DEFAULT_INTERVAL_MINUTES: u64 = 60;
+
+/// Lance en tâche de fond la suppression périodique des restaurations oubliées sur le serveur.
+/// Configurable avec RESTORE_MAX_AGE_MINUTES et RESTORE_JANITOR_INTERVAL_MINUTES.
+/// Chaque passage est enregistré dans RestorePurges, lisible par /api/admin/restore_purges.
+/// Les fichiers des liens de partage qui ne peuvent plus servir sont supprimés au même rythme.
+pub fn spawn(ssh_connexion: Arc<Session>, db: MySqlPool){
+    let max_age = env_minutes("RESTORE_MAX_AGE_MINUTES", DEFAULT_MAX_AGE_MINUTES);
+    let interval = env_minutes("RESTORE_JANITOR_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    println!("Nettoyage des restaurations toutes les {} minutes (âge maximal {} minutes)", interval, max_age);
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval*60));
+        loop {
+            ticker.tick().await;
+            remove_stale_shares(ssh_connexion.clone(), &db).await;
+            let record = match purge_restore(ssh_connexion.clone(), max_age).await{
+                Ok(purged) if purged.is_empty()=>continue,
+                Ok(purged)=>{
+                    let mut removed: u32 = 0;
+                    let mut failed: u32 = 0;
+                    let mut total: u64 = 0;
+                    for entry in &purged{
+                        if entry.removed{
+                            println!("Restauration purgée : client {} - {} ({} octets)", entry.client, entry.name, entry.size);
+                            removed += 1;
+                            total += entry.size;
+                        }else{
+                            println!("Restauration impossible à supprimer : client {} - {}", entry.client, entry.name);
+                            failed += 1;
+                        }
+                    }
+                    println!("Nettoyage des restaurations : {} éléments supprimés, {} octets libérés, {} échecs", removed, total, failed);
+                    record_purge(&db, removed, total, failed, None).await
+                },
+                Err(e)=>{
+                    println!("Erreur lors du nettoyage des restaurations : {}", e);
+                    record_purge(&db, 0, 0, 0, Some(&e.to_string())).await
+                }
+            };
+            if let Err(e) = record{
+                println!("Impossible d'enregistrer le nettoyage des restaurations : {}", e);
+            }
+        }
+    });
+}
+
+async fn remove_stale_shares(ssh_connexion: Arc<Session>, db: &MySqlPool){
+    let links = match stale_links(db).await{
+        Ok(links)=>links,
+        Err(e)=>{println!("Erreur lors de la recherche des liens de partage à nettoyer : {}", e);return}
+    };
+    for link in links{
+        let result = match remove_shared(&link.user_id, &link.restore_id, ssh_connexion.clone()).await{
+            Ok(())=>clear_restore_id(db, link.id).await,
+            Err(e)=>Err(e)
+        };
+        match result{
+            Ok(())=>println!("Fichier du lien de partage {} supprimé pour : {}", link.id, link.user_id),
+            Err(e)=>println!("Impossible de supprimer le fichier du lien de partage {} : {}", link.id, e)
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use tokio::sync::{Semaphore, watch};
+use crate::authentification::auth::{Auth, Credentials};
+use crate::authentification::repository::Repository;
+use crate::borg_script::restore::dertermining_restore_mode;
+use crate::borg_script::store_restore::{store_restore, RestoreArtifact};
+use crate::database::restore_jobs::{self, RUNNING};
+use crate::error::APIError;
+use super::webhook_delivery;
+
+/// Nombre de restaurations exécutées en même temps, les autres restent en file d'attente
+static RUNNING_JOBS: Semaphore = Semaphore::const_new(2);
+
+/// Lance la restauration job_id en tâche de fond.
+/// La tâche ne dépend pas de la requête HTTP : elle continue si le client se déconnecte.
+pub fn start(auth: Auth, credentials: Credentials, repository: Repository, body: String, job_id: String){
+    actix_web::rt::spawn(async move {
+        let Ok(_permit) = RUNNING_JOBS.acquire().await else{
+            return
+        };
+        println!("Démarrage de la restauration {} pour {}", job_id, repository.id);
+        if let Err(e) = restore_jobs::set_status(&auth.db, &job_id, RUNNING).await{
+            println!("Impossible de passer la restauration {} en cours : {}", job_id, e);
+        }
+        let result = run(&auth, &credentials, &repository, &body, &job_id).await;
+        let update = match result{
+            Ok(artifact)=>{
+                println!("Restauration {} terminée", job_id);
+                webhook_delivery::emit_restore(&auth.db, &repository.id, &body, "job");
+                restore_jobs::set_ready(&auth.db, &job_id, &artifact.restore_id, &artifact.file_name, artifact.size).await
+            },
+            Err(e)=>{
+                println!("Restauration {} échouée : {}", job_id, e);
+                restore_jobs::set_failed(&auth.db, &job_id, &e.to_string()).await
+            }
+        };
+        if let Err(e) = update{
+            println!("Impossible d'enregistrer le résultat de la restauration {} : {}", job_id, e);
+        }
+    });
+}
+
+async fn run(auth: &Auth, credentials: &Credentials, repository: &Repository, body: &str, job_id: &str)->Result<RestoreArtifact, APIError>{
+    let (sender, receiver) = watch::channel(0.0);
+    spawn_progress_writer(auth.clone(), job_id.to_string(), receiver);
+
+    let key = auth.lease_repository_key(credentials, repository).await?;
+    let restore = dertermining_restore_mode(&repository.id, body, auth.ssh_connexion.clone(), auth.sftp_connexion.clone(), Some(sender)).await;
+    // borg a ouvert le dépôt dès que les premiers octets sont arrivés, la clé peut être supprimée
+    key.release().await?;
+    let (reader, file_name) = restore?;
+    store_restore(&repository.id, reader, file_name, auth.ssh_connexion.clone()).await
+}
+
+/// Enregistre l'avancement en base à chaque point de pourcentage, jusqu'à la fin de borg
+fn spawn_progress_writer(auth: Auth, job_id: String, mut receiver: watch::Receiver<f64>){
+    actix_web::rt::spawn(async move {
+        let mut last = 0.0;
+        while receiver.changed().await.is_ok(){
+            let progress = receiver.borrow_and_update().floor();
+            if progress == last{
+                continue
+            }
+            last = progress;
+            if let Err(e) = restore_jobs::set_progress(&auth.db, &job_id, progress).await{
+                println!("Impossible d'enregistrer l'avancement de la restauration {} : {}", job_id, e);
+            }
+        }
+    });
+}
@@ -0,0 +1,176 @@
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use crate::authentification::auth::{Auth, Credentials};
+use crate::authentification::repository::Repository;
+use crate::borg_script::compact::Compaction;
+use crate::borg_script::info::invalidate_statistics;
+use crate::borg_script::list_archive::list_archive;
+use crate::borg_script::prune::{prune_archives, prune_plan, PrunePlan};
+use crate::database::archive_deletions::{record_deletion, PRUNED};
+use crate::database::archive_locks::locked_archives;
+use crate::database::events::{record_event, CRITICAL, WARNING};
+use crate::database::retention_policies::{claim_due_policy, due_policies, forget_scheduler_key, scheduler_key, RetentionPolicy};
+use crate::error::APIError;
+use super::anomaly_detection::analyze_leased;
+use super::env_minutes;
+
+/// Intervalle minimal entre deux applications de la politique (24h par défaut)
+const DEFAULT_INTERVAL_MINUTES: u64 = 24*60;
+/// Intervalle entre deux recherches de politiques dues par le planificateur
+const DEFAULT_SCHEDULER_MINUTES: u64 = 60;
+
+/// Une seule rétention à la fois, borg compact est coûteux pour le serveur
+static RUNNING_PRUNES: Semaphore = Semaphore::const_new(1);
+
+/// Applique la politique de l'utilisateur si elle est due, au plus une fois par RETENTION_INTERVAL_MINUTES.
+/// Appelée par tasks::session à la connexion et au rafraîchissement du token, avec la clé de la session :
+/// les politiques enregistrées avant le planificateur n'ont pas de clé confiée.
+pub async fn run_if_due(auth: &Auth, credentials: &Credentials, repository: &Repository){
+    let interval = env_minutes("RETENTION_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    let policy = match claim_due_policy(&auth.db, &repository.id, interval*60).await{
+        Ok(Some(policy))=>policy,
+        Ok(None)=>return,
+        Err(e)=>{println!("Erreur lors de la planification de la rétention : {}", e);return}
+    };
+    let Ok(_permit) = RUNNING_PRUNES.acquire().await else{
+        return
+    };
+    let result = match auth.lease_repository_key(credentials, repository).await{
+        Ok(key)=>{
+            let result = apply_policy(auth, &repository.id, &policy).await;
+            key.release().await.and(result)
+        },
+        Err(e)=>Err(e)
+    };
+    report(&repository.id, result);
+}
+
+/// Applique les politiques dues toutes les RETENTION_SCHEDULER_MINUTES, avec la clé confiée
+/// au planificateur lors de l'activation de la politique : la rétention ne dépend plus des connexions.
+pub fn spawn_scheduler(auth: Auth){
+    let interval = env_minutes("RETENTION_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    let scheduler = env_minutes("RETENTION_SCHEDULER_MINUTES", DEFAULT_SCHEDULER_MINUTES);
+    println!("Recherche des rétentions dues toutes les {} minutes", scheduler);
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(scheduler*60));
+        loop {
+            ticker.tick().await;
+            let due = match due_policies(&auth.db, interval*60).await{
+                Ok(due)=>due,
+                Err(e)=>{println!("Erreur lors de la recherche des rétentions dues : {}", e);continue}
+            };
+            for user_id in due{
+                run_scheduled(&auth, &user_id, interval).await;
+            }
+        }
+    });
+}
+
+async fn run_scheduled(auth: &Auth, user_id: &String, interval: u64){
+    let Ok(_permit) = RUNNING_PRUNES.acquire().await else{
+        return
+    };
+    let encrypt_master_key_2 = match scheduler_key(&auth.db, user_id).await{
+        Ok(Some(encrypt_master_key_2))=>encrypt_master_key_2,
+        // Politique désactivée entre-temps
+        Ok(None)=>return,
+        Err(e)=>return report(user_id, Err(e))
+    };
+    let key = match auth.lease_scheduler_key(user_id, &encrypt_master_key_2).await{
+        Ok(key)=>key,
+        Err(APIError::KDFError)=>return report_unreadable_key(auth, user_id).await,
+        Err(e)=>return report(user_id, Err(e))
+    };
+    // Comme à la connexion, les nouvelles archives sont analysées avant toute suppression.
+    // La politique n'est pas réservée si l'analyse a du retard : elle reprend au passage suivant
+    if ! analyze_leased(auth, user_id).await{
+        println!("Rétention reportée pour {} : analyse des archives incomplète", user_id);
+        if let Err(e) = key.release().await{
+            report(user_id, Err(e));
+        }
+        return
+    }
+    // Une session a pu appliquer la politique depuis la recherche
+    let result = match claim_due_policy(&auth.db, user_id, interval*60).await{
+        Ok(Some(policy))=>apply_policy(auth, user_id, &policy).await,
+        Ok(None)=>{
+            if let Err(e) = key.release().await{
+                report(user_id, Err(e));
+            }
+            return
+        },
+        Err(e)=>Err(e)
+    };
+    report(user_id, key.release().await.and(result));
+}
+
+/// La clé confiée ne se déchiffre plus avec SCHEDULER_KEY_SECRET : signalé une seule fois,
+/// la clé est oubliée et le planificateur ne reprend la politique qu'après un nouvel enregistrement
+async fn report_unreadable_key(auth: &Auth, repository_id: &String){
+    let result = match record_event(&auth.db, repository_id, WARNING, "retention_key_unreadable",
+        "Clé de la politique de rétention illisible par le planificateur : la rétention n'est plus appliquée qu'à la connexion. Enregistrer à nouveau la politique pour la confier au planificateur").await{
+        Ok(())=>forget_scheduler_key(&auth.db, repository_id).await,
+        Err(e)=>Err(e)
+    };
+    if let Err(e) = result{
+        println!("Erreur lors du signalement de la clé de rétention illisible pour {} : {}", repository_id, e);
+    }
+}
+
+fn report(repository_id: &str, result: Result<Vec<String>, APIError>){
+    match result{
+        Ok(deleted)=>println!("Rétention appliquée pour {} : {} archives supprimées", repository_id, deleted.len()),
+        Err(e)=>println!("Erreur lors de la rétention pour {} : {}", repository_id, e)
+    }
+}
+
+/// Liste des archives qui seraient supprimées, sans rien supprimer
+pub async fn preview(auth: &Auth, credentials: &Credentials, repository: &Repository, policy: &RetentionPolicy)->Result<PrunePlan, APIError>{
+    let key = auth.lease_repository_key(credentials, repository).await?;
+    let archives = list_archive(&repository.id, auth.ssh_connexion.clone()).await;
+    key.release().await?;
+    let locked = locked_archives(&auth.db, &repository.id).await?;
+    Ok(prune_plan(&archives?, policy, &locked))
+}
+
+/// Supprime les archives hors politique et compacte le dépôt, chaque suppression est tracée.
+/// L'appelant détient un bail sur la clé du dépôt.
+pub async fn apply_policy(auth: &Auth, repository_id: &String, policy: &RetentionPolicy)->Result<Vec<String>, APIError>{
+    if ! policy.keeps_something(){
+        return Ok(Vec::new())
+    }
+    let locked = locked_archives(&auth.db, repository_id).await?;
+    let result = match list_archive(repository_id, auth.ssh_connexion.clone()).await{
+        Ok(archives)=>{
+            let plan = prune_plan(&archives, policy, &locked);
+            prune_archives(repository_id, &plan, auth.ssh_connexion.clone()).await.map(|(deleted, compaction)| (plan, deleted, compaction))
+        },
+        Err(e)=>Err(e)
+    };
+    let (plan, deleted, compaction) = result?;
+    report_compaction(auth, repository_id, &compaction).await?;
+    if ! deleted.is_empty(){
+        invalidate_statistics(repository_id);
+    }
+    for pruned in &plan.prune{
+        let archives: Vec<String> = deleted.iter()
+        .filter(|name| **name == pruned.archive || Some(*name) == pruned.logs.as_ref())
+        .cloned().collect();
+        if archives.is_empty(){
+            continue
+        }
+        record_deletion(&auth.db, repository_id, &pruned.archive, PRUNED, Some(&archives)).await?;
+    }
+    Ok(deleted)
+}
+
+/// Un compactage refusé est signalé : les archives supprimées par le client peuvent encore
+/// être récupérées en revenant à une transaction antérieure (repo/transactions)
+pub async fn report_compaction(auth: &Auth, repository_id: &str, compaction: &Compaction)->Result<(), APIError>{
+    let Compaction::Refused(archives) = compaction else{
+        return Ok(())
+    };
+    record_event(&auth.db, repository_id, CRITICAL, "compact_refused",
+        &format!("Compactage refusé, archives supprimées par le client : {}. Vérifier repo/transactions avant de confirmer avec /api/compact", archives.join(", "))).await
+}
@@ -0,0 +1,27 @@
+use crate::authentification::auth::{Auth, Credentials};
+use crate::authentification::repository::{shared_repositories, Repository};
+use super::{anomaly_detection, backup_monitor, retention};
+
+/// Travaux qui ont besoin de la clé du dépôt, lancés à la connexion et au rafraîchissement du token.
+/// L'analyse passe avant la rétention : une archive suspecte verrouille les archives précédentes
+/// avant que la politique ne puisse les supprimer. La rétention attend donc que l'analyse
+/// n'ait plus de retard (échec, ou plus de MAX_ARCHIVES_PER_SESSION nouvelles archives).
+/// La session de chaque membre d'une organisation fait aussi ces travaux sur le dépôt partagé,
+/// qui avance même si le fondateur ne se connecte plus.
+pub fn spawn(auth: Auth, credentials: Credentials){
+    actix_web::rt::spawn(async move {
+        let mut repositories = vec![Repository::own(&credentials)];
+        match shared_repositories(&auth, &credentials).await{
+            Ok(shared)=>repositories.extend(shared),
+            Err(e)=>println!("Erreur lors de la lecture des dépôts partagés de {} : {}", credentials.id, e)
+        }
+        for repository in &repositories{
+            if anomaly_detection::analyze_new_archives(&auth, &credentials, repository).await{
+                retention::run_if_due(&auth, &credentials, repository).await;
+            }else{
+                println!("Rétention reportée pour {} : analyse des archives incomplète", repository.id);
+            }
+            backup_monitor::record_last_archive(&auth, &credentials, repository).await;
+        }
+    });
+}
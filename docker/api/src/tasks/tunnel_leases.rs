@@ -0,0 +1,50 @@
+use jsonwebtoken::get_current_timestamp;
+use std::collections::HashSet;
+use std::time::Duration;
+use crate::authentification::auth::Auth;
+use crate::borg_script::tunnels::tunnel_sessions;
+use crate::database::tunnel_leases::{active_leases, release_lease, renew_lease, EXPIRED};
+use crate::error::APIError;
+use super::env_minutes;
+
+/// Durée d'un bail sans tunnel ouvert (1h par défaut)
+const DEFAULT_LEASE_MINUTES: u64 = 60;
+/// Intervalle entre deux passages (5 minutes par défaut)
+const DEFAULT_INTERVAL_MINUTES: u64 = 5;
+
+/// Durée d'un bail en secondes, configurable avec TUNNEL_LEASE_MINUTES
+pub fn lease_seconds()->u64{
+    env_minutes("TUNNEL_LEASE_MINUTES", DEFAULT_LEASE_MINUTES)*60
+}
+
+/// Lance en tâche de fond le suivi des baux de ports : un bail dont le tunnel est ouvert est prolongé,
+/// un bail expiré sans tunnel est libéré. Configurable avec TUNNEL_REAPER_INTERVAL_MINUTES.
+pub fn spawn_reaper(auth: Auth){
+    let interval = env_minutes("TUNNEL_REAPER_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    println!("Suivi des baux de tunnel toutes les {} minutes (bail de {} secondes)", interval, lease_seconds());
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval*60));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = reap(&auth).await{
+                println!("Erreur lors du suivi des baux de tunnel : {}", e);
+            }
+        }
+    });
+}
+
+async fn reap(auth: &Auth)->Result<(), APIError>{
+    // Sans la liste des tunnels ouverts aucun bail n'est libéré
+    let open: HashSet<u16> = tunnel_sessions(auth.ssh_connexion.clone()).await?.iter().map(|session| session.port).collect();
+    let now = get_current_timestamp();
+    for lease in active_leases(&auth.db, None).await?{
+        if open.contains(&lease.port){
+            renew_lease(&auth.db, lease.id, now + lease_seconds(), Some(now)).await?;
+        }else if lease.expires_at <= now{
+            release_lease(&auth.db, lease.id, EXPIRED).await?;
+            println!("Bail du port {} expiré pour le client : {}", lease.port, lease.user_id);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use jsonwebtoken::get_current_timestamp;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use serde_json::{json, Value};
+use sqlx::MySqlPool;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use crate::database::webhooks::{self, claim_delivery, due_deliveries, record_attempt, DueDelivery, DELIVERED, FAILED, PENDING};
+use crate::borg_script::restore::restore_archive_name;
+use crate::error::APIError;
+use crate::notify::webhook::post_json;
+use super::env_minutes;
+
+pub const BACKUP_FINISHED: &str = "backup_finished";
+pub const BACKUP_FAILED: &str = "backup_failed";
+pub const MISSED_BACKUP: &str = "missed_backup";
+pub const RESTORE_PERFORMED: &str = "restore_performed";
+pub const REPO_KEY_DOWNLOADED: &str = "repo_key_downloaded";
+pub const SIGNIN_NEW_IP: &str = "signin_new_ip";
+pub const SSH_KEY_INSTALLED: &str = "ssh_key_installed";
+pub const SSH_KEY_REVOKED: &str = "ssh_key_revoked";
+pub const SHARE_LINK_REDEEMED: &str = "share_link_redeemed";
+/// Envoyé uniquement par /api/webhooks/{id}/test
+pub const PING: &str = "ping";
+
+/// Évènements auxquels un webhook peut s'abonner
+pub const EVENTS: [&str; 9] = [BACKUP_FINISHED, BACKUP_FAILED, MISSED_BACKUP, RESTORE_PERFORMED, REPO_KEY_DOWNLOADED, SIGNIN_NEW_IP, SSH_KEY_INSTALLED, SSH_KEY_REVOKED, SHARE_LINK_REDEEMED];
+
+/// Attente avant chaque nouvelle tentative, l'envoi est abandonné après la dernière
+const BACKOFF_SECONDS: [u64; 5] = [60, 5*60, 30*60, 2*60*60, 12*60*60];
+/// Durée pendant laquelle un envoi en cours n'est pas repris par une autre tâche
+const LEASE_SECONDS: u64 = 5*60;
+const DEFAULT_INTERVAL_MINUTES: u64 = 1;
+const BATCH: u32 = 100;
+
+/// Nombre d'envois simultanés, un webhook lent ne bloque pas les autres
+static SENDING: Semaphore = Semaphore::const_new(4);
+
+/// Enregistre l'évènement pour chaque webhook abonné et tente l'envoi immédiatement.
+/// Ne fait jamais échouer l'action qui a déclenché l'évènement.
+pub fn emit(db: &MySqlPool, user_id: &str, event: &'static str, data: Value){
+    let db = db.clone();
+    let user_id = user_id.to_string();
+    actix_web::rt::spawn(async move {
+        let targets = match webhooks::subscribed_webhooks(&db, &user_id, event).await{
+            Ok(targets)=>targets,
+            Err(e)=>{println!("Erreur lors de la recherche des webhooks de {} : {}", user_id, e);return}
+        };
+        for webhook_id in targets{
+            enqueue(&db, &user_id, webhook_id, event, &data).await;
+        }
+    });
+}
+
+/// Évènement de restauration, l'archive est lue dans la requête de restauration
+pub fn emit_restore(db: &MySqlPool, user_id: &str, body: &str, mode: &str){
+    emit(db, user_id, RESTORE_PERFORMED, json!({
+        "archive_name": restore_archive_name(body).unwrap_or_default(),
+        "mode": mode
+    }));
+}
+
+/// Envoie l'évènement à ce webhook, quels que soient ses abonnements
+pub async fn enqueue(db: &MySqlPool, user_id: &str, webhook_id: u64, event: &'static str, data: &Value)->Option<u64>{
+    let payload = json!({
+        "event": event,
+        "user_id": user_id,
+        "created_at": get_current_timestamp(),
+        "data": data
+    }).to_string();
+    match webhooks::insert_delivery(db, webhook_id, user_id, event, &payload).await{
+        Ok(delivery_id)=>{
+            let db = db.clone();
+            actix_web::rt::spawn(async move { attempt(&db, delivery_id).await });
+            Some(delivery_id)
+        },
+        Err(e)=>{
+            println!("Impossible d'enregistrer l'envoi {} vers le webhook {} : {}", event, webhook_id, e);
+            None
+        }
+    }
+}
+
+/// Reprend les envois en attente (nouvelles tentatives, envois interrompus par un redémarrage)
+pub fn spawn_scheduler(db: MySqlPool){
+    let interval = env_minutes("WEBHOOK_RETRY_INTERVAL_MINUTES", DEFAULT_INTERVAL_MINUTES);
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval*60));
+        loop {
+            ticker.tick().await;
+            match due_deliveries(&db, BATCH).await{
+                Ok(delivery_ids)=>for delivery_id in delivery_ids{
+                    attempt(&db, delivery_id).await;
+                },
+                Err(e)=>println!("Erreur lors de la reprise des webhooks : {}", e)
+            }
+        }
+    });
+}
+
+/// Signature HMAC-SHA256 de "timestamp.corps" avec le secret du webhook
+fn sign(secret: &str, timestamp: u64, body: &str)->Result<String, APIError>{
+    let signature = PKey::hmac(secret.as_bytes())
+    .and_then(|key| {
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+        signer.update(format!("{}.{}", timestamp, body).as_bytes())?;
+        signer.sign_to_vec()
+    });
+    match signature{
+        Ok(signature)=>Ok(hex::encode(signature)),
+        Err(_)=>Err(APIError::Notification)
+    }
+}
+
+async fn attempt(db: &MySqlPool, delivery_id: u64){
+    let Ok(_permit) = SENDING.acquire().await else{
+        return
+    };
+    let delivery = match claim_delivery(db, delivery_id, LEASE_SECONDS).await{
+        Ok(Some(delivery))=>delivery,
+        Ok(None)=>return,
+        Err(e)=>{println!("Impossible de réserver l'envoi {} : {}", delivery_id, e);return}
+    };
+    let (response_status, error) = match send(&delivery).await{
+        Ok(status) if (200..300).contains(&status)=>(Some(status), None),
+        Ok(status)=>(Some(status), Some(format!("réponse HTTP {}", status))),
+        Err(e)=>(None, Some(e))
+    };
+    let attempts = delivery.attempts + 1;
+    let result = match &error{
+        None=>record_attempt(db, delivery.id, DELIVERED, attempts, response_status, None, None).await,
+        Some(error)=>match BACKOFF_SECONDS.get(delivery.attempts as usize){
+            Some(backoff)=>record_attempt(db, delivery.id, PENDING, attempts, response_status, Some(error), Some(get_current_timestamp() + backoff)).await,
+            None=>{
+                println!("Webhook : envoi {} abandonné après {} tentatives : {}", delivery.id, attempts, error);
+                record_attempt(db, delivery.id, FAILED, attempts, response_status, Some(error), None).await
+            }
+        }
+    };
+    if let Err(e) = result{
+        println!("Impossible d'enregistrer la tentative {} : {}", delivery.id, e);
+    }
+}
+
+async fn send(delivery: &DueDelivery)->Result<u16, String>{
+    let timestamp = get_current_timestamp();
+    let signature = sign(&delivery.secret, timestamp, &delivery.payload).map_err(|e| e.to_string())?;
+    let headers = vec![
+        ("X-Strongholder-Event", delivery.event.clone()),
+        ("X-Strongholder-Delivery", delivery.id.to_string()),
+        ("X-Strongholder-Timestamp", timestamp.to_string()),
+        ("X-Strongholder-Signature", format!("sha256={}", signature))
+    ];
+    post_json(&delivery.url, delivery.payload.clone(), headers).await
+}
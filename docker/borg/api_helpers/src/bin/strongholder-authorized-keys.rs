@@ -0,0 +1,33 @@
+//! AuthorizedKeysCommand de sshd sur le docker borg.
+//! Demande à l'API les clés actives de l'utilisateur ssh et les écrit sur stdout au format authorized_keys.
+//! Usage : strongholder-authorized-keys <utilisateur>
+use std::env;
+use std::io::Write;
+use std::process::ExitCode;
+use strongholder_borg_helpers::{read_config, request, valid_user};
+
+fn main()->ExitCode{
+    let Some(user) = env::args().nth(1) else{
+        eprintln!("Usage : strongholder-authorized-keys <utilisateur>");
+        return ExitCode::FAILURE
+    };
+    if !valid_user(&user){
+        eprintln!("utilisateur invalide : {}", user);
+        return ExitCode::FAILURE
+    }
+    // En cas d'erreur rien n'est écrit : sshd ne connaît aucune clé pour cet utilisateur
+    let keys = match read_config().and_then(|config| request(&config, "GET", &format!("/internal/authorized_keys/{}", user))){
+        Ok(keys)=>keys,
+        Err(e)=>{
+            eprintln!("strongholder-authorized-keys ({}) : {}", user, e);
+            return ExitCode::FAILURE
+        }
+    };
+    let mut stdout = std::io::stdout().lock();
+    for line in keys.lines().map(str::trim).filter(|line| !line.is_empty()){
+        if writeln!(stdout, "{}", line).is_err(){
+            return ExitCode::FAILURE
+        }
+    }
+    ExitCode::SUCCESS
+}
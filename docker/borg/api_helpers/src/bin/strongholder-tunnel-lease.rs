@@ -0,0 +1,46 @@
+//! Bail de port des reverse tunnels, tenu par l'API (table TunnelLeases).
+//! Usage : strongholder-tunnel-lease acquire <client>       affiche le port prêté
+//!         strongholder-tunnel-lease release <client> <port>
+use std::env;
+use std::process::ExitCode;
+use strongholder_borg_helpers::{read_config, request, valid_user};
+
+const USAGE: &str = "Usage : strongholder-tunnel-lease acquire <client> | release <client> <port>";
+
+fn run(args: &[String])->Result<(), String>{
+    let (method, client, port) = match args{
+        [action, client] if action == "acquire"=>("POST", client, None),
+        [action, client, port] if action == "release"=>("DELETE", client, Some(port)),
+        _=>return Err(String::from(USAGE))
+    };
+    if !valid_user(client){
+        return Err(format!("client invalide : {}", client))
+    }
+    let path = match port{
+        Some(port)=>{
+            let port: u16 = port.parse().map_err(|_| format!("port invalide : {}", port))?;
+            format!("/internal/tunnel_leases/{}/{}", client, port)
+        },
+        None=>format!("/internal/tunnel_leases/{}", client)
+    };
+    let body = request(&read_config()?, method, &path)?;
+    if method == "POST"{
+        let port = body.trim();
+        if port.parse::<u16>().is_err(){
+            return Err(format!("port invalide reçu : {}", port))
+        }
+        println!("{}", port);
+    }
+    Ok(())
+}
+
+fn main()->ExitCode{
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args){
+        Ok(())=>ExitCode::SUCCESS,
+        Err(e)=>{
+            eprintln!("strongholder-tunnel-lease : {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Accès du docker borg aux routes /internal de l'API (jamais exposées par nginx).
+//! Partagé par strongholder-authorized-keys et strongholder-tunnel-lease.
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Écrit par entrypoint.sh, lisible par root et l'AuthorizedKeysCommandUser seulement
+const CONFIG: &str = "/etc/strongholder/api.conf";
+const DEFAULT_API: &str = "api:8080";
+/// sshd et les scripts attendent la réponse, l'API doit répondre vite
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Assez pour des centaines de clés, une réponse plus longue est refusée
+const MAX_RESPONSE: u64 = 1024*1024;
+
+pub struct Config{
+    /// hôte:port de l'API sur le réseau backup_net
+    api: String,
+    token: String
+}
+
+pub fn read_config()->Result<Config, String>{
+    let content = fs::read_to_string(CONFIG).map_err(|e| format!("lecture de {} impossible : {}", CONFIG, e))?;
+    let mut api = String::from(DEFAULT_API);
+    let mut token = String::new();
+    for line in content.lines(){
+        let Some((key, value)) = line.split_once('=') else{
+            continue
+        };
+        match key.trim(){
+            "api"=>api = value.trim().to_string(),
+            "token"=>token = value.trim().to_string(),
+            _=>{}
+        }
+    }
+    if token.is_empty(){
+        return Err(format!("token absent de {}", CONFIG))
+    }
+    Ok(Config{api, token})
+}
+
+/// Noms d'utilisateurs du docker borg : id des clients, tunnel, api
+pub fn valid_user(user: &str)->bool{
+    !user.is_empty() && user.len() <= 64 && user.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+}
+
+/// Envoie la requête et renvoie le corps de la réponse, erreur si le statut n'est pas 200
+pub fn request(config: &Config, method: &str, path: &str)->Result<String, String>{
+    let Some(address) = config.api.to_socket_addrs().map_err(|e| format!("adresse {} invalide : {}", config.api, e))?.next() else{
+        return Err(format!("adresse {} introuvable", config.api))
+    };
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| format!("connexion à {} impossible : {}", config.api, e))?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    let request = format!("{} {} HTTP/1.0\r\nHost: {}\r\nAuthorization: Bearer {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", method, path, config.api, config.token);
+    stream.write_all(request.as_bytes()).map_err(|e| format!("envoi de la requête impossible : {}", e))?;
+
+    let mut response = Vec::new();
+    stream.take(MAX_RESPONSE + 1).read_to_end(&mut response).map_err(|e| format!("lecture de la réponse impossible : {}", e))?;
+    if response.len() as u64 > MAX_RESPONSE{
+        return Err(String::from("réponse trop longue"))
+    }
+    let response = String::from_utf8(response).map_err(|_| String::from("réponse non UTF-8"))?;
+    let Some((head, body)) = response.split_once("\r\n\r\n") else{
+        return Err(String::from("réponse HTTP invalide"))
+    };
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200"){
+        return Err(format!("l'API a répondu {} : {}", status, body.trim()))
+    }
+    Ok(body.to_string())
+}
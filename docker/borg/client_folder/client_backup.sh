@@ -40,7 +40,7 @@ cleanup() {
 }
 trap cleanup EXIT INT TERM
 
-# reverse port unique par client
+# Port reverse prêté par l'API pour la durée de la sauvegarde (rendu par server_cleanup_key.sh)
 log "Requesting reverse port from server"
 REVERSE_PORT="$(
   ssh -i $TUNNEL_SSH_KEY \
@@ -2,6 +2,17 @@
 
 cat /root/.ssh/id_ed25519.pub > /srv/repos/api/.ssh/authorized_keys 
 
+# Accès des helpers du docker borg aux routes /internal de l'API (voir borg/api_helpers)
+if [ -z "${INTERNAL_API_TOKEN:-}" ]; then
+  echo "INTERNAL_API_TOKEN absent : aucun client ni tunnel ne pourra se connecter" >&2
+fi
+install -d -m 0750 -o root -g sshkeys /etc/strongholder
+install -m 0640 -o root -g sshkeys /dev/null /etc/strongholder/api.conf
+cat > /etc/strongholder/api.conf <<EOF
+api=${INTERNAL_API:-api:8080}
+token=${INTERNAL_API_TOKEN:-}
+EOF
+
 cat > /etc/ssh/sshd_config <<'EOF'
 Include /etc/ssh/sshd_config.d/*.conf
 
@@ -16,6 +27,22 @@ AcceptEnv LANG LC_* COLORTERM NO_COLOR
 
 # override default of no subsystems
 Subsystem       sftp    /usr/lib/openssh/sftp-server
+
+# Clés actives des clients (table SshKeys), une révocation s'applique dès la connexion suivante
+AuthorizedKeysCommand /usr/local/bin/strongholder-authorized-keys %u
+AuthorizedKeysCommandUser sshkeys
+EOF
+
+# Les fichiers authorized_keys d'avant la table SshKeys restent lus tant que l'API ne les a pas importés
+# (import_authorized_keys.sh). Ensuite seul api garde son fichier : les clés borg et tunnel ne viennent que de l'API
+if [ -f /etc/strongholder/authorized_keys.imported ]; then
+  cat >> /etc/ssh/sshd_config <<'EOF'
+
+Match User *,!api
+    AuthorizedKeysFile none
 EOF
+else
+  echo "Clés authorized_keys pas encore importées par l'API : sshd les accepte encore" >&2
+fi
 
 exec /usr/sbin/sshd -D
\ No newline at end of file
@@ -0,0 +1,19 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT STREAM_ID}" #nom client
+STREAM_ID="${2:?Usage: $0 CLIENT STREAM_ID}"
+
+RESTORE_PATH="/srv/repos/${CLIENT}/restore"
+
+[[ "$STREAM_ID" =~ ^[0-9a-f]{32}$ ]] || { echo "invalid stream id: $STREAM_ID"; exit 1; }
+
+PID_FILE="${RESTORE_PATH}/.${STREAM_ID}.pid"
+
+# l'export s'est déjà terminé
+if [ ! -f "${PID_FILE}" ]; then
+    exit 0
+fi
+
+# sudo transmet le signal à borg, export_tar.sh supprime ensuite le fichier pid
+pkill -TERM -P "$(cat "${PID_FILE}")" || true
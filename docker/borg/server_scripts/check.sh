@@ -0,0 +1,57 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT repository|verify_data}" #nom client
+MODE="${2:?Usage: $0 CLIENT repository|verify_data}"
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+# Codes de sortie :
+#   0  dépôt sain
+#   10 borg check a trouvé des erreurs d'intégrité (code 1 de borg check)
+#   11 la vérification n'a pas pu être lancée (clé absente, dépôt verrouillé...)
+#   12 borg check a échoué sans verdict sur le dépôt (code 2 ou plus : erreur de borg, connexion...)
+if [ ! -d "${REPOSITORY_PATH}" ]; then
+    echo "Repository ${REPOSITORY_PATH} not found."
+    exit 11
+fi
+
+case "${MODE}" in
+    # vérifie uniquement les segments du dépôt, la clé n'est pas nécessaire
+    repository) CHECK_OPTION="--repository-only" ;;
+    # relit et déchiffre toutes les données, nécessite la clé
+    verify_data)
+        if [ ! -f $KEY_CLEAR ]; then
+            echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+            exit 11
+        fi
+        chmod 770 "${KEY_CLEAR}"
+        chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+        CHECK_OPTION="--verify-data"
+        ;;
+    *) echo "invalid mode: ${MODE}"; exit 11 ;;
+esac
+
+# borg check écrit son rapport sur stderr, il est renvoyé sur stdout pour l'API
+set +e
+OUTPUT="$(sudo -u "${CLIENT}" borg check --lock-wait 60 ${CHECK_OPTION} "${REPOSITORY_PATH}" 2>&1)"
+RC=$?
+set -e
+echo "${OUTPUT}"
+
+if [ "${RC}" -eq 0 ]; then
+    exit 0
+fi
+# une sauvegarde en cours garde le verrou, ce n'est pas une corruption
+if echo "${OUTPUT}" | grep -q "Failed to create/acquire the lock"; then
+    exit 11
+fi
+# borg check ne signale les erreurs d'intégrité trouvées que par le code 1 (warning)
+if [ "${RC}" -eq 1 ]; then
+    exit 10
+fi
+exit 12
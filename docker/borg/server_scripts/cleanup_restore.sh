@@ -0,0 +1,13 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT RESTORE_NAME}" #nom client
+RESTORE_NAME="${2:?Usage: $0 CLIENT RESTORE_NAME}"
+
+RESTORE_PATH="/srv/repos/${CLIENT}/restore"
+
+# le nom est généré par l'API, on refuse tout ce qui pourrait sortir du dossier restore
+[[ "$RESTORE_NAME" =~ ^[0-9a-f]{32}$ ]] || { echo "invalid restore name: $RESTORE_NAME"; exit 1; }
+
+# fichier restauré, archive du dossier restauré, nom de téléchargement...
+rm -f "${RESTORE_PATH}/${RESTORE_NAME}" "${RESTORE_PATH}/${RESTORE_NAME}".*
@@ -0,0 +1,84 @@
+#!/bin/bash
+set -euo pipefail
+export LC_ALL=C
+
+CLIENT="${1:?Usage: $0 CLIENT [--accept-client-deletes] [ARCHIVE...]}" #nom client
+shift
+ACCEPT_CLIENT_DELETES=0
+if [ "${1:-}" = "--accept-client-deletes" ]; then
+    ACCEPT_CLIENT_DELETES=1
+    shift
+fi
+# les archives restantes sont celles que l'API vient de supprimer (delete_archive.sh, prune.sh)
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+TRANSACTIONS="${REPOSITORY_PATH}transactions"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+# état du dernier compactage (archives présentes, nombre de transactions du client),
+# dans le volume /srv mais hors du dossier du client
+STATE_DIR="/srv/compact/${CLIENT}"
+ARCHIVES_FILE="${STATE_DIR}/archives"
+TRANSACTIONS_FILE="${STATE_DIR}/transactions"
+
+# Codes de sortie :
+#   0 dépôt compacté
+#   1 erreur (clé absente, borg en échec)
+#   3 compactage refusé : des archives ont disparu lors de transactions du client
+#   4 premier passage : état de référence enregistré, rien n'est compacté
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+
+install -d -m 0700 -o root -g root "${STATE_DIR}"
+
+CURRENT="$(sudo -u "${CLIENT}" borg list --short "${REPOSITORY_PATH}" | sort)"
+# en append-only, seules les transactions du client sont écrites dans ce fichier
+TRANSACTION_COUNT=0
+if [ -f "${TRANSACTIONS}" ]; then
+    TRANSACTION_COUNT="$(wc -l < "${TRANSACTIONS}")"
+fi
+
+save_state() {
+    echo "${CURRENT}" > "${ARCHIVES_FILE}"
+    echo "${TRANSACTION_COUNT}" > "${TRANSACTIONS_FILE}"
+}
+
+# sans référence, les transactions passées ne peuvent pas être jugées : pas de compactage
+if [ ! -f "${ARCHIVES_FILE}" ] || [ ! -f "${TRANSACTIONS_FILE}" ]; then
+    save_state
+    echo "no compaction reference for ${CLIENT}, reference saved" >&2
+    exit 4
+fi
+
+# archives du dernier compactage, moins celles supprimées par l'API depuis
+PREVIOUS="$(sed '/^$/d' "${ARCHIVES_FILE}")"
+for ARCHIVE in "$@"; do
+    PREVIOUS="$(echo "${PREVIOUS}" | grep -vxF -- "${ARCHIVE}" || true)"
+done
+
+MISSING=""
+if [ "${TRANSACTION_COUNT}" -ne "$(cat "${TRANSACTIONS_FILE}")" ]; then
+    MISSING="$(comm -23 <(echo "${PREVIOUS}" | sed '/^$/d' | sort) <(echo "${CURRENT}" | sed '/^$/d'))"
+fi
+
+# borg compact rendrait définitives les suppressions du client, que l'append-only permet encore d'annuler
+if [ -n "${MISSING}" ] && [ "${ACCEPT_CLIENT_DELETES}" -eq 0 ]; then
+    # les suppressions de l'API ne doivent pas être signalées au passage suivant
+    echo "${PREVIOUS}" > "${ARCHIVES_FILE}"
+    while read -r ARCHIVE; do
+        echo "client deleted: ${ARCHIVE}" >&2
+    done <<< "${MISSING}"
+    echo "compaction refused: archives deleted by client transactions, see ${TRANSACTIONS}" >&2
+    exit 3
+fi
+
+sudo -u "${CLIENT}" borg compact "${REPOSITORY_PATH}" >&2
+save_state
@@ -20,7 +20,6 @@ API_USER="api"
 # --- Global paths / prerequisites (prepared by prepserv.sh) ---
 SECRET_FILE="/etc/backup_secrets/key.pass"
 TMPBASE="/tmp/borgkey"
-TUNNEL_STATE_BASE="/var/lib/tunnel/clients"
 
 SERVER_KEYS_DIR="/etc/backup_server_keys"   # plus clair que /etc/.ssh
 SERVER_TO_CLIENT_KEY="${SERVER_KEYS_DIR}/server_to_client_ed25519"
@@ -29,7 +28,6 @@ SERVER_TO_CLIENT_KEY="${SERVER_KEYS_DIR}/server_to_client_ed25519"
 if [ ! -f "${TMPBASE}" ]; then
   install -d -m 2770 -o $API_USER -g borgkey /tmp/borgkey "${TMPBASE}"
 fi
-# [ -d "$TUNNEL_STATE_BASE" ] || { echo "missing $TUNNEL_STATE_BASE (prepserv.sh must create it)"; exit 1; }
 
 
 # temp key export (server-side, short-lived)
@@ -48,9 +46,6 @@ install -d -o "$BORG_USER" -g "$API_USER" -m 0750 "$REPO_DIR"
 install -d -o "$BORG_USER" -g "$API_USER" -m 0750 "$BOOTSTRAP_DIR"
 install -d -o "$BORG_USER" -g "$API_USER" -m 0750 "$RESTORE_PATH"
 
-# Make sure alloc_reverse_port.sh never needs mkdir
-install -d -o root -g root -m 0700 "${TUNNEL_STATE_BASE}/${CLIENT}"
-
 # --- Init borg repo if needed ---
 # We use keyfile encryption with EMPTY passphrase (so borg doesn't prompt).
 # The protection is your .gpg wrapping + short-lived plaintext on client during backup.
@@ -81,19 +76,17 @@ chmod 0640 "$KEY_GPG"
 chown root:backupsecrets "$KEY_GPG"
 chmod 0640 "$KEY_GPG"
 
-# --- Prepare SSH skeleton for install_client_key.sh (no mkdir elsewhere) ---
+# --- SSH dir (les clés autorisées viennent de l'API, voir strongholder-authorized-keys) ---
 install -d -o "$BORG_USER" -g "$BORG_USER" -m 0700 "$HOME_DIR/.ssh"
-install -o "$BORG_USER" -g "$BORG_USER" -m 0600 /dev/null "$HOME_DIR/.ssh/authorized_keys"
 chmod -R 770 $HOME_DIR
 chown -R $BORG_USER:$API_USER $HOME_DIR
 
 #perms for ssh
 chmod 700 $HOME_DIR/.ssh
-chmod 600 $HOME_DIR/.ssh/authorized_keys
 chown -R $BORG_USER:$BORG_USER $HOME_DIR/.ssh
 chmod go-w $HOME_DIR
 
 
 echo "OK created/updated: user=$BORG_USER home=$HOME_DIR repo=$REPO_DIR"
 echo "Bootstrap key (encrypted): $KEY_GPG"
-echo "Next: run install_client_key.sh to allow borg serve access for this client."
\ No newline at end of file
+echo "Next: send the client borg key to /api/send_ssh_key to allow borg serve access for this client."
\ No newline at end of file
@@ -0,0 +1,40 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT ARCHIVE}" #nom client
+ARCHIVE="${2:?Usage: $0 CLIENT ARCHIVE}"
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+
+# code 2 : l'archive n'existe pas
+if ! sudo -u "${CLIENT}" borg info "${REPOSITORY_PATH}"::"${ARCHIVE}" > /dev/null 2>&1; then
+    echo "archive not found: ${ARCHIVE}"
+    exit 2
+fi
+
+sudo -u "${CLIENT}" borg delete "${REPOSITORY_PATH}"::"${ARCHIVE}"
+DELETED=("${ARCHIVE}")
+
+# l'archive des logs du client porte le même nom suivi de _logs
+if [[ "${ARCHIVE}" != *_logs ]] && sudo -u "${CLIENT}" borg info "${REPOSITORY_PATH}"::"${ARCHIVE}_logs" > /dev/null 2>&1; then
+    sudo -u "${CLIENT}" borg delete "${REPOSITORY_PATH}"::"${ARCHIVE}_logs"
+    DELETED+=("${ARCHIVE}_logs")
+    echo "${ARCHIVE}_logs"
+fi
+echo "${ARCHIVE}"
+
+# libère réellement l'espace disque (borg >= 1.2), sauf si le client a supprimé des archives :
+# compact.sh sort alors en code 3 (ou 4 au premier passage), l'archive reste supprimée
+/usr/local/sbin/compact.sh "${CLIENT}" "${DELETED[@]}" >&2
@@ -0,0 +1,23 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT ARCHIVE_1 ARCHIVE_2}" #nom client
+ARCHIVE_1="${2:?Usage: $0 CLIENT ARCHIVE_1 ARCHIVE_2}"
+ARCHIVE_2="${3:?Usage: $0 CLIENT ARCHIVE_1 ARCHIVE_2}"
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+
+# une ligne json par chemin modifié entre les deux archives
+sudo -u "${CLIENT}" borg diff --json-lines "${REPOSITORY_PATH}"::"${ARCHIVE_1}" "${ARCHIVE_2}"
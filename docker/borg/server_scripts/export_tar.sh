@@ -0,0 +1,46 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT ARCHIVE STREAM_ID FORMAT [PATH...]}" #nom client
+ARCHIVE="${2:?Usage: $0 CLIENT ARCHIVE STREAM_ID FORMAT [PATH...]}"
+STREAM_ID="${3:?Usage: $0 CLIENT ARCHIVE STREAM_ID FORMAT [PATH...]}"
+FORMAT="${4:?Usage: $0 CLIENT ARCHIVE STREAM_ID FORMAT [PATH...]}"
+shift 4
+# sans PATH l'archive complète est exportée
+
+API_USER="api"
+
+RESTORE_PATH="/srv/repos/${CLIENT}/restore"
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+
+# l'identifiant est généré par l'API, on refuse tout ce qui pourrait sortir du dossier restore
+[[ "$STREAM_ID" =~ ^[0-9a-f]{32}$ ]] || { echo "invalid stream id: $STREAM_ID" >&2; exit 1; }
+
+# le zip est produit par l'API à partir du tar
+case "${FORMAT}" in
+    tar) TAR_FILTER=() ;;
+    tar.gz) TAR_FILTER=(--tar-filter=gzip) ;;
+    tar.zst) TAR_FILTER=(--tar-filter=zstd) ;;
+    *) echo "invalid format: $FORMAT" >&2; exit 1 ;;
+esac
+
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT." >&2
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown ${CLIENT}:"${API_USER}" "${KEY_CLEAR}"
+
+# permet à cancel_export.sh d'arrêter l'export si le client se déconnecte
+PID_FILE="${RESTORE_PATH}/.${STREAM_ID}.pid"
+echo $$ > "${PID_FILE}"
+trap 'rm -f "${PID_FILE}"' EXIT
+
+# stdout est l'archive elle-même, rien d'autre ne doit y être écrit
+# export-tar conserve l'arborescence de chaque chemin demandé
+# la progression est écrite en json sur stderr et lue par l'API
+sudo -u "${CLIENT}" borg --progress --log-json export-tar "${TAR_FILTER[@]}" "${REPOSITORY_PATH}"::"${ARCHIVE}" - -- "$@"
@@ -0,0 +1,57 @@
+#!/bin/bash
+set -euo pipefail
+
+# Reprise des clés installées dans les fichiers authorized_keys avant la table SshKeys.
+#   list : une ligne "<usage> <client> <type> <base64>" par clé encore dans un fichier
+#   done : les clés sont en base, les fichiers sont renommés et sshd ne lira plus que l'API au prochain démarrage
+MODE="${1:?Usage: $0 list|done}"
+
+REPOS_DIR="/srv/repos"
+API_USER="api"
+TUNNEL_AUTH_KEYS="/home/tunnel/.ssh/authorized_keys"
+# lu par entrypoint.sh pour retirer le repli sur les fichiers authorized_keys
+IMPORTED_MARKER="/etc/strongholder/authorized_keys.imported"
+
+KEY_PATTERN='(ssh-(ed25519|rsa|dss)|ecdsa-sha2-nistp[0-9]+|sk-[a-z0-9@.-]+) [A-Za-z0-9+/=]+'
+
+client_files() {
+    for AUTH_KEYS in "${REPOS_DIR}"/*/.ssh/authorized_keys; do
+        [ -f "${AUTH_KEYS}" ] || continue
+        CLIENT="$(basename "$(dirname "$(dirname "${AUTH_KEYS}")")")"
+        # la clé de l'api reste dans son fichier
+        [ "${CLIENT}" != "${API_USER}" ] || continue
+        echo "${CLIENT} ${AUTH_KEYS}"
+    done
+}
+
+case "${MODE}" in
+    list)
+        while read -r CLIENT AUTH_KEYS; do
+            { grep -F 'borg serve' "${AUTH_KEYS}" || true; } | { grep -oE "${KEY_PATTERN}" || true; } | while read -r KEY_TYPE KEY_BLOB; do
+                echo "borg ${CLIENT} ${KEY_TYPE} ${KEY_BLOB}"
+            done
+        done < <(client_files)
+        # install_client_tunnel_key.sh ajoutait le nom du client en dernière colonne
+        if [ -f "${TUNNEL_AUTH_KEYS}" ]; then
+            while read -r LINE; do
+                KEY="$(echo "${LINE}" | grep -oE "${KEY_PATTERN}" | head -n 1)" || continue
+                CLIENT="$(echo "${LINE}" | awk '{print $NF}')"
+                # sans nom de client, la dernière colonne est la clé elle-même
+                [[ "${CLIENT}" =~ ^[A-Za-z0-9_-]+$ ]] && [ "${CLIENT}" != "${KEY##* }" ] || continue
+                echo "tunnel ${CLIENT} ${KEY}"
+            done < "${TUNNEL_AUTH_KEYS}"
+        fi
+        ;;
+    done)
+        while read -r CLIENT AUTH_KEYS; do
+            mv "${AUTH_KEYS}" "${AUTH_KEYS}.imported"
+            echo "${CLIENT}: authorized_keys imported"
+        done < <(client_files)
+        if [ -f "${TUNNEL_AUTH_KEYS}" ]; then
+            mv "${TUNNEL_AUTH_KEYS}" "${TUNNEL_AUTH_KEYS}.imported"
+            echo "tunnel: authorized_keys imported"
+        fi
+        touch "${IMPORTED_MARKER}"
+        ;;
+    *) echo "invalid mode: ${MODE}"; exit 1 ;;
+esac
@@ -0,0 +1,29 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT [ARCHIVE|--all-archives]}" #nom client
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+
+ARCHIVE="${2-}"
+if [ -z "${ARCHIVE}" ]; then
+    # tailles du dépôt complet (original, compressé, dédupliqué)
+    sudo -u "${CLIENT}" borg info "${REPOSITORY_PATH}" --json
+elif [ "${ARCHIVE}" = "--all-archives" ]; then
+    # dépôt complet et statistiques de chaque archive
+    sudo -u "${CLIENT}" borg info "${REPOSITORY_PATH}" --json -a '*'
+else
+    sudo -u "${CLIENT}" borg info "${REPOSITORY_PATH}"::"${ARCHIVE}" --json
+fi
@@ -19,12 +19,27 @@ for s in \
   preparedecrypt.sh \
   server_cleanup_key.sh \
   create_user.sh \
-  install_client_key.sh \
-  install_client_tunnel_key.sh \
   prepserv.sh \
   gen_gpg_passphrase.sh \
   list.sh \
-  restore.sh
+  diff.sh \
+  restore.sh \
+  export_tar.sh \
+  cancel_export.sh \
+  store_restore.sh \
+  share_file.sh \
+  cleanup_restore.sh \
+  purge_restore.sh \
+  delete_archive.sh \
+  prune.sh \
+  compact.sh \
+  info.sh \
+  check.sh \
+  last_transaction.sh \
+  read_logs.sh \
+  tunnel_sessions.sh \
+  kill_tunnel.sh \
+  import_authorized_keys.sh
 do
   install -m 0700 -o root -g root "${SRC_DIR}/${s}" "${DST_DIR}/${s}"
 done
@@ -34,4 +49,4 @@ rm -r /usr/local/sbin/scripts
 echo "[install_all] Done."
 
 echo "[install_all] Running prepserv.sh"
-/usr/local/sbin/prepserv.sh
\ No newline at end of file
+/usr/local/sbin/prepserv.sh
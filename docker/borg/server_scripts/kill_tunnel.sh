@@ -0,0 +1,22 @@
+#!/bin/bash
+set -euo pipefail
+
+# Ferme la session sshd de l'utilisateur tunnel qui écoute sur PORT (reverse tunnel bloqué).
+# Affiche "KILLED <nombre>" ou "NOT_FOUND".
+# Usage: kill_tunnel.sh PORT
+
+PORT="${1:?Usage: $0 PORT}"
+[[ "$PORT" =~ ^[0-9]+$ ]] || { echo "invalid port: $PORT"; exit 1; }
+
+SESSIONS="$(/usr/local/sbin/tunnel_sessions.sh "$PORT")"
+if [ -z "$SESSIONS" ]; then
+  echo "NOT_FOUND"
+  exit 0
+fi
+
+KILLED=0
+while read -r _ PID; do
+  kill "$PID" 2>/dev/null && KILLED=$((KILLED + 1)) || true
+done <<< "$SESSIONS"
+
+echo "KILLED ${KILLED}"
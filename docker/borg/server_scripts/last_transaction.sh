@@ -0,0 +1,16 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT}" #nom client
+
+# borg serve --append-only (connexion des clients) ajoute une ligne à ce journal à chaque transaction validée :
+#   transaction 42, UTC time 2026-02-18T16:37:10.123456
+# Les commandes lancées par l'API sur le dépôt (prune, compact, delete) n'y écrivent pas, la clé n'est pas nécessaire
+TRANSACTIONS="/srv/repos/${CLIENT}/repo/transactions"
+
+# Aucune sauvegarde n'a encore été validée
+if [ ! -f "${TRANSACTIONS}" ]; then
+    exit 0
+fi
+
+tail -n 1 "${TRANSACTIONS}"
@@ -12,6 +12,8 @@ BACKUP_SSH_DIR="${BACKUP_HOME}/.ssh"
 TUNNEL_USER="tunnel"
 TUNNEL_HOME="/home/tunnel"
 
+KEYS_COMMAND_USER="sshkeys" # AuthorizedKeysCommandUser (voir entrypoint.sh)
+
 SECRET_DIR="/etc/backup_secrets"
 SECRET_GROUP="backupsecrets"
 SECRET_FILE="${SECRET_DIR}/key.pass" #clé de chiffrement serveur
@@ -19,8 +21,6 @@ SECRET_FILE="${SECRET_DIR}/key.pass" #clé de chiffrement serveur
 SERVER_KEYS_DIR="/etc/backup_server_keys" #clés ssh du serveur
 SERVER_TO_CLIENT_KEY="${SERVER_KEYS_DIR}/server_to_client_ed25519"
 
-TUNNEL_STATE_DIR="/var/lib/tunnel"          # utilisé par alloc_reverse_port.sh
-
 SUDOERS_BACKUP="/etc/sudoers.d/backup-maint"
 # ------------------
 
@@ -29,10 +29,25 @@ SUDOERS_BACKUP="/etc/sudoers.d/backup-maint"
 SCRIPTS_DIR="/usr/local/sbin/"
 
 CREATE_USER_SCRIPT="${SCRIPTS_DIR}/create_user.sh"
-INSTALL_CLIENT_KEY_SCRIPT="${SCRIPTS_DIR}/install_client_key.sh"
-INSTALL_CLIENT_TUNNEL_SCRIPT="${SCRIPTS_DIR}/install_client_tunnel_key.sh"
 LIST_SCRIPT="${SCRIPTS_DIR}/list.sh"
 RESTORE_SCRIPT="${SCRIPTS_DIR}/restore.sh"
+DIFF_SCRIPT="${SCRIPTS_DIR}/diff.sh"
+EXPORT_TAR_SCRIPT="${SCRIPTS_DIR}/export_tar.sh"
+CANCEL_EXPORT_SCRIPT="${SCRIPTS_DIR}/cancel_export.sh"
+STORE_RESTORE_SCRIPT="${SCRIPTS_DIR}/store_restore.sh"
+SHARE_FILE_SCRIPT="${SCRIPTS_DIR}/share_file.sh"
+CLEANUP_RESTORE_SCRIPT="${SCRIPTS_DIR}/cleanup_restore.sh"
+PURGE_RESTORE_SCRIPT="${SCRIPTS_DIR}/purge_restore.sh"
+DELETE_ARCHIVE_SCRIPT="${SCRIPTS_DIR}/delete_archive.sh"
+PRUNE_SCRIPT="${SCRIPTS_DIR}/prune.sh"
+COMPACT_SCRIPT="${SCRIPTS_DIR}/compact.sh"
+INFO_SCRIPT="${SCRIPTS_DIR}/info.sh"
+CHECK_SCRIPT="${SCRIPTS_DIR}/check.sh"
+LAST_TRANSACTION_SCRIPT="${SCRIPTS_DIR}/last_transaction.sh"
+READ_LOGS_SCRIPT="${SCRIPTS_DIR}/read_logs.sh"
+TUNNEL_SESSIONS_SCRIPT="${SCRIPTS_DIR}/tunnel_sessions.sh"
+KILL_TUNNEL_SCRIPT="${SCRIPTS_DIR}/kill_tunnel.sh"
+IMPORT_AUTHORIZED_KEYS_SCRIPT="${SCRIPTS_DIR}/import_authorized_keys.sh"
 
 SUDOERS_TUNNEL="/etc/sudoers.d/tunnel-backup"
 
@@ -63,6 +78,11 @@ if ! id "${BACKUP_USER}" >/dev/null 2>&1; then
   passwd -d api
 fi
 
+# utilisateur sans droits qui lance l'AuthorizedKeysCommand de sshd
+if ! id "${KEYS_COMMAND_USER}" >/dev/null 2>&1; then
+  useradd -r -M -d /nonexistent -s /usr/sbin/nologin "${KEYS_COMMAND_USER}"
+fi
+
 if ! id "${TUNNEL_USER}" >/dev/null 2>&1; then
   useradd -d "${TUNNEL_HOME}" -m -s /bin/sh "${TUNNEL_USER}"
   usermod -aG borgkey $TUNNEL_USER
@@ -93,12 +113,6 @@ if [ -f "${SECRET_FILE}" ]; then
   chmod 0640 "${SECRET_FILE}"
 fi
 
-# state dir pour les ports (very important :D)
-echo "[prepareserv] Prepare tunnel state dir ${TUNNEL_STATE_DIR}"
-install -d -m 0700 -o root -g root "${TUNNEL_STATE_DIR}"
-install -d -m 0700 -o root -g root "${TUNNEL_STATE_DIR}/locks"
-install -d -m 0700 -o root -g root "${TUNNEL_STATE_DIR}/clients"
-
 # clé server to client
 echo "[prepareserv] Prepare server->client SSH key in ${SERVER_KEYS_DIR}"
 install -d -m 0770 -o root -g api "${SERVER_KEYS_DIR}"
@@ -142,7 +156,7 @@ install -d -m 0755 -o root -g root /etc/sudoers.d
 
 cat > "${SUDOERS_BACKUP}" <<EOF
 # Allow backup user to run only specific maintenance scripts without password
-${BACKUP_USER} ALL=(root) NOPASSWD: ${CREATE_USER_SCRIPT}, ${INSTALL_CLIENT_KEY_SCRIPT}, ${RESTORE_SCRIPT}, ${LIST_SCRIPT}, ${INSTALL_CLIENT_TUNNEL_SCRIPT}
+${BACKUP_USER} ALL=(root) NOPASSWD: ${CREATE_USER_SCRIPT}, ${RESTORE_SCRIPT}, ${LIST_SCRIPT}, ${DIFF_SCRIPT}, ${EXPORT_TAR_SCRIPT}, ${CANCEL_EXPORT_SCRIPT}, ${STORE_RESTORE_SCRIPT}, ${SHARE_FILE_SCRIPT}, ${CLEANUP_RESTORE_SCRIPT}, ${PURGE_RESTORE_SCRIPT}, ${DELETE_ARCHIVE_SCRIPT}, ${PRUNE_SCRIPT}, ${COMPACT_SCRIPT}, ${INFO_SCRIPT}, ${CHECK_SCRIPT}, ${LAST_TRANSACTION_SCRIPT}, ${READ_LOGS_SCRIPT}, ${TUNNEL_SESSIONS_SCRIPT}, ${KILL_TUNNEL_SCRIPT}, ${IMPORT_AUTHORIZED_KEYS_SCRIPT}
 EOF
 chmod 0440 "${SUDOERS_BACKUP}"
 
@@ -0,0 +1,50 @@
+#!/bin/bash
+set -euo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT ARCHIVE...}" #nom client
+shift
+if [ "$#" -eq 0 ]; then
+    echo "Usage: $0 CLIENT ARCHIVE..."
+    exit 1
+fi
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+
+# Codes de sortie (en plus de ceux de compact.sh, 3 et 4, la liste des suppressions reste valable) :
+#   0 archives supprimées, compactage fait ou en échec
+#   1 erreur
+
+# La liste est calculée par l'API (règles de rétention, paires _logs, verrous),
+# borg prune n'est donc pas utilisé. Une ligne par archive supprimée.
+DELETED=()
+for ARCHIVE in "$@"; do
+    if sudo -u "${CLIENT}" borg delete "${REPOSITORY_PATH}"::"${ARCHIVE}" >&2; then
+        echo "${ARCHIVE}"
+        DELETED+=("${ARCHIVE}")
+    else
+        echo "failed to delete ${ARCHIVE}" >&2
+    fi
+done
+
+# les archives sont déjà supprimées, un échec du compactage ne doit pas masquer la liste.
+# compact.sh refuse de compacter si le client a supprimé des archives (append-only)
+set +e
+/usr/local/sbin/compact.sh "${CLIENT}" "${DELETED[@]}" >&2
+RC=$?
+set -e
+if [ "${RC}" -eq 3 ] || [ "${RC}" -eq 4 ]; then
+    exit "${RC}"
+fi
+[ "${RC}" -eq 0 ] || echo "borg compact failed" >&2
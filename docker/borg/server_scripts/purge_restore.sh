@@ -0,0 +1,24 @@
+#!/bin/bash
+set -euo pipefail
+
+MAX_AGE="${1:?Usage: $0 MAX_AGE_MINUTES}"
+
+[[ "$MAX_AGE" =~ ^[0-9]+$ ]] || { echo "invalid age: $MAX_AGE" >&2; exit 1; }
+
+# Supprime tout ce qui traine dans les dossiers restore depuis plus de MAX_AGE minutes.
+# Les fichiers pid des exports en cours sont supprimés par export_tar.sh lui-même.
+# Une ligne "<client> <taille> <nom>" est écrite sur stdout pour chaque élément supprimé,
+# "<client> failed <nom>" pour chaque élément qui n'a pas pu l'être : un échec n'arrête pas le nettoyage.
+for RESTORE_PATH in /srv/repos/*/restore; do
+    [ -d "${RESTORE_PATH}" ] || continue
+    CLIENT="$(basename "$(dirname "${RESTORE_PATH}")")"
+    find "${RESTORE_PATH}" -mindepth 1 -maxdepth 1 -mmin +"${MAX_AGE}" ! -name '.*.pid' -print0 |
+    while IFS= read -r -d '' ENTRY; do
+        SIZE="$(du -sb "${ENTRY}" | cut -f1)" || SIZE=0
+        if rm -rf "${ENTRY}"; then
+            echo "${CLIENT} ${SIZE} $(basename "${ENTRY}")"
+        else
+            echo "${CLIENT} failed $(basename "${ENTRY}")"
+        fi
+    done
+done
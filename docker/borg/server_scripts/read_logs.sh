@@ -0,0 +1,37 @@
+#!/bin/bash
+set -uo pipefail
+
+CLIENT="${1:?Usage: $0 CLIENT ARCHIVE_logs...}" #nom client
+shift
+
+REPOSITORY_PATH="/srv/repos/${CLIENT}/repo/"
+
+HOME_DIR="/srv/repos/${CLIENT}"
+KEY_CLEAR="${HOME_DIR}/.config/borg/keys/srv_repos_${CLIENT}_repo"
+API_USER="api"
+
+if [ ! -f $KEY_CLEAR ]; then
+    echo "Repository key: $KEY_CLEAR not present in .config/borg/keys of $CLIENT."
+    exit 1
+fi
+
+chmod 770 "${KEY_CLEAR}"
+chown "${CLIENT}":"${API_USER}" "${KEY_CLEAR}"
+
+TMP_LOG="$(mktemp)"
+trap 'rm -f "${TMP_LOG}"' EXIT
+
+# Chaque fichier de logs est lu directement dans l'archive, sans passer par restore/.
+# Sortie pour chaque archive :
+#   ### ARCHIVE ok TAILLE   suivi de TAILLE octets de contenu
+#   ### ARCHIVE failed      si l'archive n'a pas pu être lue
+for ARCHIVE in "$@"; do
+    LOG_FILE="${ARCHIVE%_logs}_${CLIENT}.log"
+    if sudo -u "${CLIENT}" borg extract --stdout "${REPOSITORY_PATH}"::"${ARCHIVE}" "sh:**/${LOG_FILE}" > "${TMP_LOG}" 2>/dev/null \
+        && [ -s "${TMP_LOG}" ]; then
+        echo "### ${ARCHIVE} ok $(stat -c %s "${TMP_LOG}")"
+        cat "${TMP_LOG}"
+    else
+        echo "### ${ARCHIVE} failed"
+    fi
+done
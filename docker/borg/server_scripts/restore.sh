@@ -1,8 +1,8 @@
 #!/bin/bash
 set -euo pipefail
 
-CLIENT="${1:?Usage: $0 CLIENT ARCHIVE}" #nom client
-ARCHIVE="${2:?Usage: $0 CLIENT ARCHIVE}"
+CLIENT="${1:?Usage: $0 CLIENT ARCHIVE [TARGET [RESTORE_NAME [FORMAT]]]}" #nom client
+ARCHIVE="${2:?Usage: $0 CLIENT ARCHIVE [TARGET [RESTORE_NAME [FORMAT]]]}"
 
 TARGET="${3-}"
 
@@ -26,14 +26,39 @@ chown ${CLIENT}:"${API_USER}" "${KEY_CLEAR}"
 if [ -z "${TARGET}" ]; then
     sudo -u "${CLIENT}" borg export-tar "${REPOSITORY_PATH}"::"${ARCHIVE}" "${RESTORE_PATH}"/"${ARCHIVE}".tar.gz
 else
-    cd $RESTORE_PATH
-    sudo -u "${CLIENT}" borg extract "${REPOSITORY_PATH}"::"${ARCHIVE}" "${TARGET}"
-    cp "${TARGET}" "${RESTORE_PATH}"
-    rm -r "${TARGET}"
-fi
+    TARGET="${TARGET%/}"
+    # nom du fichier produit, généré par l'API (par défaut le nom de la cible)
+    RESTORE_NAME="${4:-$(basename "${TARGET}")}"
+    if [ -n "${4-}" ] && [[ ! "$RESTORE_NAME" =~ ^[0-9a-f]{32}$ ]]; then
+        echo "invalid restore name: $RESTORE_NAME"
+        exit 1
+    fi
+    # format de l'archive produite si TARGET est un dossier
+    FORMAT="${5:-tar.gz}"
+    case "${FORMAT}" in
+        tar) TAR_OPTION="" ;;
+        tar.gz) TAR_OPTION="--gzip" ;;
+        tar.zst) TAR_OPTION="--zstd" ;;
+        *) echo "invalid format: $FORMAT"; exit 1 ;;
+    esac
 
-chown "${CLIENT}":"${API_USER}" "${RESTORE_PATH}"/*
-chmod 770 "${RESTORE_PATH}"/*
+    # extraction dans un dossier de travail pour ne pas laisser l'arborescence mnt/... derrière
+    WORK_DIR="$(mktemp -d -p "${RESTORE_PATH}")"
+    trap 'rm -rf "${WORK_DIR}"' EXIT
+    chown "${CLIENT}":"${API_USER}" "${WORK_DIR}"
+    cd "${WORK_DIR}"
+    sudo -u "${CLIENT}" borg extract "${REPOSITORY_PATH}"::"${ARCHIVE}" "${TARGET}" >&2
 
+    # stdout indique à l'API le type de ce qui a été restauré
+    if [ -d "${TARGET}" ]; then
+        tar ${TAR_OPTION} -cf "${RESTORE_PATH}/${RESTORE_NAME}.${FORMAT}" -C "$(dirname "${TARGET}")" "$(basename "${TARGET}")"
+        echo "directory"
+    else
+        mv "${TARGET}" "${RESTORE_PATH}/${RESTORE_NAME}"
+        echo "file"
+    fi
+    cd "${RESTORE_PATH}"
+fi
 
-# option pour restore un fichier seul
\ No newline at end of file
+chown "${CLIENT}":"${API_USER}" "${RESTORE_PATH}"/*
+chmod 770 "${RESTORE_PATH}"/*
\ No newline at end of file
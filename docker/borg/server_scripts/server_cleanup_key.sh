@@ -13,4 +13,7 @@ ssh -i "$SERVER_TO_CLIENT_KEY" -p "$REVERSE_PORT" \
   borghelper@localhost \
   "cleanup $CLIENT $LOCAL_USER"
 
+# Le port est rendu à l'API, sans quoi le bail expire une fois le tunnel fermé
+/usr/local/bin/strongholder-tunnel-lease release "$CLIENT" "$REVERSE_PORT" || echo "tunnel lease release failed for port $REVERSE_PORT" >&2
+
 echo "OK cleaned"
\ No newline at end of file
@@ -0,0 +1,36 @@
+#!/bin/bash
+set -euo pipefail
+
+USAGE="Usage: $0 CLIENT RESTORE_ID keep|remove"
+CLIENT="${1:?$USAGE}" #nom client
+RESTORE_ID="${2:?$USAGE}"
+ACTION="${3:?$USAGE}"
+
+API_USER="api"
+
+RESTORE_PATH="/srv/repos/${CLIENT}/restore"
+# fichiers des liens de partage, hors de portée de purge_restore.sh : l'API les supprime
+# quand le lien expire, est révoqué ou a servi s'il est à usage unique
+SHARED_PATH="/srv/repos/${CLIENT}/shared"
+
+# l'identifiant est généré par l'API, on refuse tout ce qui pourrait sortir du dossier
+[[ "$RESTORE_ID" =~ ^[0-9a-f]{32}$ ]] || { echo "invalid restore id: $RESTORE_ID" >&2; exit 1; }
+
+[ -d "${RESTORE_PATH}" ] || { echo "missing ${RESTORE_PATH}" >&2; exit 1; }
+
+case "${ACTION}" in
+    keep)
+        # restauration préparée par store_restore.sh, déplacée avec son nom de téléchargement
+        [ -f "${RESTORE_PATH}/${RESTORE_ID}" ] || { echo "missing ${RESTORE_PATH}/${RESTORE_ID}" >&2; exit 1; }
+        [ -f "${RESTORE_PATH}/${RESTORE_ID}.name" ] || { echo "missing ${RESTORE_PATH}/${RESTORE_ID}.name" >&2; exit 1; }
+        install -d -m 750 -o "${CLIENT}" -g "${API_USER}" "${SHARED_PATH}"
+        mv "${RESTORE_PATH}/${RESTORE_ID}.name" "${RESTORE_PATH}/${RESTORE_ID}" "${SHARED_PATH}/"
+        ;;
+    remove)
+        rm -f "${SHARED_PATH}/${RESTORE_ID}" "${SHARED_PATH}/${RESTORE_ID}.name"
+        ;;
+    *)
+        echo "$USAGE" >&2
+        exit 1
+        ;;
+esac
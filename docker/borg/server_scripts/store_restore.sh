@@ -0,0 +1,43 @@
+#!/bin/bash
+set -euo pipefail
+
+USAGE="Usage: $0 CLIENT RESTORE_ID write|commit FILE_NAME|abort"
+CLIENT="${1:?$USAGE}" #nom client
+RESTORE_ID="${2:?$USAGE}"
+ACTION="${3:?$USAGE}"
+
+API_USER="api"
+
+RESTORE_PATH="/srv/repos/${CLIENT}/restore"
+
+# l'identifiant est généré par l'API, on refuse tout ce qui pourrait sortir du dossier restore
+[[ "$RESTORE_ID" =~ ^[0-9a-f]{32}$ ]] || { echo "invalid restore id: $RESTORE_ID" >&2; exit 1; }
+
+[ -d "${RESTORE_PATH}" ] || { echo "missing ${RESTORE_PATH}" >&2; exit 1; }
+
+OUTPUT="${RESTORE_PATH}/${RESTORE_ID}"
+
+case "${ACTION}" in
+    write)
+        # l'API envoie l'archive sur stdin, une fin de flux prématurée ne doit pas produire un fichier "complet"
+        # le fichier n'apparait donc sous son nom définitif qu'avec commit
+        cat > "${OUTPUT}.part"
+        ;;
+    commit)
+        FILE_NAME="${4:?$USAGE}"
+        [ -f "${OUTPUT}.part" ] || { echo "missing ${OUTPUT}.part" >&2; exit 1; }
+        printf '%s' "${FILE_NAME}" > "${OUTPUT}.name"
+        mv "${OUTPUT}.part" "${OUTPUT}"
+        chown "${CLIENT}":"${API_USER}" "${OUTPUT}" "${OUTPUT}.name"
+        chmod 740 "${OUTPUT}" "${OUTPUT}.name"
+        # taille en octets, lue par l'API
+        stat -c %s "${OUTPUT}"
+        ;;
+    abort)
+        rm -f "${OUTPUT}.part"
+        ;;
+    *)
+        echo "$USAGE" >&2
+        exit 1
+        ;;
+esac
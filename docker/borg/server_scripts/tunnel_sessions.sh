@@ -0,0 +1,28 @@
+#!/bin/bash
+set -euo pipefail
+
+# Reverse tunnels ouverts sur le serveur : une ligne "<port> <pid>" par port de la plage
+# écouté par une session sshd de l'utilisateur tunnel. Avec un PORT, seulement ce port.
+# Usage: tunnel_sessions.sh [PORT]
+
+TUNNEL_USER="tunnel"
+BASE=22000
+MAX=22999
+ONLY_PORT="${1-}"
+
+if [ -n "$ONLY_PORT" ] && [[ ! "$ONLY_PORT" =~ ^[0-9]+$ ]]; then
+  echo "invalid port: $ONLY_PORT"
+  exit 1
+fi
+
+ss -ltnpH | while read -r _ _ _ LOCAL _ PROCESS; do
+  PORT="${LOCAL##*:}"
+  [[ "$PORT" =~ ^[0-9]+$ ]] || continue
+  (( PORT >= BASE && PORT <= MAX )) || continue
+  [ -z "$ONLY_PORT" ] || [ "$PORT" -eq "$ONLY_PORT" ] || continue
+  # users:(("sshd",pid=1234,fd=9)) : un port peut être écouté par plusieurs processus
+  for PID in $(grep -o 'pid=[0-9]*' <<< "$PROCESS" | cut -d= -f2); do
+    [ "$(ps -o user= -p "$PID" | tr -d ' ')" = "$TUNNEL_USER" ] || continue
+    echo "$PORT $PID"
+  done
+done | sort -n -u
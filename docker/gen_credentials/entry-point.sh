@@ -7,6 +7,7 @@ MARIADB_DATABASE=strongholder
 MARIADB_USER=api
 MARIADB_PASSWORD=$(openssl rand -base64 48)
 JWT_SECRET=$(openssl rand -base64 48)
+SCHEDULER_KEY_SECRET=$(openssl rand -base64 48)
 DB_PORT=3306
 DB_HOST=db
 
@@ -20,9 +21,14 @@ if ! [ -d "$credentials_dir/api" ]; then
   DB_HOST="$DB_HOST"
   DB_PORT=$DB_PORT
   JWT_SECRET="$JWT_SECRET"
+  SCHEDULER_KEY_SECRET="$SCHEDULER_KEY_SECRET"
 EOF
   mv $credentials_dir/.env_api $credentials_dir/api/.env
 fi
+# Installations antérieures au secret du planificateur de rétention
+if ! grep -q SCHEDULER_KEY_SECRET $credentials_dir/api/.env; then
+  echo "  SCHEDULER_KEY_SECRET=\"$SCHEDULER_KEY_SECRET\"" >> $credentials_dir/api/.env
+fi
 
 #borg
 if ! [ -d "$credentials_dir/borg" ]; then
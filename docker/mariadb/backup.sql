@@ -45,6 +45,774 @@ set autocommit=0;
 /*!40000 ALTER TABLE `Credentials` ENABLE KEYS */;
 UNLOCK TABLES;
 commit;
+
+--
+-- Table structure for table `RestoreJobs`
+--
+
+DROP TABLE IF EXISTS `RestoreJobs`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `RestoreJobs` (
+  `job_id` varchar(32) NOT NULL,
+  `user_id` varchar(32) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `status` varchar(16) NOT NULL DEFAULT 'queued',
+  `progress` double NOT NULL DEFAULT 0,
+  `restore_id` varchar(32) DEFAULT NULL,
+  `file_name` varchar(1024) DEFAULT NULL,
+  `size` bigint(20) unsigned DEFAULT NULL,
+  `error` varchar(1024) DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `updated_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`job_id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `RestoreJobs_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `RestoreJobs`
+--
+
+LOCK TABLES `RestoreJobs` WRITE;
+/*!40000 ALTER TABLE `RestoreJobs` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `RestoreJobs` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `ArchiveLocks`
+--
+
+DROP TABLE IF EXISTS `ArchiveLocks`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `ArchiveLocks` (
+  `user_id` varchar(32) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `reason` varchar(255) DEFAULT NULL,
+  `locked_until` bigint(20) unsigned DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`user_id`,`archive_name`),
+  CONSTRAINT `ArchiveLocks_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `ArchiveLocks`
+--
+
+LOCK TABLES `ArchiveLocks` WRITE;
+/*!40000 ALTER TABLE `ArchiveLocks` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `ArchiveLocks` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `ArchiveDeletions`
+--
+
+DROP TABLE IF EXISTS `ArchiveDeletions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `ArchiveDeletions` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `status` varchar(16) NOT NULL,
+  `deleted_archives` varchar(1024) DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `ArchiveDeletions_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `ArchiveDeletions`
+--
+
+LOCK TABLES `ArchiveDeletions` WRITE;
+/*!40000 ALTER TABLE `ArchiveDeletions` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `ArchiveDeletions` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `RetentionPolicies`
+--
+
+DROP TABLE IF EXISTS `RetentionPolicies`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `RetentionPolicies` (
+  `user_id` varchar(32) NOT NULL,
+  `keep_hourly` int(10) unsigned NOT NULL DEFAULT 0,
+  `keep_daily` int(10) unsigned NOT NULL DEFAULT 0,
+  `keep_weekly` int(10) unsigned NOT NULL DEFAULT 0,
+  `keep_monthly` int(10) unsigned NOT NULL DEFAULT 0,
+  `keep_yearly` int(10) unsigned NOT NULL DEFAULT 0,
+  `enabled` tinyint(1) NOT NULL DEFAULT 0,
+  `last_applied_at` bigint(20) unsigned DEFAULT NULL,
+  `encrypt_master_key_2` varchar(1200) DEFAULT NULL,
+  `updated_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`user_id`),
+  CONSTRAINT `RetentionPolicies_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `RetentionPolicies`
+--
+
+LOCK TABLES `RetentionPolicies` WRITE;
+/*!40000 ALTER TABLE `RetentionPolicies` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `RetentionPolicies` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `Quotas`
+--
+
+DROP TABLE IF EXISTS `Quotas`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `Quotas` (
+  `user_id` varchar(32) NOT NULL,
+  `quota_bytes` bigint(20) unsigned DEFAULT NULL,
+  `original_size` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `compressed_size` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `deduplicated_size` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `over_quota` tinyint(1) NOT NULL DEFAULT 0,
+  `measured_at` bigint(20) unsigned DEFAULT NULL,
+  PRIMARY KEY (`user_id`),
+  CONSTRAINT `Quotas_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `Quotas`
+--
+
+LOCK TABLES `Quotas` WRITE;
+/*!40000 ALTER TABLE `Quotas` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `Quotas` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `CheckRuns`
+--
+
+DROP TABLE IF EXISTS `CheckRuns`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `CheckRuns` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `mode` varchar(16) NOT NULL,
+  `triggered_by` varchar(16) NOT NULL,
+  `status` varchar(16) NOT NULL DEFAULT 'queued',
+  `output` text DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `finished_at` bigint(20) unsigned DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `CheckRuns_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `CheckRuns`
+--
+
+LOCK TABLES `CheckRuns` WRITE;
+/*!40000 ALTER TABLE `CheckRuns` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `CheckRuns` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `Events`
+--
+
+DROP TABLE IF EXISTS `Events`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `Events` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `severity` varchar(16) NOT NULL,
+  `kind` varchar(64) NOT NULL,
+  `message` varchar(1024) NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`,`created_at`),
+  CONSTRAINT `Events_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `Events`
+--
+
+LOCK TABLES `Events` WRITE;
+/*!40000 ALTER TABLE `Events` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `Events` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `ArchiveAnalyses`
+--
+
+DROP TABLE IF EXISTS `ArchiveAnalyses`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `ArchiveAnalyses` (
+  `user_id` varchar(32) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `nfiles` bigint(20) unsigned NOT NULL,
+  `original_size` bigint(20) unsigned NOT NULL,
+  `deduplicated_size` bigint(20) unsigned NOT NULL,
+  `added` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `modified` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `removed` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `suspicious` tinyint(1) NOT NULL DEFAULT 0,
+  `reasons` varchar(1024) DEFAULT NULL,
+  `analyzed_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`user_id`,`archive_name`),
+  KEY `suspicious` (`user_id`,`suspicious`),
+  CONSTRAINT `ArchiveAnalyses_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `ArchiveAnalyses`
+--
+
+LOCK TABLES `ArchiveAnalyses` WRITE;
+/*!40000 ALTER TABLE `ArchiveAnalyses` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `ArchiveAnalyses` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `LogCache`
+--
+
+DROP TABLE IF EXISTS `LogCache`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `LogCache` (
+  `user_id` varchar(32) NOT NULL,
+  `archive_id` char(64) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `content` mediumtext NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`user_id`,`archive_id`),
+  CONSTRAINT `LogCache_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `LogCache`
+--
+
+LOCK TABLES `LogCache` WRITE;
+/*!40000 ALTER TABLE `LogCache` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `LogCache` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `BackupReports`
+--
+
+DROP TABLE IF EXISTS `BackupReports`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `BackupReports` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `archive_id` char(64) DEFAULT NULL,
+  `status` varchar(16) NOT NULL,
+  `exit_code` int(11) NOT NULL,
+  `start_time` varchar(32) DEFAULT NULL,
+  `end_time` varchar(32) DEFAULT NULL,
+  `duration` double NOT NULL DEFAULT 0,
+  `original_size` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `compressed_size` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `deduplicated_size` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `nfiles` bigint(20) unsigned NOT NULL DEFAULT 0,
+  `count_added` int(10) unsigned NOT NULL DEFAULT 0,
+  `count_modified` int(10) unsigned NOT NULL DEFAULT 0,
+  `count_deleted` int(10) unsigned NOT NULL DEFAULT 0,
+  `count_error` int(10) unsigned NOT NULL DEFAULT 0,
+  `count_warning` int(10) unsigned NOT NULL DEFAULT 0,
+  `files` longtext NOT NULL,
+  `warnings` mediumtext NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `user_archive` (`user_id`,`archive_name`),
+  CONSTRAINT `BackupReports_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `BackupReports`
+--
+
+LOCK TABLES `BackupReports` WRITE;
+/*!40000 ALTER TABLE `BackupReports` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `BackupReports` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `BackupSchedules`
+--
+
+DROP TABLE IF EXISTS `BackupSchedules`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `BackupSchedules` (
+  `user_id` varchar(32) NOT NULL,
+  `interval_hours` int(10) unsigned NOT NULL,
+  `enabled` tinyint(1) NOT NULL DEFAULT 1,
+  `email` varchar(254) DEFAULT NULL,
+  `last_archive_at` bigint(20) unsigned DEFAULT NULL,
+  `missed_alert_for` bigint(20) unsigned DEFAULT NULL,
+  `failed_alert_for` bigint(20) unsigned DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `updated_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`user_id`),
+  CONSTRAINT `BackupSchedules_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `BackupSchedules`
+--
+
+LOCK TABLES `BackupSchedules` WRITE;
+/*!40000 ALTER TABLE `BackupSchedules` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `BackupSchedules` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `Webhooks`
+--
+
+DROP TABLE IF EXISTS `Webhooks`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `Webhooks` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `url` varchar(2048) NOT NULL,
+  `secret` char(64) NOT NULL,
+  `events` varchar(512) NOT NULL,
+  `enabled` tinyint(1) NOT NULL DEFAULT 1,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `Webhooks_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `Webhooks`
+--
+
+LOCK TABLES `Webhooks` WRITE;
+/*!40000 ALTER TABLE `Webhooks` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `Webhooks` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `WebhookDeliveries`
+--
+
+DROP TABLE IF EXISTS `WebhookDeliveries`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `WebhookDeliveries` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `webhook_id` bigint(20) unsigned NOT NULL,
+  `user_id` varchar(32) NOT NULL,
+  `event` varchar(64) NOT NULL,
+  `payload` mediumtext NOT NULL,
+  `status` varchar(16) NOT NULL,
+  `attempts` int(10) unsigned NOT NULL DEFAULT 0,
+  `response_status` smallint(5) unsigned DEFAULT NULL,
+  `error` varchar(1024) DEFAULT NULL,
+  `next_attempt_at` bigint(20) unsigned DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `delivered_at` bigint(20) unsigned DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `pending` (`status`,`next_attempt_at`),
+  KEY `webhook_id` (`webhook_id`),
+  CONSTRAINT `WebhookDeliveries_ibfk_1` FOREIGN KEY (`webhook_id`) REFERENCES `Webhooks` (`id`) ON DELETE CASCADE,
+  CONSTRAINT `WebhookDeliveries_ibfk_2` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `WebhookDeliveries`
+--
+
+LOCK TABLES `WebhookDeliveries` WRITE;
+/*!40000 ALTER TABLE `WebhookDeliveries` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `WebhookDeliveries` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `KnownAddresses`
+--
+
+DROP TABLE IF EXISTS `KnownAddresses`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `KnownAddresses` (
+  `user_id` varchar(32) NOT NULL,
+  `ip` varchar(45) NOT NULL,
+  `first_seen` bigint(20) unsigned NOT NULL,
+  `last_seen` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`user_id`,`ip`),
+  CONSTRAINT `KnownAddresses_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `KnownAddresses`
+--
+
+LOCK TABLES `KnownAddresses` WRITE;
+/*!40000 ALTER TABLE `KnownAddresses` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `KnownAddresses` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `Devices`
+--
+
+DROP TABLE IF EXISTS `Devices`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `Devices` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `name` varchar(32) NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `user_name` (`user_id`,`name`),
+  CONSTRAINT `Devices_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `Devices`
+--
+
+LOCK TABLES `Devices` WRITE;
+/*!40000 ALTER TABLE `Devices` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `Devices` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `SshKeys`
+--
+
+DROP TABLE IF EXISTS `SshKeys`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `SshKeys` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `device_id` bigint(20) unsigned DEFAULT NULL,
+  `purpose` varchar(16) NOT NULL,
+  `key_type` varchar(64) NOT NULL,
+  `fingerprint` varchar(128) NOT NULL,
+  `public_key` text NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `revoked_at` bigint(20) unsigned DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_fingerprint` (`user_id`,`fingerprint`),
+  KEY `device_id` (`device_id`),
+  KEY `purpose_revoked` (`purpose`,`revoked_at`),
+  CONSTRAINT `SshKeys_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE,
+  CONSTRAINT `SshKeys_ibfk_2` FOREIGN KEY (`device_id`) REFERENCES `Devices` (`id`) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `SshKeys`
+--
+
+LOCK TABLES `SshKeys` WRITE;
+/*!40000 ALTER TABLE `SshKeys` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `SshKeys` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `TunnelLeases`
+--
+
+DROP TABLE IF EXISTS `TunnelLeases`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `TunnelLeases` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `port` smallint(5) unsigned NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `last_seen_at` bigint(20) unsigned DEFAULT NULL,
+  `expires_at` bigint(20) unsigned NOT NULL,
+  `released_at` bigint(20) unsigned DEFAULT NULL,
+  `release_reason` varchar(16) DEFAULT NULL,
+  `active_port` smallint(5) unsigned GENERATED ALWAYS AS (if(`released_at` is null,`port`,NULL)) STORED,
+  `active_user` varchar(32) GENERATED ALWAYS AS (if(`released_at` is null,`user_id`,NULL)) STORED,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `active_port` (`active_port`),
+  UNIQUE KEY `active_user` (`active_user`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `TunnelLeases_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `TunnelLeases`
+--
+
+LOCK TABLES `TunnelLeases` WRITE;
+/*!40000 ALTER TABLE `TunnelLeases` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `TunnelLeases` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `Organisations`
+--
+
+DROP TABLE IF EXISTS `Organisations`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `Organisations` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `name` varchar(64) NOT NULL,
+  `repository_id` varchar(32) NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `repository_id` (`repository_id`),
+  CONSTRAINT `Organisations_ibfk_1` FOREIGN KEY (`repository_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `Organisations`
+--
+
+LOCK TABLES `Organisations` WRITE;
+/*!40000 ALTER TABLE `Organisations` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `Organisations` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `OrganisationMembers`
+--
+
+DROP TABLE IF EXISTS `OrganisationMembers`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `OrganisationMembers` (
+  `organisation_id` bigint(20) unsigned NOT NULL,
+  `user_id` varchar(32) NOT NULL,
+  `role` varchar(16) NOT NULL,
+  `encrypt_master_key_1` varchar(1200) DEFAULT NULL,
+  `encrypt_master_key_2` varchar(1200) DEFAULT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`organisation_id`,`user_id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `OrganisationMembers_ibfk_1` FOREIGN KEY (`organisation_id`) REFERENCES `Organisations` (`id`) ON DELETE CASCADE,
+  CONSTRAINT `OrganisationMembers_ibfk_2` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `OrganisationMembers`
+--
+
+LOCK TABLES `OrganisationMembers` WRITE;
+/*!40000 ALTER TABLE `OrganisationMembers` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `OrganisationMembers` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `OrganisationInvitations`
+--
+
+DROP TABLE IF EXISTS `OrganisationInvitations`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `OrganisationInvitations` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `organisation_id` bigint(20) unsigned NOT NULL,
+  `role` varchar(16) NOT NULL,
+  `token_hash` char(64) NOT NULL,
+  `encrypt_master_key_1` varchar(1200) NOT NULL,
+  `encrypt_master_key_2` varchar(1200) NOT NULL,
+  `created_by` varchar(32) NOT NULL,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `expires_at` bigint(20) unsigned NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `organisation_id` (`organisation_id`),
+  KEY `created_by` (`created_by`),
+  CONSTRAINT `OrganisationInvitations_ibfk_1` FOREIGN KEY (`organisation_id`) REFERENCES `Organisations` (`id`) ON DELETE CASCADE,
+  CONSTRAINT `OrganisationInvitations_ibfk_2` FOREIGN KEY (`created_by`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `OrganisationInvitations`
+--
+
+LOCK TABLES `OrganisationInvitations` WRITE;
+/*!40000 ALTER TABLE `OrganisationInvitations` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `OrganisationInvitations` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `ShareLinks`
+--
+
+DROP TABLE IF EXISTS `ShareLinks`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `ShareLinks` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `user_id` varchar(32) NOT NULL,
+  `created_by` varchar(32) NOT NULL,
+  `archive_name` varchar(255) NOT NULL,
+  `file_name` varchar(1024) NOT NULL,
+  `restore_id` char(32) DEFAULT NULL,
+  `single_use` tinyint(1) NOT NULL DEFAULT 0,
+  `use_count` int(10) unsigned NOT NULL DEFAULT 0,
+  `created_at` bigint(20) unsigned NOT NULL,
+  `expires_at` bigint(20) unsigned NOT NULL,
+  `revoked_at` bigint(20) unsigned DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  KEY `created_by` (`created_by`),
+  CONSTRAINT `ShareLinks_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE,
+  CONSTRAINT `ShareLinks_ibfk_2` FOREIGN KEY (`created_by`) REFERENCES `Credentials` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `ShareLinks`
+--
+
+LOCK TABLES `ShareLinks` WRITE;
+/*!40000 ALTER TABLE `ShareLinks` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `ShareLinks` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `ShareLinkRedemptions`
+--
+
+DROP TABLE IF EXISTS `ShareLinkRedemptions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `ShareLinkRedemptions` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `link_id` bigint(20) unsigned NOT NULL,
+  `redeemed_at` bigint(20) unsigned NOT NULL,
+  `ip` varchar(45) NOT NULL,
+  `user_agent` varchar(255) DEFAULT NULL,
+  `status` varchar(16) NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `link_id` (`link_id`),
+  CONSTRAINT `ShareLinkRedemptions_ibfk_1` FOREIGN KEY (`link_id`) REFERENCES `ShareLinks` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `ShareLinkRedemptions`
+--
+
+LOCK TABLES `ShareLinkRedemptions` WRITE;
+/*!40000 ALTER TABLE `ShareLinkRedemptions` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `ShareLinkRedemptions` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
+
+--
+-- Table structure for table `RestorePurges`
+--
+
+DROP TABLE IF EXISTS `RestorePurges`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!40101 SET character_set_client = utf8mb4 */;
+CREATE TABLE `RestorePurges` (
+  `id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+  `ran_at` bigint(20) unsigned NOT NULL,
+  `purged` int(10) unsigned NOT NULL,
+  `freed_bytes` bigint(20) unsigned NOT NULL,
+  `failed` int(10) unsigned NOT NULL,
+  `error` varchar(1024) DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `ran_at` (`ran_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `RestorePurges`
+--
+
+LOCK TABLES `RestorePurges` WRITE;
+/*!40000 ALTER TABLE `RestorePurges` DISABLE KEYS */;
+set autocommit=0;
+/*!40000 ALTER TABLE `RestorePurges` ENABLE KEYS */;
+UNLOCK TABLES;
+commit;
 /*!40103 SET TIME_ZONE=@OLD_TIME_ZONE */;
 
 /*!40101 SET SQL_MODE=@OLD_SQL_MODE */;
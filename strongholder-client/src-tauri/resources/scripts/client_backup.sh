@@ -9,10 +9,19 @@ log() {
 
 CLIENT="${1:?Usage: $0 CLIENT /path/to/save}"
 PATTERN_FILE="${2:?Usage: $0 CLIENT /path/to/save PATTERN_FILE}"
+# Appareil enregistré sur l'API : les archives sont nommées <appareil>@<date>
+DEVICE="${3-}"
 LOCAL_USER="$(id -un)"
 
 
 SAVE_NAME=$(date +%F_%H-%M-%S)
+if [ -n "$DEVICE" ]; then
+  if [[ ! "$DEVICE" =~ ^[A-Za-z0-9_-]{1,32}$ ]]; then
+    echo "Invalid device name: $DEVICE" >&2
+    exit 1
+  fi
+  SAVE_NAME="${DEVICE}@${SAVE_NAME}"
+fi
 
 LOG_DIRECTORY="$HOME/.config/borg/logs"
 LOG_FILE="${LOG_DIRECTORY}/${SAVE_NAME}_${CLIENT}.log"
@@ -21,6 +30,33 @@ if [ ! -d $LOG_DIRECTORY ]; then
 mkdir $LOG_DIRECTORY
 fi
 
+# Lancé par cron (STRONGHOLDER_CRON=1 dans la ligne de crontab), personne ne lit la sortie :
+# le rapport attend dans REPORT_DIRECTORY que l'application l'envoie à l'API au prochain lancement
+REPORT_DIRECTORY="$HOME/.config/borg/reports"
+REPORT_FILE="${REPORT_DIRECTORY}/${SAVE_NAME}.report"
+if [ -n "${STRONGHOLDER_CRON-}" ]; then
+  mkdir -p -m 700 "$REPORT_DIRECTORY"
+fi
+
+# Lignes lues par l'application (backup.rs), sur la sortie standard ou dans le rapport en attente
+write_report() {
+  local borg_exit="$1"
+  local log="$2"
+  if [ -n "${STRONGHOLDER_CRON-}" ]; then
+    (
+      umask 077
+      {
+        echo "ARCHIVE: ${SAVE_NAME}"
+        sed 's/^/BORG: /' "$log"
+        echo "BORG_EXIT: ${borg_exit}"
+      } > "$REPORT_FILE"
+    )
+  else
+    sed 's/^/BORG: /' "$log"
+    echo "BORG_EXIT: ${borg_exit}"
+  fi
+}
+
 SERVER_HOST="strongholder.fr"
 SERVER_SSH_PORT=22
 
@@ -33,6 +69,11 @@ CLIENT_SSH_KEY="$HOME/.ssh/borg_${CLIENT}_key"
 TUNNEL_PID=""
 
 cleanup() {
+  local status=$?
+  # Une sauvegarde programmée interrompue avant la fin de borg create est signalée comme un échec
+  if [ -n "${STRONGHOLDER_CRON-}" ] && [ "$status" -ne 0 ] && [ ! -e "$REPORT_FILE" ]; then
+    write_report 2 <(echo "Sauvegarde interrompue avant la fin de borg create (code ${status})") || true
+  fi
   if [[ -n "${TUNNEL_PID}" ]] && kill -0 "$TUNNEL_PID" 2>/dev/null; then
     kill "$TUNNEL_PID" 2>/dev/null || true
     wait "$TUNNEL_PID" 2>/dev/null || true
@@ -40,7 +81,7 @@ cleanup() {
 }
 trap cleanup EXIT INT TERM
 
-# Port reverse choisi (idéalement unique par client, récupéré via API si tu veux)
+# Port reverse prêté par l'API pour la durée de la sauvegarde (rendu par server_cleanup_key.sh)
 log "Requesting reverse port from server"
 echo "PROGRESS: 10"
 echo "FILE: Requesting secure tunnel port..."
@@ -104,15 +145,24 @@ echo "PROGRESS: 40"
 echo "FILE: Transfering your data..."
 
 export BORG_RSH="ssh -p $SERVER_SSH_PORT -i $CLIENT_SSH_KEY -o IdentitiesOnly=yes -o BatchMode=yes"
+echo "ARCHIVE: ${SAVE_NAME}"
+# borg renvoie 1 pour des avertissements, la sauvegarde est tout de même créée
+BORG_EXIT=0
 {
 borg create --compression zstd,6 --stats --list --json \
   "${REPO}::${SAVE_NAME}" \
   "--patterns-from" \
   "$PATTERN_FILE"
-} >> "$LOG_FILE" 2>&1
+} >> "$LOG_FILE" 2>&1 || BORG_EXIT=$?
+
+# Le journal est transmis à l'application qui en fait le rapport envoyé à l'API
+write_report "$BORG_EXIT" "$LOG_FILE"
+
+if [ "$BORG_EXIT" -lt 2 ]; then
 borg create --compression zstd,6 \
   "${REPO}::${SAVE_NAME}_logs" \
   $LOG_FILE
+fi
 shred -u $LOG_DIRECTORY/*
 
 # 4) Cleanup de la clé claire côté client (déclenché par le serveur via tunnel)
@@ -123,6 +173,11 @@ echo "FILE: Cleaning up server keys..."
 ssh "${SSH_OPTS[@]}" "${SERVER_USER}@${SERVER_HOST}" \
   "sudo /usr/local/sbin/server_cleanup_key.sh ${CLIENT} ${REVERSE_PORT} ${LOCAL_USER}"
 
+if [ "$BORG_EXIT" -ge 2 ]; then
+  echo "Borg create failed with code ${BORG_EXIT}" >&2
+  exit "$BORG_EXIT"
+fi
+
 echo "Backup OK"
 log "Backup finished successfully"
 
@@ -1,4 +1,4 @@
-use crate::parsing::{self, DashboardLogEntry, LogEntry};
+use crate::parsing::{self, BackupReport, DashboardLogEntry, LogEntry};
 use flate2::read::GzDecoder;
 use reqwest::cookie::Jar;
 use reqwest::{Client, StatusCode};
@@ -50,6 +50,12 @@ impl NetworkManager {
         Ok(text)
     }
 
+    pub async fn get_and_parse<R: DeserializeOwned>(&self, url: &str) -> Result<R, String> {
+        let text = self.fetch_text(self.client.get(url)).await?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("Impossible d'analyser le JSON renvoyé : {}", e))
+    }
+
     pub async fn post_raw(&self, url: &str) -> Result<String, String> {
         self.fetch_text(self.client.post(url)).await
     }
@@ -101,6 +107,18 @@ struct ServerKeyResponse {
 #[derive(Serialize)]
 struct SshKeyPayload {
     ssh: String,
+    device_id: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct DevicePayload {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeviceInfo {
+    pub id: u64,
+    pub name: String,
 }
 
 // Intercepte les erreurs HTTP classiques ainsi que les messages d'erreur textuels renvoyés par l'API
@@ -173,11 +191,47 @@ pub async fn get_repo_key_req(state: State<'_, NetworkManager>) -> Result<Vec<u8
     Ok(bytes.to_vec())
 }
 
+// Nom de l'ordinateur, réduit aux caractères acceptés par l'API dans les noms d'archives
+fn local_device_name() -> String {
+    let hostname = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+        .unwrap_or_default();
+    let name: String = hostname
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(32)
+        .collect();
+    if name.is_empty() {
+        "device".to_string()
+    } else {
+        name
+    }
+}
+
+// Enregistre cet ordinateur sur le compte, l'API renvoie l'appareil existant s'il est déjà connu
+#[tauri::command]
+pub async fn register_device_req(state: State<'_, NetworkManager>) -> Result<DeviceInfo, String> {
+    let url = format!("{}/devices", API_BASE);
+    let payload = DevicePayload {
+        name: local_device_name(),
+    };
+    state.post_and_parse_with_payload(&url, &payload).await
+}
+
 #[tauri::command]
 pub async fn send_ssh_key_req(
     state: State<'_, NetworkManager>,
     key_content: String,
     is_tunnel: bool,
+    device_id: Option<u64>,
 ) -> Result<(), String> {
     let endpoint = if is_tunnel {
         "send_ssh_key_tunnel"
@@ -185,7 +239,10 @@ pub async fn send_ssh_key_req(
         "send_ssh_key"
     };
     let url = format!("{}/{}", API_BASE, endpoint);
-    let payload = SshKeyPayload { ssh: key_content };
+    let payload = SshKeyPayload {
+        ssh: key_content,
+        device_id,
+    };
 
     state.post_with_payload_raw(&url, &payload).await?;
     Ok(())
@@ -198,8 +255,108 @@ pub async fn get_server_ssh_key_req(state: State<'_, NetworkManager>) -> Result<
     Ok(json.ssh_pub)
 }
 
+// --- Quota de stockage ---
+
+#[derive(Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub quota_bytes: Option<u64>,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub deduplicated_size: u64,
+    pub over_quota: bool,
+    pub measured_at: Option<u64>,
+}
+
+#[tauri::command]
+pub async fn get_storage_usage_req(
+    state: State<'_, NetworkManager>,
+) -> Result<StorageUsage, String> {
+    let url = format!("{}/storage_usage", API_BASE);
+    state.post_and_parse(&url).await
+}
+
+fn format_gigabytes(bytes: u64) -> String {
+    format!("{:.2} Go", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+// Refuse une nouvelle sauvegarde si le serveur a signalé un dépassement de quota.
+// Si l'API est injoignable, la sauvegarde est laissée au serveur borg.
+pub async fn check_storage_quota(state: &NetworkManager) -> Result<(), String> {
+    let url = format!("{}/storage_usage", API_BASE);
+    let usage: StorageUsage = match state.post_and_parse(&url).await {
+        Ok(usage) => usage,
+        Err(e) => {
+            println!(
+                "[Quota] Vérification impossible, sauvegarde autorisée : {}",
+                e
+            );
+            return Ok(());
+        }
+    };
+    if !usage.over_quota {
+        return Ok(());
+    }
+    let quota = usage
+        .quota_bytes
+        .map(format_gigabytes)
+        .unwrap_or_else(|| "?".to_string());
+    Err(format!(
+        "Quota de stockage dépassé : {} utilisés sur {} autorisés. Supprimez d'anciennes sauvegardes ou contactez l'administrateur avant de lancer une nouvelle sauvegarde.",
+        format_gigabytes(usage.deduplicated_size),
+        quota
+    ))
+}
+
 // --- Historique et Journaux ---
 
+// Résumé d'un rapport de sauvegarde stocké par l'API
+#[derive(Deserialize)]
+struct BackupReportSummary {
+    archive_name: String,
+    archive_id: Option<String>,
+    status: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    duration: f64,
+    original_size: u64,
+    nfiles: u64,
+    count_added: usize,
+    count_modified: usize,
+    count_deleted: usize,
+    count_error: usize,
+}
+
+impl From<BackupReportSummary> for DashboardLogEntry {
+    fn from(report: BackupReportSummary) -> Self {
+        Self {
+            id: report.archive_id.unwrap_or(report.archive_name),
+            date: report.end_time.or(report.start_time).unwrap_or_default(),
+            duration: report.duration,
+            total_size: report.original_size,
+            total_files: report.nfiles,
+            status: if report.status == "error" || report.count_error > 0 {
+                "Error".to_string()
+            } else {
+                "Success".to_string()
+            },
+            count_added: report.count_added,
+            count_modified: report.count_modified,
+            count_deleted: report.count_deleted,
+            count_error: report.count_error,
+        }
+    }
+}
+
+// Un échec d'envoi ne doit pas faire échouer la sauvegarde, l'appelant se contente de l'afficher
+pub async fn send_backup_report(
+    state: &NetworkManager,
+    report: &BackupReport,
+) -> Result<(), String> {
+    let url = format!("{}/backup_report", API_BASE);
+    state.post_with_payload_raw(&url, report).await?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_logs_req(state: State<'_, NetworkManager>) -> Result<Vec<LogEntry>, String> {
     let url = format!("{}/get_log", crate::network::API_BASE);
@@ -215,6 +372,13 @@ pub async fn get_logs_req(state: State<'_, NetworkManager>) -> Result<Vec<LogEnt
 pub struct ArchiveItem {
     pub archive: String,
     pub time: String,
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ArchiveListRequest {
+    device: String,
 }
 
 #[derive(Deserialize)]
@@ -241,11 +405,33 @@ struct ArchiveRequest {
     archive_name: String,
 }
 
+#[derive(Serialize)]
+struct RestoreArchiveRequest {
+    archive_name: String,
+    // tar, tar.gz, tar.zst ou zip
+    format: &'static str,
+}
+
+// Déduit le format demandé au serveur à partir de l'extension choisie dans la boîte de dialogue
+fn restore_format_from_path(target_path: &str) -> &'static str {
+    let lower = target_path.to_lowercase();
+    if lower.ends_with(".zip") {
+        "zip"
+    } else if lower.ends_with(".tar.zst") {
+        "tar.zst"
+    } else if lower.ends_with(".tar") {
+        "tar"
+    } else {
+        "tar.gz"
+    }
+}
+
 // --- Commandes de Restauration ---
 
 #[tauri::command]
 pub async fn fetch_archives_list_req(
     state: State<'_, NetworkManager>,
+    device: Option<String>,
 ) -> Result<Vec<ArchiveItem>, String> {
     let url = format!("{}/get_list", API_BASE);
     println!(
@@ -253,7 +439,14 @@ pub async fn fetch_archives_list_req(
         url
     );
 
-    let data: ArchiveListResponse = state.post_and_parse(&url).await?;
+    // Sans appareil, les archives de tous les ordinateurs du compte sont renvoyées
+    let data: ArchiveListResponse = match device {
+        Some(device) => {
+            let payload = ArchiveListRequest { device };
+            state.post_and_parse_with_payload(&url, &payload).await?
+        }
+        None => state.post_and_parse(&url).await?,
+    };
 
     // On masque les archives de type "logs" à l'utilisateur
     let filtered_archives: Vec<ArchiveItem> = data
@@ -301,6 +494,164 @@ pub fn cancel_restore_operation(state: State<'_, RestoreState>) {
     state.is_cancelled.store(true, Ordering::SeqCst);
 }
 
+// Restauration préparée sur le serveur par /prepare_restore
+#[derive(Serialize, Deserialize, Clone)]
+struct RestoreArtifact {
+    restore_id: String,
+    file_name: String,
+    size: u64,
+    etag: String,
+}
+
+// Enregistré à côté du fichier partiel pour pouvoir reprendre le téléchargement plus tard
+#[derive(Serialize, Deserialize)]
+struct ResumeState {
+    archive_name: String,
+    format: String,
+    artifact: RestoreArtifact,
+}
+
+enum DownloadError {
+    Cancelled,
+    // La restauration n'existe plus sur le serveur (erreur 600)
+    NotFound,
+    // Coupure réseau : le fichier partiel est conservé et le téléchargement peut reprendre
+    Network(String),
+    Fatal(String),
+}
+
+// Nombre de reprises automatiques après une coupure réseau
+const DOWNLOAD_RETRIES: u32 = 5;
+
+fn load_resume_state(
+    state_path: &str,
+    archive_name: &str,
+    format: &str,
+) -> Option<RestoreArtifact> {
+    let content = std::fs::read_to_string(state_path).ok()?;
+    let state: ResumeState = serde_json::from_str(&content).ok()?;
+    if state.archive_name == archive_name && state.format == format {
+        Some(state.artifact)
+    } else {
+        None
+    }
+}
+
+async fn prepare_restore(
+    network_state: &NetworkManager,
+    archive_name: &str,
+    format: &'static str,
+    part_path: &str,
+    state_path: &str,
+) -> Result<RestoreArtifact, String> {
+    let url = format!("{}/prepare_restore", API_BASE);
+    let payload = RestoreArchiveRequest {
+        archive_name: archive_name.to_string(),
+        format,
+    };
+    let artifact: RestoreArtifact = network_state
+        .post_and_parse_with_payload(&url, &payload)
+        .await
+        .map_err(|e| format!("Échec de la préparation de la restauration : {}", e))?;
+
+    // Un ancien fichier partiel ne correspond pas à cette nouvelle restauration
+    let _ = std::fs::remove_file(part_path);
+    let state = ResumeState {
+        archive_name: archive_name.to_string(),
+        format: format.to_string(),
+        artifact: artifact.clone(),
+    };
+    if let Ok(content) = serde_json::to_string(&state) {
+        let _ = std::fs::write(state_path, content);
+    }
+    Ok(artifact)
+}
+
+// Télécharge la suite du fichier partiel, en demandant au serveur uniquement les octets manquants
+async fn download_remaining(
+    network_state: &NetworkManager,
+    restore_state: &RestoreState,
+    artifact: &RestoreArtifact,
+    part_path: &str,
+) -> Result<(), DownloadError> {
+    let offset = std::fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    if offset == artifact.size {
+        return Ok(());
+    }
+
+    let url = format!("{}/download_restore/{}", API_BASE, artifact.restore_id);
+    let mut req = network_state
+        .client
+        .get(&url)
+        .header("Accept", "application/octet-stream");
+    if offset > 0 {
+        println!("[Téléchargement] Reprise à partir de l'octet {}", offset);
+        req = req
+            .header("Range", format!("bytes={}-", offset))
+            .header("If-Range", &artifact.etag);
+    }
+    let mut res = req
+        .send()
+        .await
+        .map_err(|e| DownloadError::Network(format!("Erreur de connexion : {}", e)))?;
+
+    let mut file = match res.status() {
+        StatusCode::PARTIAL_CONTENT => std::fs::OpenOptions::new()
+            .append(true)
+            .open(part_path)
+            .map_err(|e| {
+                DownloadError::Fatal(format!("Impossible d'ouvrir le fichier partiel : {}", e))
+            })?,
+        // Le serveur renvoie le fichier complet : on repart de zéro
+        StatusCode::OK => std::fs::File::create(part_path).map_err(|e| {
+            DownloadError::Fatal(format!("Impossible de créer le fichier cible : {}", e))
+        })?,
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // Fichier partiel plus grand que la restauration, il est inutilisable
+            let _ = std::fs::remove_file(part_path);
+            return Err(DownloadError::Network(
+                "Plage refusée par le serveur".to_string(),
+            ));
+        }
+        status => {
+            let err = res.text().await.unwrap_or_default();
+            if err.trim() == "600" {
+                return Err(DownloadError::NotFound);
+            }
+            return Err(DownloadError::Fatal(format!(
+                "Échec du téléchargement (Code {}) : {}",
+                status, err
+            )));
+        }
+    };
+
+    // Téléchargement en streaming par morceaux (chunks).
+    // Cela permet de télécharger des fichiers de plusieurs Go sans exploser la RAM du système.
+    while let Some(chunk) = res.chunk().await.map_err(|e| {
+        DownloadError::Network(format!("Erreur lors de la lecture du flux réseau : {}", e))
+    })? {
+        // On vérifie à chaque morceau si l'utilisateur a cliqué sur "Annuler"
+        if restore_state.is_cancelled.load(Ordering::SeqCst) {
+            return Err(DownloadError::Cancelled);
+        }
+
+        file.write_all(&chunk).map_err(|e| {
+            DownloadError::Fatal(format!(
+                "Impossible d'écrire les données sur le disque : {}",
+                e
+            ))
+        })?;
+    }
+
+    let downloaded = std::fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    if downloaded < artifact.size {
+        return Err(DownloadError::Network(
+            "Le flux s'est terminé avant la fin du fichier".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn download_and_save_archive_req(
     network_state: State<'_, NetworkManager>,
@@ -311,48 +662,94 @@ pub async fn download_and_save_archive_req(
     // Réinitialise le drapeau d'annulation avant de commencer
     restore_state.is_cancelled.store(false, Ordering::SeqCst);
 
-    let url = format!("{}/get_restore", API_BASE);
     println!(
         "[Téléchargement] Enregistrement direct sur le disque : {}",
         target_path
     );
 
-    let payload = ArchiveRequest { archive_name };
-
-    let mut res = network_state
-        .client
-        .post(&url)
-        .json(&payload)
-        .header("Accept", "application/octet-stream")
-        .send()
-        .await
-        .map_err(|e| format!("Erreur de connexion : {}", e))?;
-
-    if !res.status().is_success() {
-        let err = res.text().await.unwrap_or_default();
-        return Err(format!("Échec du téléchargement : {}", err));
-    }
-
-    let mut file = std::fs::File::create(&target_path)
-        .map_err(|e| format!("Impossible de créer le fichier cible : {}", e))?;
+    let format = restore_format_from_path(&target_path);
+    // Le fichier partiel et la restauration associée sont conservés en cas de coupure
+    let part_path = format!("{}.part", target_path);
+    let state_path = format!("{}.part.json", target_path);
+
+    let mut artifact = match load_resume_state(&state_path, &archive_name, format) {
+        Some(artifact) => {
+            println!(
+                "[Téléchargement] Reprise de la restauration {}",
+                artifact.restore_id
+            );
+            artifact
+        }
+        None => {
+            prepare_restore(
+                &network_state,
+                &archive_name,
+                format,
+                &part_path,
+                &state_path,
+            )
+            .await?
+        }
+    };
 
-    // Téléchargement en streaming par morceaux (chunks).
-    // Cela permet de télécharger des fichiers de plusieurs Go sans exploser la RAM du système.
-    while let Some(chunk) = res
-        .chunk()
-        .await
-        .map_err(|e| format!("Erreur lors de la lecture du flux réseau : {}", e))?
-    {
-        // On vérifie à chaque morceau si l'utilisateur a cliqué sur "Annuler"
-        if restore_state.is_cancelled.load(Ordering::SeqCst) {
-            println!("[Téléchargement] Opération interrompue. Nettoyage du fichier partiel.");
-            let _ = std::fs::remove_file(&target_path);
-            return Err("Téléchargement annulé par l'utilisateur".to_string());
+    let mut attempt = 0;
+    let mut prepared_again = false;
+    loop {
+        match download_remaining(&network_state, &restore_state, &artifact, &part_path).await {
+            Ok(()) => break,
+            Err(DownloadError::Cancelled) => {
+                println!("[Téléchargement] Opération interrompue. Nettoyage du fichier partiel.");
+                let _ = std::fs::remove_file(&part_path);
+                let _ = std::fs::remove_file(&state_path);
+                return Err("Téléchargement annulé par l'utilisateur".to_string());
+            }
+            // La restauration a expiré sur le serveur, elle est préparée à nouveau une seule fois
+            Err(DownloadError::NotFound) if !prepared_again => {
+                prepared_again = true;
+                artifact = prepare_restore(
+                    &network_state,
+                    &archive_name,
+                    format,
+                    &part_path,
+                    &state_path,
+                )
+                .await?;
+            }
+            Err(DownloadError::NotFound) => {
+                let _ = std::fs::remove_file(&state_path);
+                return Err("La restauration n'existe plus sur le serveur".to_string());
+            }
+            Err(DownloadError::Network(e)) => {
+                attempt += 1;
+                if attempt > DOWNLOAD_RETRIES {
+                    return Err(format!(
+                        "{} (le téléchargement reprendra au prochain essai)",
+                        e
+                    ));
+                }
+                println!(
+                    "[Téléchargement] {} - nouvelle tentative {}/{}",
+                    e, attempt, DOWNLOAD_RETRIES
+                );
+                tokio::time::sleep(std::time::Duration::from_secs(2u64.pow(attempt))).await;
+            }
+            Err(DownloadError::Fatal(e)) => return Err(e),
         }
+    }
 
-        file.write_all(&chunk)
-            .map_err(|e| format!("Impossible d'écrire les données sur le disque : {}", e))?;
+    // Vérification de la taille finale avant de livrer le fichier
+    let downloaded = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    if downloaded != artifact.size {
+        let _ = std::fs::remove_file(&part_path);
+        let _ = std::fs::remove_file(&state_path);
+        return Err(format!(
+            "Taille du fichier incorrecte : {} octets reçus pour {} attendus",
+            downloaded, artifact.size
+        ));
     }
+    std::fs::rename(&part_path, &target_path)
+        .map_err(|e| format!("Impossible de finaliser le fichier : {}", e))?;
+    let _ = std::fs::remove_file(&state_path);
 
     println!("[Téléchargement] Fichier téléchargé et sauvegardé avec succès !");
     Ok(target_path)
@@ -539,6 +936,17 @@ pub async fn check_internet_connection(state: State<'_, NetworkManager>) -> Resu
 pub async fn get_backup_logs(
     state: State<'_, NetworkManager>,
 ) -> Result<Vec<DashboardLogEntry>, String> {
+    // Les rapports structurés sont servis directement par l'API
+    let url = format!("{}/backup_reports", API_BASE);
+    match state.get_and_parse::<Vec<BackupReportSummary>>(&url).await {
+        Ok(reports) if !reports.is_empty() => {
+            return Ok(reports.into_iter().map(DashboardLogEntry::from).collect());
+        }
+        Ok(_) => {}
+        Err(e) => println!("[Système] Rapports de sauvegarde indisponibles : {}", e),
+    }
+
+    // Sauvegardes antérieures aux rapports : lecture des archives _logs
     let url = format!("{}/get_log", crate::network::API_BASE);
 
     print!("[Système] Récupération des logs de sauvegarde...\n");
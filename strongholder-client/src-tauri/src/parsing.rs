@@ -74,15 +74,38 @@ struct BorgArchiveWrapper {
 #[derive(Deserialize, Debug)]
 struct BorgArchive {
     id: String,
+    #[serde(default)]
+    start: String,
     end: String,
     duration: f64,
     stats: BorgStats,
 }
 
-#[derive(Deserialize, Debug)]
-struct BorgStats {
-    original_size: u64,
-    nfiles: u64,
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BorgStats {
+    pub original_size: u64,
+    #[serde(default)]
+    pub compressed_size: u64,
+    #[serde(default)]
+    pub deduplicated_size: u64,
+    pub nfiles: u64,
+}
+
+// --- Rapport de sauvegarde (Envoyé à l'API après chaque sauvegarde) ---
+
+#[derive(Serialize, Debug)]
+pub struct BackupReport {
+    pub archive_name: String,
+    pub archive_id: Option<String>,
+    // Code de retour de borg create : 0 succès, 1 avertissements, 2 erreur
+    pub exit_code: i32,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub duration: f64,
+    pub stats: BorgStats,
+    // Seuls les fichiers ajoutés, modifiés, supprimés ou en erreur sont transmis
+    pub files: Vec<FileStatus>,
+    pub warnings: Vec<String>,
 }
 
 // --- Logique d'analyse (Parsing) ---
@@ -107,7 +130,58 @@ pub fn parse_server_response(json_text: &str) -> Vec<LogEntry> {
     entries
 }
 
+// Construit le rapport d'une sauvegarde à partir du journal de borg create (--list --json)
+pub fn build_backup_report(archive_name: String, exit_code: i32, raw_text: &str) -> BackupReport {
+    let (wrapper, files) = split_log_block(raw_text);
+    let files = files
+        .into_iter()
+        .filter(|f| matches!(f.status, 'A' | 'M' | 'D' | 'E'))
+        .collect();
+
+    // Tout ce qui n'est ni le JSON final ni une ligne de fichier est un message de borg
+    let json_bounds = raw_text.find('{').zip(raw_text.rfind('}'));
+    let mut warnings = Vec::new();
+    let mut offset = 0;
+    for line in raw_text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if json_bounds.is_some_and(|(start, end)| line_start <= end && offset > start) {
+            continue;
+        }
+        let trimmed = line.trim();
+        let bytes = trimmed.as_bytes();
+        let is_file_line = bytes.len() > 2 && bytes[1] == b' ';
+        if !trimmed.is_empty() && !is_file_line {
+            warnings.push(trimmed.to_string());
+        }
+    }
+
+    let archive = wrapper.map(|w| w.archive);
+    BackupReport {
+        archive_name,
+        archive_id: archive.as_ref().map(|a| a.id.clone()),
+        exit_code,
+        start_time: archive
+            .as_ref()
+            .map(|a| a.start.clone())
+            .filter(|start| !start.is_empty()),
+        end_time: archive.as_ref().map(|a| a.end.clone()),
+        duration: archive.as_ref().map(|a| a.duration).unwrap_or(0.0),
+        stats: archive.map(|a| a.stats).unwrap_or_default(),
+        files,
+        warnings,
+    }
+}
+
 fn parse_mixed_block(raw_text: &str) -> Option<LogEntry> {
+    let (wrapper, files) = split_log_block(raw_text);
+
+    // Si les données de l'archive ont été extraites avec succès, on assemble et on renvoie l'entrée formatée
+    wrapper.map(|w| format_log_entry(w.archive, files))
+}
+
+// Sépare le JSON de fin de sauvegarde et les lignes de fichiers d'un journal borg
+fn split_log_block(raw_text: &str) -> (Option<BorgArchiveWrapper>, Vec<FileStatus>) {
     let mut files: Vec<FileStatus> = Vec::new();
     let mut wrapper: Option<BorgArchiveWrapper> = None;
 
@@ -149,8 +223,7 @@ fn parse_mixed_block(raw_text: &str) -> Option<LogEntry> {
         }
     }
 
-    // Si les données de l'archive ont été extraites avec succès, on assemble et on renvoie l'entrée formatée
-    wrapper.map(|w| format_log_entry(w.archive, files))
+    (wrapper, files)
 }
 
 fn format_log_entry(archive: BorgArchive, files: Vec<FileStatus>) -> LogEntry {
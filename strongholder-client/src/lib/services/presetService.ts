@@ -225,7 +225,8 @@ export async function syncPresetScheduleToSystem(preset: BackupPreset): Promise<
             cronString: cronStr,
             presetPath: fullPath,
             clientId,
-            enabled: isEnabled
+            enabled: isEnabled,
+            deviceName: localStorage.getItem('device_name')
         });
     } catch (e) {
         throw new Error('SCHEDULE_SYNC_FAILED', { cause: e });
@@ -5,6 +5,7 @@ import { invoke } from '@tauri-apps/api/core';
 export interface ArchiveItem {
     archive: string;
     time: string;
+    device: string | null;
 }
 
 export interface ArchiveFileRaw {
@@ -135,7 +135,8 @@ export async function executeBackup(
         await invoke('run_backup_script', {
             clientId,
             presetPath,
-            username
+            username,
+            deviceName: localStorage.getItem('device_name')
         });
     } catch (e: unknown) {
         if (isBackupCancelled) {